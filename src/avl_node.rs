@@ -1,5 +1,6 @@
-use std::ptr;
-use std::cmp::max;
+use core::ptr;
+use core::cmp::max;
+use core::cmp::Ordering;
 
 pub struct AVLNode {
     pub left: AVLNodePtr,
@@ -247,7 +248,7 @@ impl AVLNodePtrBase for *mut AVLNode {
     }
 
     fn check_valid(self) -> bool {
-        use std::cmp;
+        use core::cmp;
         if self.is_null() {
             return true;
         }
@@ -500,6 +501,146 @@ pub unsafe fn avl_node_tear(root: &mut AVLRoot, next: *mut AVLNodePtr) -> AVLNod
     node
 }
 
+/// Join the tree `left`, the node `mid` and the tree `right` into one tree, where all nodes of
+/// `left` are before `mid` and all nodes of `right` are after `mid`. Both `left` and `right` are
+/// left empty.
+///
+/// O(|height(left) - height(right)| + 1) time complexity.
+pub unsafe fn avl_join(left: AVLRootPtr, mid: AVLNodePtr, right: AVLRootPtr) -> AVLRoot {
+    let (left_node, right_node) = ((*left).node, (*right).node);
+    (*left).node = ptr::null_mut();
+    (*right).node = ptr::null_mut();
+    let (hl, hr) = (left_node.height(), right_node.height());
+    let mut root = AVLRoot::default();
+    if hl > hr + 1 {
+        // attach `mid` to the right spine of `left`, where the heights fit
+        let mut node = left_node;
+        while node.height() > hr + 1 {
+            node = node.right();
+        }
+        let parent = if node.is_null() {
+            left_node.last_node()
+        } else {
+            node.parent()
+        };
+        join_node(mid, node, right_node, parent);
+        parent.set_right(mid);
+        root.node = left_node;
+        rebalance_node(parent, &mut root);
+    } else if hr > hl + 1 {
+        let mut node = right_node;
+        while node.height() > hl + 1 {
+            node = node.left();
+        }
+        let parent = if node.is_null() {
+            right_node.first_node()
+        } else {
+            node.parent()
+        };
+        join_node(mid, left_node, node, parent);
+        parent.set_left(mid);
+        root.node = right_node;
+        rebalance_node(parent, &mut root);
+    } else {
+        join_node(mid, left_node, right_node, ptr::null_mut());
+        root.node = mid;
+    }
+    root
+}
+
+/// Split the tree `root` into the nodes before the pivot, the node equal to the pivot (or null)
+/// and the nodes after the pivot. `pivot_cmp` returns the ordering of a node relative to the
+/// pivot. `pivot_cmp` is called once for each node on the search path of the pivot, from the
+/// root down.
+///
+/// O(log n) time complexity.
+pub unsafe fn avl_split(
+    root: AVLNodePtr,
+    mut pivot_cmp: impl FnMut(AVLNodePtr) -> Ordering,
+) -> (AVLRoot, AVLNodePtr, AVLRoot) {
+    recursive_split(root, &mut pivot_cmp)
+}
+
+unsafe fn recursive_split<F>(node: AVLNodePtr, pivot_cmp: &mut F) -> (AVLRoot, AVLNodePtr, AVLRoot)
+where
+    F: FnMut(AVLNodePtr) -> Ordering,
+{
+    if node.is_null() {
+        return (AVLRoot::default(), ptr::null_mut(), AVLRoot::default());
+    }
+    let mut left = AVLRoot { node: node.left() };
+    let mut right = AVLRoot { node: node.right() };
+    if left.node.not_null() {
+        left.node.set_parent(ptr::null_mut());
+    }
+    if right.node.not_null() {
+        right.node.set_parent(ptr::null_mut());
+    }
+    match pivot_cmp(node) {
+        Ordering::Equal => {
+            node.reset(ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), 1);
+            (left, node, right)
+        }
+        Ordering::Less => {
+            let (mut less, mid, greater) = recursive_split(right.node, pivot_cmp);
+            (avl_join(&mut left, node, &mut less), mid, greater)
+        }
+        Ordering::Greater => {
+            let (less, mid, mut greater) = recursive_split(left.node, pivot_cmp);
+            (less, mid, avl_join(&mut greater, node, &mut right))
+        }
+    }
+}
+
+#[inline]
+unsafe fn join_node(node: AVLNodePtr, left: AVLNodePtr, right: AVLNodePtr, parent: AVLNodePtr) {
+    node.reset(left, right, parent, 0);
+    node.height_update();
+    if left.not_null() {
+        left.set_parent(node);
+    }
+    if right.not_null() {
+        right.set_parent(node);
+    }
+}
+
+/// Visit every node of the tree in post-order and leave the tree empty. Nodes are detached
+/// before being passed to `f`, so `f` may free them.
+///
+/// Nodes are torn off one by one, which uses O(1) extra space whatever the tree shape is.
+pub unsafe fn avl_for_each_post_order(root: &mut AVLRoot, f: &mut dyn FnMut(AVLNodePtr)) {
+    let mut next = ptr::null_mut();
+    loop {
+        let node = avl_node_tear(root, &mut next);
+        if node.is_null() {
+            break;
+        }
+        f(node);
+    }
+}
+
+/// Build a perfectly balanced tree from `nodes`, which must be sorted, and return its root.
+/// O(n) time complexity.
+pub unsafe fn avl_build_from_sorted(nodes: &mut [AVLNodePtr]) -> AVLRoot {
+    AVLRoot {
+        node: recursive_build_from_sorted(nodes, ptr::null_mut()),
+    }
+}
+
+unsafe fn recursive_build_from_sorted(nodes: &mut [AVLNodePtr], parent: AVLNodePtr) -> AVLNodePtr {
+    if nodes.is_empty() {
+        return ptr::null_mut();
+    }
+    let mid = nodes.len() / 2;
+    let node = nodes[mid];
+    let (left, right) = nodes.split_at_mut(mid);
+    node.set_parent(parent);
+    node.set_left(recursive_build_from_sorted(left, node));
+    node.set_right(recursive_build_from_sorted(&mut right[1..], node));
+    node.height_update();
+    node
+}
+
 /// convert AVL to list
 /// left become prev
 /// right become next
@@ -517,3 +658,194 @@ pub unsafe fn avl_tree_convert_to_list(root: &mut AVLRoot) -> AVLNodePtr {
     }
     head
 }
+
+#[cfg(test)]
+mod test {
+    use avl_node;
+    use avl_node::{AVLNode, AVLNodePtr, AVLNodePtrBase, AVLRoot};
+    use std::ptr;
+
+    struct Item {
+        node: AVLNode,
+        key: usize,
+    }
+
+    fn key_of(node: AVLNodePtr) -> usize {
+        unsafe { (*container_of!(node, Item, node)).key }
+    }
+
+    fn new_items(keys: ::std::ops::Range<usize>) -> Vec<Box<Item>> {
+        keys.map(|key| {
+            Box::new(Item {
+                node: AVLNode::default(),
+                key,
+            })
+        }).collect()
+    }
+
+    unsafe fn insert(root: &mut AVLRoot, item: &mut Item) {
+        let node = &mut item.node as AVLNodePtr;
+        let mut parent = ptr::null_mut();
+        let mut link = &mut root.node as *mut AVLNodePtr;
+        while (*link).not_null() {
+            parent = *link;
+            link = if item.key < key_of(parent) {
+                parent.left_mut()
+            } else {
+                parent.right_mut()
+            };
+        }
+        avl_node::link_node(node, parent, link);
+        avl_node::node_post_insert(node, root);
+    }
+
+    fn build(items: &mut [Box<Item>]) -> AVLRoot {
+        let mut root = AVLRoot::default();
+        for item in items.iter_mut() {
+            unsafe { insert(&mut root, item) };
+        }
+        root
+    }
+
+    fn check_parent(node: AVLNodePtr) -> bool {
+        if node.is_null() {
+            return true;
+        }
+        (node.left().is_null() || node.left().parent() == node)
+            && (node.right().is_null() || node.right().parent() == node)
+            && check_parent(node.left()) && check_parent(node.right())
+    }
+
+    fn in_order_keys(root: &AVLRoot) -> Vec<usize> {
+        let mut keys = vec![];
+        let mut node = root.node.first_node();
+        while node.not_null() {
+            keys.push(key_of(node));
+            node = node.next();
+        }
+        keys
+    }
+
+    #[test]
+    fn test_avl_join() {
+        let mut max_diff = 0;
+        for left_cnt in 0..40 {
+            for right_cnt in 0..40 {
+                let mut left_items = new_items(0..left_cnt);
+                let mut mid = new_items(left_cnt..left_cnt + 1);
+                let mut right_items = new_items(left_cnt + 1..left_cnt + 1 + right_cnt);
+                let mut left = build(&mut left_items);
+                let mut right = build(&mut right_items);
+                let diff = (left.node.height() - right.node.height()).abs();
+                max_diff = ::std::cmp::max(max_diff, diff);
+                let root = unsafe {
+                    avl_node::avl_join(&mut left, &mut mid[0].node, &mut right)
+                };
+                assert!(left.node.is_null());
+                assert!(right.node.is_null());
+                assert!(root.node.parent().is_null());
+                assert!(root.node.check_valid());
+                assert!(check_parent(root.node));
+                assert_eq!(in_order_keys(&root), (0..left_cnt + 1 + right_cnt).collect::<Vec<_>>());
+            }
+        }
+        assert!(max_diff >= 3);
+    }
+
+    #[test]
+    fn test_avl_for_each_post_order() {
+        let mut items = new_items(0..1000);
+        let mut root = build(&mut items);
+        let mut visited = vec![];
+        unsafe {
+            avl_node::avl_for_each_post_order(&mut root, &mut |node| {
+                assert!(node.left().is_null() && node.right().is_null());
+                visited.push(key_of(node));
+            })
+        };
+        assert!(root.node.is_null());
+        visited.sort();
+        assert_eq!(visited, (0..1000).collect::<Vec<_>>());
+
+        // a degenerate tree, which is too deep to be destroyed recursively
+        let cnt = 1000000;
+        let mut items = new_items(0..cnt);
+        let mut root = AVLRoot::default();
+        let mut parent: AVLNodePtr = ptr::null_mut();
+        for item in items.iter_mut() {
+            let node = &mut item.node as AVLNodePtr;
+            node.reset(ptr::null_mut(), ptr::null_mut(), parent, 0);
+            if parent.is_null() {
+                root.node = node;
+            } else {
+                parent.set_right(node);
+            }
+            parent = node;
+        }
+        let mut visited = 0;
+        unsafe { avl_node::avl_for_each_post_order(&mut root, &mut |_| visited += 1) };
+        assert_eq!(visited, cnt);
+        assert!(root.node.is_null());
+    }
+
+    #[test]
+    fn test_avl_build_from_sorted() {
+        for &cnt in &[0, 1, 2, 3, 7, 8, 1000, 4095, 4096] {
+            let mut items = new_items(0..cnt);
+            let mut nodes: Vec<_> = items
+                .iter_mut()
+                .map(|item| &mut item.node as AVLNodePtr)
+                .collect();
+            let root = unsafe { avl_node::avl_build_from_sorted(&mut nodes) };
+            assert!(root.node.check_valid());
+            assert!(check_parent(root.node));
+            assert!(root.node.is_null() || root.node.parent().is_null());
+            assert_eq!(in_order_keys(&root), (0..cnt).collect::<Vec<_>>());
+            // minimal height is ceil(log2(cnt + 1))
+            let mut height = 0;
+            while (1usize << height) < cnt + 1 {
+                height += 1;
+            }
+            assert_eq!(root.node.height(), height);
+        }
+    }
+
+    #[test]
+    fn test_avl_split() {
+        let cnt = 1000;
+        let mut items = new_items(0..cnt);
+        for &pivot in &[0, 1, 2, 100, 499, 500, 777, 998, 999, 1000, 5000] {
+            let mut root = build(&mut items);
+            let (left, mid, right) = unsafe {
+                avl_node::avl_split(root.node, |node| key_of(node).cmp(&pivot))
+            };
+            root.node = ptr::null_mut();
+            for half in &[&left, &right] {
+                assert!(half.node.is_null() || half.node.parent().is_null());
+                assert!(half.node.check_valid());
+                assert!(check_parent(half.node));
+            }
+            assert_eq!(in_order_keys(&left), (0..pivot.min(cnt)).collect::<Vec<_>>());
+            assert_eq!(in_order_keys(&right), (pivot + 1..cnt).collect::<Vec<_>>());
+            if pivot < cnt {
+                assert_eq!(key_of(mid), pivot);
+                assert!(mid.left().is_null() && mid.right().is_null());
+            } else {
+                assert!(mid.is_null());
+            }
+        }
+
+        // pivot between keys
+        let mut items = new_items(0..cnt);
+        for item in items.iter_mut() {
+            item.key *= 2;
+        }
+        let root = build(&mut items);
+        let (left, mid, right) =
+            unsafe { avl_node::avl_split(root.node, |node| key_of(node).cmp(&601)) };
+        assert!(mid.is_null());
+        assert_eq!(in_order_keys(&left), (0..301).map(|i| i * 2).collect::<Vec<_>>());
+        assert_eq!(in_order_keys(&right), (301..cnt).map(|i| i * 2).collect::<Vec<_>>());
+        assert!(left.node.check_valid() && right.node.check_valid());
+    }
+}
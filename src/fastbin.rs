@@ -1,5 +1,6 @@
-use libc::{c_void, free, malloc};
-use std::{cmp, mem};
+use alloc::alloc::{self, Layout};
+use core::{cmp, mem};
+use alloc::vec::Vec;
 
 pub type VoidPtr = *mut u8;
 
@@ -11,81 +12,411 @@ const MAXIMUM_PAGE_SIZE: usize = 1usize << 16;
 /// Default object num in one page
 const PAGE_OBJ_CNT: usize = 1usize << 5;
 
-pub struct Fastbin {
+/// Default factor by which the page size grows
+const PAGE_GROWTH: usize = 2;
+
+
+/// Size of page header: pointer to next page and size of current page.
+const PAGE_HEADER_SIZE: usize = mem::size_of::<VoidPtr>() + mem::size_of::<usize>();
+
+/// An allocator which `Fastbin` requests pages from.
+///
+/// # Safety
+///
+/// Implementors must return either null or memory that is valid for reads and writes of
+/// `layout`, and keep it valid until it is passed to `dealloc`.
+///
+/// Memory allocated by an allocator may be deallocated by any of its clones. Allocators created
+/// separately, e.g. by two calls to a constructor, need not share memory, so a page must never be
+/// freed by an allocator which is not a clone of the one it came from, unless `is_interchangeable`
+/// says so. `is_interchangeable` must only return `true` if either allocator is able to free the
+/// memory of the other.
+pub unsafe trait Alloc: Clone {
+    /// Allocate memory as described by `layout`. Return null if allocation fails.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must have a non-zero size.
+    unsafe fn alloc(&self, layout: Layout) -> VoidPtr;
+
+    /// Deallocate the memory referenced by `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be allocated by this allocator, or by a clone of it, with the same `layout`,
+    /// and must not be used afterwards.
+    unsafe fn dealloc(&self, ptr: VoidPtr, layout: Layout);
+
+    /// Return whether memory allocated by `self` may be deallocated by `other`, and the other way
+    /// around. Maps built on interchangeable allocators hand whole pages over to each other
+    /// instead of moving the entries.
+    ///
+    /// There is no telling clones from separately created allocators in general, so the default
+    /// is `false`.
+    #[inline]
+    fn is_interchangeable(&self, _other: &Self) -> bool {
+        false
+    }
+}
+
+/// The global memory allocator, which is the default allocator of `Fastbin`.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Global;
+
+unsafe impl Alloc for Global {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> VoidPtr {
+        alloc::alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: VoidPtr, layout: Layout) {
+        alloc::dealloc(ptr, layout)
+    }
+
+    #[inline]
+    fn is_interchangeable(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+pub struct Fastbin<A = Global>
+where
+    A: Alloc,
+{
     obj_size: usize,
     page_size: usize,
     align: usize,
     maximum: usize,
+    growth: usize,
     start: VoidPtr,
     end: VoidPtr,
     next: VoidPtr,
     pages: VoidPtr,
+    allocator: A,
 }
 
-impl Default for Fastbin {
+impl<A> Default for Fastbin<A>
+where
+    A: Alloc + Default,
+{
     fn default() -> Self {
-        let mut fastbin = unsafe { mem::uninitialized::<Fastbin>() };
-        fastbin.reset();
-        fastbin
+        Fastbin::new_lazy_in(0, 0, A::default())
     }
 }
 
 impl Fastbin {
     #[inline]
-    pub fn new(obj_size: usize) -> Self {
-        Fastbin::new_with_parameter(obj_size, PAGE_OBJ_CNT, MAXIMUM_PAGE_SIZE)
+    pub fn new(obj_size: usize, align: usize) -> Self {
+        Fastbin::new_in(obj_size, align, Global)
+    }
+
+    /// Create a `Fastbin` in an uninitialized state, which will be initialized with default
+    /// parameters when the first object is allocated.
+    #[inline]
+    pub const fn new_lazy(obj_size: usize, align: usize) -> Self {
+        Fastbin {
+            obj_size,
+            page_size: 0,
+            align,
+            maximum: MAXIMUM_PAGE_SIZE,
+            growth: PAGE_GROWTH,
+            start: VOID_PTR_NULL,
+            end: VOID_PTR_NULL,
+            next: VOID_PTR_NULL,
+            pages: VOID_PTR_NULL,
+            allocator: Global,
+        }
+    }
+
+    #[inline]
+    pub fn new_with_parameter(
+        obj_size: usize,
+        align: usize,
+        page_obj_cnt: usize,
+        maximum: usize,
+        growth: usize,
+    ) -> Self {
+        Fastbin::new_with_parameter_in(obj_size, align, page_obj_cnt, maximum, growth, Global)
+    }
+}
+
+impl<A> Fastbin<A>
+where
+    A: Alloc,
+{
+    /// Create a `Fastbin` which allocates pages from `allocator`.
+    #[inline]
+    pub fn new_in(obj_size: usize, align: usize, allocator: A) -> Self {
+        Fastbin::new_with_parameter_in(
+            obj_size,
+            align,
+            PAGE_OBJ_CNT,
+            MAXIMUM_PAGE_SIZE,
+            PAGE_GROWTH,
+            allocator,
+        )
     }
 
-    pub fn new_with_parameter(obj_size: usize, page_obj_cnt: usize, maximum: usize) -> Self {
-        let mut fastbin = Default::default();
-        (&mut fastbin as FastbinPtr).fastbin_init(
+    /// Same as `new_lazy`, but allocates pages from `allocator`.
+    #[inline]
+    pub fn new_lazy_in(obj_size: usize, align: usize, allocator: A) -> Self {
+        Fastbin {
+            obj_size,
+            page_size: 0,
+            align,
+            maximum: MAXIMUM_PAGE_SIZE,
+            growth: PAGE_GROWTH,
+            start: VOID_PTR_NULL,
+            end: VOID_PTR_NULL,
+            next: VOID_PTR_NULL,
+            pages: VOID_PTR_NULL,
+            allocator,
+        }
+    }
+
+    /// Objects are aligned to `align`, which must be a power of two, and at least to the
+    /// alignment of a pointer.
+    ///
+    /// Each new page is `growth` times as large as the previous one until `maximum` is reached.
+    /// A `growth` of 1 keeps every page at the same size.
+    pub fn new_with_parameter_in(
+        obj_size: usize,
+        align: usize,
+        page_obj_cnt: usize,
+        maximum: usize,
+        growth: usize,
+        allocator: A,
+    ) -> Self {
+        assert!(growth >= 1, "growth factor must be at least 1");
+        let mut fastbin = Fastbin::new_lazy_in(obj_size, align, allocator);
+        (&mut fastbin as FastbinPtr<A>).fastbin_init(
             cmp::max(mem::size_of::<VoidPtr>(), obj_size),
+            align,
             page_obj_cnt,
             maximum,
+            growth,
         );
         fastbin
     }
 
+    /// Return a reference to the underlying allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+
+    /// Return the total size in bytes of all pages allocated by this `Fastbin`.
+    pub fn allocated_bytes(&self) -> usize {
+        let mut bytes = 0;
+        let mut page = self.pages;
+        while !page.is_null() {
+            bytes += get_page_size(page);
+            page = get_page_next(page);
+        }
+        bytes
+    }
+
+    /// Return the number of deleted objects kept for reuse.
+    pub fn free_list_len(&self) -> usize {
+        let mut len = 0;
+        let mut obj = self.next;
+        while !obj.is_null() {
+            len += 1;
+            obj = get_page_next(obj);
+        }
+        len
+    }
+
+    /// Release the pages whose objects have all been deleted back to the allocator.
+    ///
+    /// Page usage is computed from the recycled list here, so `alloc` and `del` are not slowed
+    /// down by bookkeeping. O(n log n) time complexity, where n is the number of recycled
+    /// objects and pages.
+    pub fn shrink(&mut self) {
+        struct PageStat {
+            page: VoidPtr,
+            first: usize,
+            carved: usize,
+            free: usize,
+        }
+
+        if self.pages.is_null() {
+            return;
+        }
+        let align = self.align;
+        let first_obj = |page: VoidPtr| round_up_to_next(page as usize + PAGE_HEADER_SIZE, align);
+        let mut stats = Vec::new();
+        let mut page = self.pages;
+        while !page.is_null() {
+            let first = first_obj(page);
+            let page_end = page as usize + get_page_size(page);
+            // only the page `start` points into is partly carved; after its release by an
+            // earlier `shrink`, `start` is null and the head page is a full one
+            let start = self.start as usize;
+            let end = if start >= first && start <= page_end {
+                start
+            } else {
+                page_end
+            };
+            stats.push(PageStat {
+                page,
+                first,
+                carved: (end - first) / self.obj_size,
+                free: 0,
+            });
+            page = get_page_next(page);
+        }
+        stats.sort_by_key(|stat| stat.first);
+        let page_of = |stats: &Vec<PageStat>, obj: VoidPtr| {
+            stats
+                .binary_search_by_key(&(obj as usize), |stat| stat.first)
+                .unwrap_or_else(|pos| pos - 1)
+        };
+
+        let mut obj = self.next;
+        while !obj.is_null() {
+            let pos = page_of(&stats, obj);
+            stats[pos].free += 1;
+            obj = get_page_next(obj);
+        }
+        if stats.iter().all(|stat| stat.free < stat.carved) {
+            return;
+        }
+
+        // remove objects of released pages from the recycled list
+        let mut obj = self.next;
+        let mut prev = VOID_PTR_NULL;
+        while !obj.is_null() {
+            let next = get_page_next(obj);
+            let stat = &stats[page_of(&stats, obj)];
+            if stat.free == stat.carved {
+                if prev.is_null() {
+                    self.next = next;
+                } else {
+                    set_page_next(prev, next);
+                }
+            } else {
+                prev = obj;
+            }
+            obj = next;
+        }
+
+        let current = self.pages;
+        let mut page = self.pages;
+        let mut prev = VOID_PTR_NULL;
+        while !page.is_null() {
+            let next = get_page_next(page);
+            let stat = &stats[page_of(&stats, first_obj(page) as VoidPtr)];
+            if stat.free == stat.carved {
+                if prev.is_null() {
+                    self.pages = next;
+                } else {
+                    set_page_next(prev, next);
+                }
+                if page == current {
+                    self.start = VOID_PTR_NULL;
+                    self.end = VOID_PTR_NULL;
+                }
+                unsafe {
+                    let layout = (self as FastbinPtr<A>).page_layout(get_page_size(page));
+                    self.allocator.dealloc(page, layout);
+                }
+            } else {
+                prev = page;
+            }
+            page = next;
+        }
+    }
+
+    /// Allocate pages in advance so that the next `count` calls to `alloc` are served without
+    /// asking the allocator for memory.
+    pub fn reserve(&mut self, count: usize) {
+        unsafe { (self as FastbinPtr<A>).fastbin_reserve(count) }
+    }
+
     #[inline]
     pub fn del(&self, ptr: VoidPtr) {
-        (self as *const _ as FastbinPtr).fastbin_del(ptr);
+        (self as *const _ as FastbinPtr<A>).fastbin_del(ptr);
     }
 
     #[inline]
     pub fn alloc(&mut self) -> VoidPtr {
-        unsafe { (self as FastbinPtr).fastbin_new() }
+        unsafe { (self as FastbinPtr<A>).fastbin_new() }
     }
 
     #[inline]
     fn destroy(&mut self) {
-        (self as FastbinPtr).fastbin_destroy();
+        (self as FastbinPtr<A>).fastbin_destroy();
     }
 
-    fn reset(&mut self) {
-        self.obj_size = 0;
-        self.page_size = 0;
-        self.align = 0;
-        self.maximum = MAXIMUM_PAGE_SIZE;
-        self.start = VOID_PTR_NULL;
-        self.end = VOID_PTR_NULL;
-        self.next = VOID_PTR_NULL;
-        self.pages = VOID_PTR_NULL;
+    /// Take over all pages of `other`, which must serve objects of the same size and alignment,
+    /// and whose allocator must be interchangeable with the one of `self`.
+    ///
+    /// Objects allocated from `other` stay valid and are deleted into `self` from now on, and
+    /// the pages are released by the allocator of `self`. `other` is left without pages.
+    pub fn absorb(&mut self, other: &mut Fastbin<A>) {
+        if other.pages.is_null() {
+            return;
+        }
+        if self.page_size == 0 {
+            self.obj_size = other.obj_size;
+            self.align = other.align;
+            self.page_size = other.page_size;
+        }
+        debug_assert_eq!(self.obj_size, other.obj_size);
+        debug_assert_eq!(self.align, other.align);
+        if self.pages.is_null() {
+            self.pages = other.pages;
+            self.start = other.start;
+            self.end = other.end;
+        } else {
+            // `start` and `end` point into the first page of `self`, so it stays at the head,
+            // and the rest of the current page of `other` goes to the recycled list
+            let obj_size = other.obj_size as isize;
+            while unsafe { other.start.offset(obj_size) } <= other.end {
+                let obj = other.start;
+                other.start = unsafe { obj.offset(obj_size) };
+                other.del(obj);
+            }
+            let mut tail = other.pages;
+            while !get_page_next(tail).is_null() {
+                tail = get_page_next(tail);
+            }
+            set_page_next(tail, get_page_next(self.pages));
+            set_page_next(self.pages, other.pages);
+        }
+        if !other.next.is_null() {
+            let mut tail = other.next;
+            while !get_page_next(tail).is_null() {
+                tail = get_page_next(tail);
+            }
+            set_page_next(tail, self.next);
+            self.next = other.next;
+        }
+        other.start = VOID_PTR_NULL;
+        other.end = VOID_PTR_NULL;
+        other.next = VOID_PTR_NULL;
+        other.pages = VOID_PTR_NULL;
     }
 
     pub fn move_to(&mut self) -> Self {
-        let mut fastbin = Fastbin::default();
+        let mut fastbin = Fastbin::new_lazy_in(self.obj_size, self.align, self.allocator.clone());
         mem::swap(&mut fastbin, self);
         fastbin
     }
 }
 
-impl Drop for Fastbin {
+impl<A> Drop for Fastbin<A>
+where
+    A: Alloc,
+{
     fn drop(&mut self) {
         self.destroy();
     }
 }
 
-pub type FastbinPtr = *mut Fastbin;
+pub type FastbinPtr<A = Global> = *mut Fastbin<A>;
 
 #[inline]
 fn get_page_next(ptr: VoidPtr) -> VoidPtr {
@@ -97,6 +428,16 @@ fn set_page_next(ptr: VoidPtr, data: VoidPtr) {
     unsafe { *(ptr as *mut VoidPtr) = data }
 }
 
+#[inline]
+fn get_page_size(page: VoidPtr) -> usize {
+    unsafe { *(page.offset(mem::size_of::<VoidPtr>() as isize) as *mut usize) }
+}
+
+#[inline]
+fn set_page_size(page: VoidPtr, size: usize) {
+    unsafe { *(page.offset(mem::size_of::<VoidPtr>() as isize) as *mut usize) = size }
+}
+
 trait FastbinPtrBase {
     fn start(self) -> VoidPtr;
     fn set_start(self, start: VoidPtr);
@@ -112,29 +453,52 @@ trait FastbinPtrBase {
     fn set_page_size(self, page_size: usize);
     fn maximum(self) -> usize;
     fn set_maximum(self, maximum: usize);
+    fn growth(self) -> usize;
+    fn set_growth(self, growth: usize);
     fn align(self) -> usize;
     fn set_align(self, align: usize);
+    fn page_layout(self, page_size: usize) -> Layout;
 }
 
 pub trait FastbinPtrOperation {
-    fn fastbin_init(self, obj_size: usize, page_obj_cnt: usize, maximum: usize);
+    fn fastbin_init(
+        self,
+        obj_size: usize,
+        align: usize,
+        page_obj_cnt: usize,
+        maximum: usize,
+        growth: usize,
+    );
     fn fastbin_destroy(self);
     unsafe fn fastbin_new(self) -> VoidPtr;
     fn fastbin_del(self, ptr: VoidPtr);
+    unsafe fn fastbin_new_page(self);
+    unsafe fn fastbin_reserve(self, count: usize);
 }
 
-impl FastbinPtrOperation for *mut Fastbin {
+impl<A> FastbinPtrOperation for *mut Fastbin<A>
+where
+    A: Alloc,
+{
     #[inline]
-    fn fastbin_init(self, obj_size: usize, page_obj_cnt: usize, maximum: usize) {
-        let align = mem::align_of::<VoidPtr>();
+    fn fastbin_init(
+        self,
+        obj_size: usize,
+        align: usize,
+        page_obj_cnt: usize,
+        maximum: usize,
+        growth: usize,
+    ) {
+        let align = cmp::max(align, mem::align_of::<VoidPtr>());
+        debug_assert!(align.is_power_of_two());
         self.set_maximum(maximum);
+        self.set_growth(growth);
         self.set_start(VOID_PTR_NULL);
         self.set_end(VOID_PTR_NULL);
         self.set_next(VOID_PTR_NULL);
         self.set_pages(VOID_PTR_NULL);
         self.set_obj_size(round_up_to_next(obj_size, align));
-        let mut need =
-            self.obj_size() * page_obj_cnt + mem::size_of::<VoidPtr>() + mem::size_of::<usize>();
+        let mut need = self.obj_size() * page_obj_cnt + round_up_to_next(PAGE_HEADER_SIZE, align);
         need = round_up_to_next(need, align);
         self.set_page_size(1usize << 5);
         while self.page_size() < need {
@@ -151,7 +515,9 @@ impl FastbinPtrOperation for *mut Fastbin {
             let next = get_page_next(page);
             self.set_pages(next);
             unsafe {
-                free(page as *mut c_void);
+                (*self)
+                    .allocator
+                    .dealloc(page, self.page_layout(get_page_size(page)));
             }
         }
         self.set_start(VOID_PTR_NULL);
@@ -169,20 +535,17 @@ impl FastbinPtrOperation for *mut Fastbin {
             return obj;
         }
         if self.start().offset(obj_size) > self.end() {
-            let page = malloc(self.page_size()) as VoidPtr;
-            if page.is_null() {
-                panic!("memory overflow");
-            }
-            let mut line_ptr = page;
-            set_page_next(page, self.pages());
-            self.set_pages(page);
-            line_ptr = round_up_to_next(line_ptr as usize + mem::size_of::<VoidPtr>(), self.align())
-                as VoidPtr;
-            self.set_start(line_ptr);
-            self.set_end(page.offset(self.page_size() as isize));
-            if self.page_size() < self.maximum() {
-                self.set_page_size(self.page_size() * 2);
+            if self.page_size() == 0 {
+                self.fastbin_init(
+                    cmp::max(mem::size_of::<VoidPtr>(), self.obj_size()),
+                    self.align(),
+                    PAGE_OBJ_CNT,
+                    self.maximum(),
+                    self.growth(),
+                );
+                return self.fastbin_new();
             }
+            self.fastbin_new_page();
         }
         obj = self.start();
         self.set_start(self.start().offset(obj_size));
@@ -195,9 +558,62 @@ impl FastbinPtrOperation for *mut Fastbin {
         set_page_next(ptr, self.next());
         self.set_next(ptr);
     }
+
+    unsafe fn fastbin_new_page(self) {
+        let page = (*self)
+            .allocator
+            .alloc(self.page_layout(self.page_size()));
+        if page.is_null() {
+            panic!("memory overflow");
+        }
+        let mut line_ptr = page;
+        set_page_next(page, self.pages());
+        set_page_size(page, self.page_size());
+        self.set_pages(page);
+        line_ptr = round_up_to_next(line_ptr as usize + PAGE_HEADER_SIZE, self.align()) as VoidPtr;
+        self.set_start(line_ptr);
+        self.set_end(page.offset(self.page_size() as isize));
+        if self.page_size() < self.maximum() {
+            let page_size = self.page_size().saturating_mul(self.growth());
+            self.set_page_size(cmp::min(page_size, self.maximum()));
+        }
+    }
+
+    unsafe fn fastbin_reserve(self, count: usize) {
+        if self.page_size() == 0 {
+            self.fastbin_init(
+                cmp::max(mem::size_of::<VoidPtr>(), self.obj_size()),
+                self.align(),
+                PAGE_OBJ_CNT,
+                self.maximum(),
+                self.growth(),
+            );
+        }
+        let obj_size = self.obj_size();
+        let mut available = 0;
+        let mut obj = self.next();
+        while !obj.is_null() && available < count {
+            available += 1;
+            obj = get_page_next(obj);
+        }
+        available += (self.end() as usize - self.start() as usize) / obj_size;
+        while available < count {
+            // hand the rest of the current page over to the recycled list
+            while self.start().offset(obj_size as isize) <= self.end() {
+                let obj = self.start();
+                self.set_start(obj.offset(obj_size as isize));
+                self.fastbin_del(obj);
+            }
+            self.fastbin_new_page();
+            available += (self.end() as usize - self.start() as usize) / obj_size;
+        }
+    }
 }
 
-impl FastbinPtrBase for *mut Fastbin {
+impl<A> FastbinPtrBase for *mut Fastbin<A>
+where
+    A: Alloc,
+{
     #[inline]
     fn start(self) -> VoidPtr {
         unsafe { (*self).start }
@@ -268,6 +684,16 @@ impl FastbinPtrBase for *mut Fastbin {
         unsafe { (*self).maximum = maximum }
     }
 
+    #[inline]
+    fn growth(self) -> usize {
+        unsafe { (*self).growth }
+    }
+
+    #[inline]
+    fn set_growth(self, growth: usize) {
+        unsafe { (*self).growth = growth }
+    }
+
     #[inline]
     fn align(self) -> usize {
         unsafe { (*self).align }
@@ -277,6 +703,12 @@ impl FastbinPtrBase for *mut Fastbin {
     fn set_align(self, align: usize) {
         unsafe { (*self).align = align }
     }
+
+    #[inline]
+    fn page_layout(self, page_size: usize) -> Layout {
+        let align = cmp::max(self.align(), mem::align_of::<VoidPtr>());
+        Layout::from_size_align(page_size, align).expect("invalid page layout")
+    }
 }
 
 #[inline]
@@ -287,8 +719,10 @@ fn round_up_to_next(unrounded: usize, target_alignment: usize) -> usize {
 #[cfg(test)]
 mod test {
     use fastbin;
-    use fastbin::Fastbin;
-    use std::mem;
+    use fastbin::{Alloc, Fastbin, Global};
+    use std::alloc::Layout;
+    use std::cell::Cell;
+    use std::{cmp, mem};
     use fastbin::VoidPtr;
 
     #[test]
@@ -300,7 +734,7 @@ mod test {
             e: u64,
             d: u8,
         }
-        let fb = Fastbin::new(mem::size_of::<Node>());
+        let fb = Fastbin::new(mem::size_of::<Node>(), mem::align_of::<Node>());
         assert_eq!(fb.align, mem::align_of::<VoidPtr>());
         assert_eq!(fb.obj_size, 24);
         assert_eq!(fb.page_size, 1024);
@@ -312,7 +746,7 @@ mod test {
         struct Node {
             a: u8,
         }
-        let mut fb = Fastbin::new(mem::size_of::<Node>());
+        let mut fb = Fastbin::new(mem::size_of::<Node>(), mem::align_of::<Node>());
         fb.alloc() as *mut Node;
         assert!(!fb.pages.is_null());
         let page = fb.pages;
@@ -331,7 +765,7 @@ mod test {
         struct Node {
             a: u8,
         }
-        let mut fb = Fastbin::new(mem::size_of::<Node>());
+        let mut fb = Fastbin::new(mem::size_of::<Node>(), mem::align_of::<Node>());
         for _ in 0..3 {
             fb.alloc() as *mut Node;
         }
@@ -355,4 +789,307 @@ mod test {
         assert_eq!(fastbin::get_page_next(c), b);
         assert_eq!(fb.next, c);
     }
+
+    #[test]
+    fn test_fastbin_new_lazy() {
+        struct Node {
+            a: u8,
+            b: u64,
+            c: u8,
+            e: u64,
+            d: u8,
+        }
+        let mut fb = Fastbin::new_lazy(mem::size_of::<Node>(), mem::align_of::<Node>());
+        assert_eq!(fb.page_size, 0);
+        assert!(fb.pages.is_null());
+        let a = fb.alloc();
+        assert!(!a.is_null());
+        assert_eq!(fb.align, mem::align_of::<VoidPtr>());
+        assert_eq!(fb.obj_size, 24);
+        assert_eq!(fb.page_size, 1024 * 2);
+        let b = fb.alloc();
+        assert_eq!(b as usize - a as usize, 24);
+    }
+
+    #[derive(Clone)]
+    struct CountingAlloc<'a> {
+        alloc_cnt: &'a Cell<usize>,
+        dealloc_cnt: &'a Cell<usize>,
+    }
+
+    unsafe impl<'a> Alloc for CountingAlloc<'a> {
+        unsafe fn alloc(&self, layout: Layout) -> VoidPtr {
+            self.alloc_cnt.set(self.alloc_cnt.get() + 1);
+            Global.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: VoidPtr, layout: Layout) {
+            self.dealloc_cnt.set(self.dealloc_cnt.get() + 1);
+            Global.dealloc(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn test_fastbin_allocator() {
+        let (alloc_cnt, dealloc_cnt) = (Cell::new(0), Cell::new(0));
+        let allocator = CountingAlloc {
+            alloc_cnt: &alloc_cnt,
+            dealloc_cnt: &dealloc_cnt,
+        };
+        let mut fb = Fastbin::new_in(mem::size_of::<u64>(), mem::align_of::<u64>(), allocator);
+        let mut page_cnt = 0;
+        let mut page = fb.pages;
+        for _ in 0..1000 {
+            fb.alloc();
+            if fb.pages != page {
+                page = fb.pages;
+                page_cnt += 1;
+            }
+        }
+        assert_eq!(alloc_cnt.get(), page_cnt);
+        assert_eq!(dealloc_cnt.get(), 0);
+        drop(fb);
+        assert_eq!(alloc_cnt.get(), page_cnt);
+        assert_eq!(dealloc_cnt.get(), page_cnt);
+    }
+
+    #[test]
+    fn test_fastbin_statistics() {
+        let mut fb = Fastbin::new(mem::size_of::<u64>(), mem::align_of::<u64>());
+        assert_eq!(fb.allocated_bytes(), 0);
+        assert_eq!(fb.free_list_len(), 0);
+        let mut objs = vec![];
+        let mut last_bytes = 0;
+        for i in 0..1000 {
+            objs.push(fb.alloc());
+            if i % 100 == 99 {
+                assert!(fb.allocated_bytes() >= last_bytes);
+                last_bytes = fb.allocated_bytes();
+            }
+        }
+        assert!(fb.allocated_bytes() >= 1000 * fb.obj_size);
+        for (idx, obj) in objs.drain(..).enumerate().filter(|&(idx, _)| idx % 2 == 0) {
+            fb.del(obj);
+            assert_eq!(fb.free_list_len(), idx / 2 + 1);
+        }
+        assert_eq!(fb.free_list_len(), 500);
+        assert_eq!(fb.allocated_bytes(), last_bytes);
+        fb.alloc();
+        assert_eq!(fb.free_list_len(), 499);
+    }
+
+    fn page_count(fb: &Fastbin) -> usize {
+        let mut cnt = 0;
+        let mut page = fb.pages;
+        while !page.is_null() {
+            cnt += 1;
+            page = fastbin::get_page_next(page);
+        }
+        cnt
+    }
+
+    #[test]
+    fn test_fastbin_shrink() {
+        let mut fb = Fastbin::new(mem::size_of::<u64>(), mem::align_of::<u64>());
+        fb.shrink();
+        let objs: Vec<_> = (0..300).map(|_| fb.alloc()).collect();
+        let cnt = page_count(&fb);
+        assert!(cnt > 1);
+        // nothing to release while every page is in use
+        fb.del(objs[0]);
+        fb.shrink();
+        assert_eq!(page_count(&fb), cnt);
+        assert_eq!(fb.free_list_len(), 1);
+        for &obj in &objs[1..] {
+            fb.del(obj);
+        }
+        fb.shrink();
+        assert_eq!(page_count(&fb), 0);
+        assert_eq!(fb.free_list_len(), 0);
+        assert_eq!(fb.allocated_bytes(), 0);
+        let a = fb.alloc();
+        assert!(!a.is_null());
+        assert_eq!(page_count(&fb), 1);
+    }
+
+    #[test]
+    fn test_fastbin_shrink_partial() {
+        let mut fb = Fastbin::new(mem::size_of::<u64>(), mem::align_of::<u64>());
+        let objs: Vec<_> = (0..300).map(|_| fb.alloc()).collect();
+        let cnt = page_count(&fb);
+        // the first allocated page holds the first objects
+        for &obj in &objs[..100] {
+            fb.del(obj);
+        }
+        fb.shrink();
+        assert!(page_count(&fb) < cnt);
+        assert!(page_count(&fb) > 0);
+        let left = fb.free_list_len();
+        assert!(left < 100);
+        // recycled objects are still valid
+        let mut reused = vec![];
+        for _ in 0..left {
+            let obj = fb.alloc();
+            assert!(objs[..100].contains(&obj));
+            reused.push(obj);
+        }
+        assert_eq!(fb.free_list_len(), 0);
+        for &obj in objs[100..].iter().chain(reused.iter()) {
+            fb.del(obj);
+        }
+        fb.shrink();
+        assert_eq!(page_count(&fb), 0);
+    }
+
+    #[test]
+    fn test_fastbin_shrink_twice() {
+        let mut fb = Fastbin::new(mem::size_of::<u64>(), mem::align_of::<u64>());
+        let objs: Vec<_> = (0..300).map(|_| fb.alloc()).collect();
+        // the last allocated page is the one being carved, release it
+        for &obj in &objs[200..] {
+            fb.del(obj);
+        }
+        fb.shrink();
+        let (cnt, left) = (page_count(&fb), fb.free_list_len());
+        assert!(cnt > 0);
+        // the head page is a full one now
+        fb.shrink();
+        assert_eq!(page_count(&fb), cnt);
+        assert_eq!(fb.free_list_len(), left);
+        for &obj in &objs[..200] {
+            fb.del(obj);
+        }
+        fb.shrink();
+        fb.shrink();
+        assert_eq!(page_count(&fb), 0);
+        assert!(!fb.alloc().is_null());
+    }
+
+    #[test]
+    fn test_fastbin_reserve() {
+        let mut fb = Fastbin::new_lazy(mem::size_of::<u64>(), mem::align_of::<u64>());
+        fb.reserve(1000);
+        let cnt = page_count(&fb);
+        assert!(cnt > 0);
+        let objs: Vec<_> = (0..1000).map(|_| fb.alloc()).collect();
+        assert_eq!(page_count(&fb), cnt);
+        let mut sorted = objs.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 1000);
+        // recycled objects count as available
+        for &obj in &objs[..10] {
+            fb.del(obj);
+        }
+        fb.reserve(10);
+        assert_eq!(page_count(&fb), cnt);
+        for &obj in &objs[10..] {
+            fb.del(obj);
+        }
+        fb.reserve(1000);
+        assert_eq!(page_count(&fb), cnt);
+    }
+
+    #[test]
+    fn test_fastbin_growth() {
+        let mut fb = Fastbin::new_with_parameter(
+            mem::size_of::<u64>(),
+            mem::align_of::<u64>(),
+            32,
+            1usize << 16,
+            1,
+        );
+        let page_size = fb.page_size;
+        for _ in 0..1000 {
+            fb.alloc();
+        }
+        assert!(page_count(&fb) > 1);
+        let mut page = fb.pages;
+        while !page.is_null() {
+            assert_eq!(fastbin::get_page_size(page), page_size);
+            page = fastbin::get_page_next(page);
+        }
+
+        // growth stops at maximum even if it is not a power of the factor
+        let mut fb = Fastbin::new_with_parameter(8, 8, 32, 3000, 3);
+        for _ in 0..10000 {
+            fb.alloc();
+        }
+        assert_eq!(fb.page_size, 3000);
+        assert_eq!(fastbin::get_page_size(fb.pages), 3000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fastbin_growth_zero() {
+        Fastbin::new_with_parameter(8, 8, 32, 1usize << 16, 0);
+    }
+
+    #[test]
+    fn test_fastbin_align() {
+        #[repr(align(64))]
+        struct Node {
+            a: u8,
+        }
+        let mut fb = Fastbin::new(mem::size_of::<Node>(), mem::align_of::<Node>());
+        assert_eq!(fb.align, 64);
+        assert_eq!(fb.obj_size, 64);
+        let objs: Vec<_> = (0..1000).map(|_| fb.alloc()).collect();
+        for &obj in &objs {
+            assert_eq!(obj as usize % 64, 0);
+        }
+        for &obj in &objs {
+            fb.del(obj);
+        }
+        fb.shrink();
+        assert_eq!(page_count(&fb), 0);
+
+        let mut fb = Fastbin::new_lazy(mem::size_of::<u128>(), mem::align_of::<u128>());
+        for _ in 0..1000 {
+            assert_eq!(fb.alloc() as usize % mem::align_of::<u128>(), 0);
+        }
+        assert_eq!(fb.align, cmp::max(mem::align_of::<u128>(), mem::align_of::<VoidPtr>()));
+    }
+
+    #[test]
+    fn test_fastbin_absorb() {
+        let mut fb = Fastbin::new(mem::size_of::<u64>(), mem::align_of::<u64>());
+        let mut other = Fastbin::new(mem::size_of::<u64>(), mem::align_of::<u64>());
+        let mine: Vec<_> = (0..100).map(|_| fb.alloc()).collect();
+        let theirs: Vec<_> = (0..300).map(|_| other.alloc()).collect();
+        for &obj in &theirs[..10] {
+            other.del(obj);
+        }
+        let (cnt, other_cnt) = (page_count(&fb), page_count(&other));
+        let bytes = fb.allocated_bytes() + other.allocated_bytes();
+        fb.absorb(&mut other);
+        assert_eq!(page_count(&fb), cnt + other_cnt);
+        assert_eq!(fb.allocated_bytes(), bytes);
+        assert_eq!(page_count(&other), 0);
+        assert_eq!(other.free_list_len(), 0);
+        // the deleted objects and the uncarved rest of the absorbed page are recycled
+        assert!(fb.free_list_len() >= 10);
+        let reused = fb.alloc();
+        assert!(!mine.contains(&reused));
+        fb.del(reused);
+
+        for &obj in mine.iter().chain(theirs[10..].iter()) {
+            fb.del(obj);
+        }
+        fb.shrink();
+        assert_eq!(page_count(&fb), 0);
+
+        // a lazy `Fastbin` takes the pages over as they are
+        let mut lazy = Fastbin::new_lazy(mem::size_of::<u64>(), mem::align_of::<u64>());
+        let objs: Vec<_> = (0..100).map(|_| other.alloc()).collect();
+        lazy.absorb(&mut other);
+        assert_eq!(lazy.obj_size, other.obj_size);
+        let obj = lazy.alloc();
+        assert!(!objs.contains(&obj));
+        for &obj in objs.iter().chain(Some(&obj)) {
+            lazy.del(obj);
+        }
+        lazy.shrink();
+        assert_eq!(page_count(&lazy), 0);
+    }
 }
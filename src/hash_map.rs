@@ -1,19 +1,35 @@
 extern crate fnv;
 
-use fastbin::{Fastbin, VoidPtr};
+use fastbin::{Fastbin, Global, VoidPtr};
 use hash_table::{HashIndexPtrOperation, HashNode, HashNodeOperation, HashNodePtrOperation,
                  HashTable, HashUint};
 use hash_table;
-use std::hash::BuildHasher;
-use std::hash::Hash;
-use std::{mem, ptr};
+pub use hash_table::TryReserveError;
+use alloc::alloc;
+use core::hash::BuildHasher;
+use core::hash::{Hash, Hasher};
+use core::{marker, mem, ptr};
 use avl_node::{AVLNodePtr, AVLNodePtrBase};
 use list::ListHeadPtrFn;
 use avl_node;
-use std::ops::Index;
-use std::borrow::Borrow;
-use std::iter::FromIterator;
+use core::ops::Index;
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::borrow::ToOwned;
+use fastbin::Alloc;
+use ord_map::OrdMap;
+#[cfg(feature = "std")]
+use std::collections::HashMap as StdHashMap;
 use self::fnv::FnvBuildHasher as RandomState;
+use self::fnv::FnvHasher;
+#[cfg(feature = "rayon")]
+use hash_table::HashIndex;
+#[cfg(feature = "rayon")]
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 /// A hash map which uses AVL to resolve collision.
 ///
@@ -164,9 +180,12 @@ use self::fnv::FnvBuildHasher as RandomState;
 ///      ("Iceland", 10)]
 ///      .iter().cloned().collect();
 /// ```
-pub struct HashMap<K, V, S = RandomState> {
-    entry_fastbin: Fastbin,
-    kv_fastbin: Fastbin,
+pub struct HashMap<K, V, S = RandomState, A = Global>
+where
+    A: Alloc,
+{
+    entry_fastbin: Fastbin<A>,
+    kv_fastbin: Fastbin<A>,
     hash_table: Box<HashTable<K, V>>,
     hash_builder: S,
 }
@@ -183,20 +202,22 @@ struct InternalHashEntry<K, V> {
 ///
 /// [`keys`]: struct.HashMap.html#method.keys
 /// [`HashMap`]: struct.HashMap.html
-pub struct Keys<'a, K, V, S>
+pub struct Keys<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
-    inner: Iter<'a, K, V, S>,
+    inner: Iter<'a, K, V, S, A>,
 }
 
-impl<'a, K, V, S> Iterator for Keys<'a, K, V, S>
+impl<'a, K, V, S, A> Iterator for Keys<'a, K, V, S, A>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc,
 {
     type Item = &'a K;
 
@@ -217,20 +238,22 @@ where
 ///
 /// [`values`]: struct.HashMap.html#method.values
 /// [`HashMap`]: struct.HashMap.html
-pub struct Values<'a, K, V, S>
+pub struct Values<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
-    inner: Iter<'a, K, V, S>,
+    inner: Iter<'a, K, V, S, A>,
 }
 
-impl<'a, K, V, S> Iterator for Values<'a, K, V, S>
+impl<'a, K, V, S, A> Iterator for Values<'a, K, V, S, A>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc,
 {
     type Item = &'a V;
 
@@ -251,20 +274,22 @@ where
 ///
 /// [`values_mut`]: struct.HashMap.html#method.values_mut
 /// [`HashMap`]: struct.HashMap.html
-pub struct ValuesMut<'a, K, V, S>
+pub struct ValuesMut<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
-    inner: IterMut<'a, K, V, S>,
+    inner: IterMut<'a, K, V, S, A>,
 }
 
-impl<'a, K, V, S> Iterator for ValuesMut<'a, K, V, S>
+impl<'a, K, V, S, A> Iterator for ValuesMut<'a, K, V, S, A>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc,
 {
     type Item = &'a mut V;
 
@@ -278,23 +303,92 @@ where
     }
 }
 
+impl<'a, K, V, S, A> DoubleEndedIterator for ValuesMut<'a, K, V, S, A>
+where
+    K: 'a,
+    V: 'a,
+    S: 'a,
+    A: Alloc,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K, V, S, A> ExactSizeIterator for ValuesMut<'a, K, V, S, A>
+where
+    K: 'a,
+    V: 'a,
+    S: 'a,
+    A: Alloc,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// A handle to an entry of a `HashMap`, which reads the entry again without hashing or
+/// searching for its key.
+///
+/// This `struct` is created by the [`find_ref`] method on [`HashMap`]. See its
+/// documentation for more.
+///
+/// [`find_ref`]: struct.HashMap.html#method.find_ref
+/// [`HashMap`]: struct.HashMap.html
+pub struct EntryHandle<'a, K: 'a, V: 'a> {
+    entry: *mut InternalHashEntry<K, V>,
+    _marker: marker::PhantomData<&'a (K, V)>,
+}
+
+impl<'a, K, V> Clone for EntryHandle<'a, K, V> {
+    fn clone(&self) -> EntryHandle<'a, K, V> {
+        *self
+    }
+}
+
+impl<'a, K, V> Copy for EntryHandle<'a, K, V> {}
+
+impl<'a, K, V> EntryHandle<'a, K, V> {
+    /// Returns a reference to the key of the entry.
+    #[inline]
+    pub fn key(&self) -> &'a K {
+        unsafe { &*self.entry.key() }
+    }
+
+    /// Returns a reference to the value of the entry.
+    #[inline]
+    pub fn value(&self) -> &'a V {
+        unsafe { &*self.entry.value() }
+    }
+
+    /// Returns references to the key and the value of the entry.
+    #[inline]
+    pub fn get(&self) -> (&'a K, &'a V) {
+        (self.key(), self.value())
+    }
+}
+
 /// An iterator over the (key, value) of a `HashMap`.
-pub struct Iter<'a, K, V, S>
+pub struct Iter<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
     inner: *mut InternalHashEntry<K, V>,
-    map: &'a HashMap<K, V, S>,
+    map: &'a HashMap<K, V, S, A>,
     len: usize,
 }
 
-impl<'a, K, V, S> Iterator for Iter<'a, K, V, S>
+impl<'a, K, V, S, A> Iterator for Iter<'a, K, V, S, A>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc,
 {
     type Item = (&'a K, &'a V);
 
@@ -317,22 +411,25 @@ where
 }
 
 /// An iterator over the (key, mut value) of a `HashMap`.
-pub struct IterMut<'a, K, V, S>
+pub struct IterMut<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
     inner: *mut InternalHashEntry<K, V>,
-    map: &'a HashMap<K, V, S>,
+    tail: *mut InternalHashEntry<K, V>,
+    map: &'a HashMap<K, V, S, A>,
     len: usize,
 }
 
-impl<'a, K, V, S> Iterator for IterMut<'a, K, V, S>
+impl<'a, K, V, S, A> Iterator for IterMut<'a, K, V, S, A>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc,
 {
     type Item = (&'a K, &'a mut V);
 
@@ -354,6 +451,231 @@ where
     }
 }
 
+impl<'a, K, V, S, A> DoubleEndedIterator for IterMut<'a, K, V, S, A>
+where
+    K: 'a,
+    V: 'a,
+    S: 'a,
+    A: Alloc,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        let entry = self.tail;
+        if entry.is_null() || self.len == 0 {
+            return None;
+        }
+        let res = unsafe { Some((&(*entry.key()), &mut (*entry.value()))) };
+        self.tail = self.map.prev(entry);
+        self.len -= 1;
+        res
+    }
+}
+
+impl<'a, K, V, S, A> ExactSizeIterator for IterMut<'a, K, V, S, A>
+where
+    K: 'a,
+    V: 'a,
+    S: 'a,
+    A: Alloc,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// A parallel iterator over the (key, value) of a `HashMap`.
+///
+/// This `struct` is created by the `par_iter` method on [`HashMap`] (provided by rayon's
+/// `IntoParallelRefIterator` trait). The work is split over the hash indexes, and the AVL under
+/// every index is visited by a single worker.
+///
+/// [`HashMap`]: struct.HashMap.html
+#[cfg(feature = "rayon")]
+pub struct ParIter<'a, K: 'a, V: 'a> {
+    index: *mut HashIndex,
+    index_size: usize,
+    _marker: marker::PhantomData<&'a (K, V)>,
+}
+
+/// The indexes are disjoint and not modified while the map is borrowed, so they can be read
+/// from any thread.
+#[cfg(feature = "rayon")]
+unsafe impl<'a, K: Sync, V: Sync> Send for ParIter<'a, K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let producer = IndexProducer {
+            index: self.index,
+            start: 0,
+            end: self.index_size,
+            _marker: marker::PhantomData,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, K, V, S, A> IntoParallelIterator for &'a HashMap<K, V, S, A>
+where
+    K: Ord + Hash + Sync,
+    V: Sync,
+    S: BuildHasher,
+    A: Alloc,
+{
+    type Item = (&'a K, &'a V);
+    type Iter = ParIter<'a, K, V>;
+
+    fn into_par_iter(self) -> ParIter<'a, K, V> {
+        ParIter {
+            index: self.hash_table.index_ptr(),
+            index_size: self.hash_table.index_size(),
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+/// Produces the entries under the hash indexes in `[start, end)`.
+#[cfg(feature = "rayon")]
+struct IndexProducer<'a, K: 'a, V: 'a> {
+    index: *mut HashIndex,
+    start: usize,
+    end: usize,
+    _marker: marker::PhantomData<&'a (K, V)>,
+}
+
+#[cfg(feature = "rayon")]
+unsafe impl<'a, K: Sync, V: Sync> Send for IndexProducer<'a, K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, K: Sync, V: Sync> UnindexedProducer for IndexProducer<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn split(self) -> (Self, Option<Self>) {
+        if self.end - self.start <= 1 {
+            return (self, None);
+        }
+        let mid = self.start + (self.end - self.start) / 2;
+        let right = IndexProducer {
+            start: mid,
+            ..self
+        };
+        (IndexProducer { end: mid, ..self }, Some(right))
+    }
+
+    fn fold_with<F>(self, mut folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        for i in self.start..self.end {
+            let index = unsafe { self.index.offset(i as isize) };
+            let mut avl_node = index.avl_root_node().first_node();
+            while avl_node.not_null() {
+                let entry: *mut InternalHashEntry<K, V> =
+                    avl_node.avl_hash_deref_mut::<K>().deref_to_hash_entry();
+                folder = folder.consume(unsafe { (&(*entry.key()), &(*entry.value())) });
+                if folder.full() {
+                    return folder;
+                }
+                avl_node = avl_node.next();
+            }
+        }
+        folder
+    }
+}
+
+/// A parallel iterator over the values of a `HashMap`, yielding mutable references.
+///
+/// This `struct` is created by the [`par_values_mut`] method on [`HashMap`]. See its
+/// documentation for more.
+///
+/// [`par_values_mut`]: struct.HashMap.html#method.par_values_mut
+/// [`HashMap`]: struct.HashMap.html
+#[cfg(feature = "rayon")]
+pub struct ParValuesMut<'a, K: 'a, V: 'a> {
+    index: *mut HashIndex,
+    index_size: usize,
+    _marker: marker::PhantomData<&'a mut (K, V)>,
+}
+
+/// Each value is reachable from a single index, and the indexes are split into disjoint ranges,
+/// so no value is ever handed to two threads.
+#[cfg(feature = "rayon")]
+unsafe impl<'a, K: Sync, V: Send> Send for ParValuesMut<'a, K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, K: Sync, V: Send> ParallelIterator for ParValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let producer: IndexValuesMutProducer<K, V> = IndexValuesMutProducer {
+            index: self.index,
+            start: 0,
+            end: self.index_size,
+            _marker: marker::PhantomData,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+/// Produces the values under the hash indexes in `[start, end)` mutably.
+#[cfg(feature = "rayon")]
+struct IndexValuesMutProducer<'a, K: 'a, V: 'a> {
+    index: *mut HashIndex,
+    start: usize,
+    end: usize,
+    _marker: marker::PhantomData<&'a mut (K, V)>,
+}
+
+#[cfg(feature = "rayon")]
+unsafe impl<'a, K: Sync, V: Send> Send for IndexValuesMutProducer<'a, K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, K: Sync, V: Send> UnindexedProducer for IndexValuesMutProducer<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn split(self) -> (Self, Option<Self>) {
+        if self.end - self.start <= 1 {
+            return (self, None);
+        }
+        let mid = self.start + (self.end - self.start) / 2;
+        let right = IndexValuesMutProducer {
+            start: mid,
+            ..self
+        };
+        (IndexValuesMutProducer { end: mid, ..self }, Some(right))
+    }
+
+    fn fold_with<F>(self, mut folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        for i in self.start..self.end {
+            let index = unsafe { self.index.offset(i as isize) };
+            let mut avl_node = index.avl_root_node().first_node();
+            while avl_node.not_null() {
+                let entry: *mut InternalHashEntry<K, V> =
+                    avl_node.avl_hash_deref_mut::<K>().deref_to_hash_entry();
+                folder = folder.consume(unsafe { &mut (*entry.value()) });
+                if folder.full() {
+                    return folder;
+                }
+                avl_node = avl_node.next();
+            }
+        }
+        folder
+    }
+}
+
 #[inline]
 fn key_deref_to_kv<K, V>(key: *mut K) -> *mut (K, V) {
     container_of!(key, (K, V), 0)
@@ -432,8 +754,8 @@ where
 
 /// Alloc an InternalHashEntry in `Fastbin`
 #[inline]
-fn entry_alloc<K, V>(
-    entry_fastbin: &mut Fastbin,
+fn entry_alloc<K, V, A: Alloc>(
+    entry_fastbin: &mut Fastbin<A>,
     key: *mut K,
     value: *mut V,
     hash_value: HashUint,
@@ -448,7 +770,7 @@ fn entry_alloc<K, V>(
 
 /// Alloc a (Key, Value) pair in `Fastbin`
 #[inline]
-fn kv_alloc<K, V>(kv_fastbin: &mut Fastbin, key: K, value: V) -> *mut (K, V) {
+fn kv_alloc<K, V, A: Alloc>(kv_fastbin: &mut Fastbin<A>, key: K, value: V) -> *mut (K, V) {
     let kv = kv_fastbin.alloc() as *mut (K, V);
     unsafe {
         let key_ptr = &mut (*kv).0 as *mut K;
@@ -465,20 +787,24 @@ fn kv_alloc<K, V>(kv_fastbin: &mut Fastbin, key: K, value: V) -> *mut (K, V) {
 ///
 /// [`HashMap`]: struct.HashMap.html
 /// [`entry`]: struct.HashMap.html#method.entry
-pub enum Entry<'a, K, V, S>
+pub enum Entry<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
     /// An occupied entry.
-    Occupied(OccupiedEntry<'a, K, V, S>),
+    Occupied(OccupiedEntry<'a, K, V, S, A>),
 
     /// A vacant entry.
-    Vacant(VacantEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S, A>),
 }
 
-impl<'a, K, V, S> Entry<'a, K, V, S> {
+impl<'a, K, V, S, A> Entry<'a, K, V, S, A>
+where
+    A: Alloc,
+{
     /// Returns a reference to this entry's key.
     ///
     /// # Examples
@@ -497,10 +823,11 @@ impl<'a, K, V, S> Entry<'a, K, V, S> {
     }
 }
 
-impl<'a, K, V, S> Entry<'a, K, V, S>
+impl<'a, K, V, S, A> Entry<'a, K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     /// Ensures a value is in the entry by inserting the default if empty, and returns
     /// a mutable reference to the value in the entry.
@@ -585,38 +912,41 @@ where
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-pub struct OccupiedEntry<'a, K, V, S>
+pub struct OccupiedEntry<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
     key: Option<K>,
     hash_entry: *mut InternalHashEntry<K, V>,
-    hash_map_mut: &'a mut HashMap<K, V, S>,
+    hash_map_mut: &'a mut HashMap<K, V, S, A>,
 }
 
 /// A view into a vacant entry in a `HashMap`.
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-pub struct VacantEntry<'a, K, V, S>
+pub struct VacantEntry<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
     hash_value: HashUint,
     key: K,
     parent: AVLNodePtr,
     link: *mut AVLNodePtr,
-    hash_map_mut: &'a mut HashMap<K, V, S>,
+    hash_map_mut: &'a mut HashMap<K, V, S, A>,
 }
 
-impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
+impl<'a, K, V, S, A> OccupiedEntry<'a, K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     /// Take the ownership of the key and value from the map.
     ///
@@ -661,7 +991,10 @@ where
     }
 }
 
-impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
+impl<'a, K, V, S, A> OccupiedEntry<'a, K, V, S, A>
+where
+    A: Alloc,
+{
     /// Gets a reference to the key in the entry.
     ///
     /// # Examples
@@ -827,7 +1160,10 @@ impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
     }
 }
 
-impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
+impl<'a, K, V, S, A> VacantEntry<'a, K, V, S, A>
+where
+    A: Alloc,
+{
     /// Gets a reference to the key that would be used when inserting a value
     /// through the `VacantEntry`.
     ///
@@ -861,10 +1197,11 @@ impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
     }
 }
 
-impl<'a, K, V, S> VacantEntry<'a, K, V, S>
+impl<'a, K, V, S, A> VacantEntry<'a, K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     unsafe fn _internal_insert(self, value: V) -> &'a mut V {
         let hash_value = self.hash_value;
@@ -887,7 +1224,9 @@ where
         avl_node::node_post_insert(new_node.avl_node_ptr(), index.avl_root_ptr());
         self.hash_map_mut.hash_table.inc_count(1);
         let new_len = self.hash_map_mut.len();
-        self.hash_map_mut.rehash(new_len);
+        if self.hash_map_mut.hash_table.need_rehash(new_len) {
+            self.hash_map_mut.rehash(new_len);
+        }
         &mut *new_entry.value()
     }
 
@@ -910,24 +1249,153 @@ where
     }
 }
 
-impl<K, V, S> HashMap<K, V, S> {
-    fn recurse_destroy<F>(&mut self, node: avl_node::AVLNodePtr, f: &mut F)
+/// A view into a single entry of a borrowed key in a map, which may either be vacant or
+/// occupied.
+///
+/// This `enum` is constructed from the [`entry_ref`] method on [`HashMap`].
+///
+/// [`HashMap`]: struct.HashMap.html
+/// [`entry_ref`]: struct.HashMap.html#method.entry_ref
+pub enum EntryRef<'a, 'b, K, Q: ?Sized, V, S, A = Global>
+where
+    K: 'a,
+    Q: 'b,
+    V: 'a,
+    S: 'a,
+    A: Alloc + 'a,
+{
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V, S, A>),
+
+    /// A vacant entry.
+    Vacant(VacantEntryRef<'a, 'b, K, Q, V, S, A>),
+}
+
+impl<'a, 'b, K, Q: ?Sized, V, S, A> EntryRef<'a, 'b, K, Q, V, S, A>
+where
+    K: Ord + Hash + Borrow<Q>,
+    Q: Ord + Hash + ToOwned<Owned = K>,
+    S: BuildHasher,
+    A: Alloc,
+{
+    /// Ensures a value is in the entry by inserting the default if empty, and returns
+    /// a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map: HashMap<String, u32> = HashMap::new();
+    /// map.entry_ref("poneyland").or_insert(12);
+    ///
+    /// *map.entry_ref("poneyland").or_insert(12) += 10;
+    /// assert_eq!(map["poneyland"], 22);
+    /// ```
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the map.
+    pub fn and_modify<F>(self, mut f: F) -> Self
     where
-        F: FnMut((K, V)),
+        F: FnMut(&mut V),
     {
-        if node.left().not_null() {
-            self.recurse_destroy(node.left(), f);
+        match self {
+            EntryRef::Occupied(mut entry) => {
+                f(entry.get_mut());
+                EntryRef::Occupied(entry)
+            }
+            EntryRef::Vacant(entry) => EntryRef::Vacant(entry),
         }
-        if node.right().not_null() {
-            self.recurse_destroy(node.right(), f);
+    }
+}
+
+/// A view into a vacant entry of a borrowed key in a `HashMap`.
+/// It is part of the [`EntryRef`] enum.
+///
+/// [`EntryRef`]: enum.EntryRef.html
+pub struct VacantEntryRef<'a, 'b, K, Q: ?Sized, V, S, A = Global>
+where
+    K: 'a,
+    Q: 'b,
+    V: 'a,
+    S: 'a,
+    A: Alloc + 'a,
+{
+    hash_value: HashUint,
+    key: &'b Q,
+    parent: AVLNodePtr,
+    link: *mut AVLNodePtr,
+    hash_map_mut: &'a mut HashMap<K, V, S, A>,
+}
+
+impl<'a, 'b, K, Q: ?Sized, V, S, A> VacantEntryRef<'a, 'b, K, Q, V, S, A>
+where
+    K: Ord + Hash + Borrow<Q>,
+    Q: Ord + Hash + ToOwned<Owned = K>,
+    S: BuildHasher,
+    A: Alloc,
+{
+    /// Gets a reference to the key that would be used when inserting a value
+    /// through the `VacantEntryRef`.
+    pub fn key(&self) -> &'b Q {
+        self.key
+    }
+
+    /// Sets the value of the entry with an owned copy of the VacantEntryRef's key,
+    /// and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let entry = VacantEntry {
+            hash_value: self.hash_value,
+            key: self.key.to_owned(),
+            parent: self.parent,
+            link: self.link,
+            hash_map_mut: self.hash_map_mut,
+        };
+        entry.insert(value)
+    }
+}
+
+impl<K, V, S, A> HashMap<K, V, S, A>
+where
+    A: Alloc,
+{
+    fn destroy_tree<F>(&mut self, node: avl_node::AVLNodePtr, f: &mut F)
+    where
+        F: FnMut((K, V)),
+    {
+        let mut root = avl_node::AVLRoot { node };
+        let mut cnt = 0;
+        {
+            let entry_fastbin = &mut self.entry_fastbin;
+            let kv_fastbin = &mut self.kv_fastbin;
+            unsafe {
+                avl_node::avl_for_each_post_order(&mut root, &mut |node| {
+                    let hash_node = node.avl_hash_deref_mut::<K>();
+                    let entry: *mut InternalHashEntry<K, V> = hash_node.deref_to_hash_entry();
+                    entry_fastbin.del(entry as VoidPtr);
+                    let kv_ptr = key_deref_to_kv::<K, V>(hash_node.key_ptr());
+                    (*f)(ptr::read(kv_ptr));
+                    kv_fastbin.del(kv_ptr as VoidPtr);
+                    cnt += 1;
+                });
+            }
         }
-        let hash_node = node.avl_hash_deref_mut::<K>();
-        let entry: *mut InternalHashEntry<K, V> = hash_node.deref_to_hash_entry();
-        self.entry_fastbin.del(entry as VoidPtr);
-        let kv_ptr = key_deref_to_kv::<K, V>(hash_node.key_ptr());
-        unsafe { (*f)(ptr::read(kv_ptr)) };
-        self.kv_fastbin.del(kv_ptr as VoidPtr);
-        self.hash_table.dec_count(1);
+        self.hash_table.dec_count(cnt);
     }
 
     /// Clears the map, removing all key-value pairs. Keeps the allocated memory
@@ -950,11 +1418,35 @@ impl<K, V, S> HashMap<K, V, S> {
             if node.is_null() {
                 break;
             }
-            self.recurse_destroy(node, &mut destroy_callback);
+            self.destroy_tree(node, &mut destroy_callback);
         }
         debug_assert_eq!(self.hash_table.size(), 0);
     }
 
+    /// Clears the map, removing all key-value pairs, and releases the allocated memory. The
+    /// bucket array falls back to the small inline one of an empty map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut a = HashMap::new();
+    /// let init_capacity = a.capacity();
+    /// for i in 0..100 {
+    ///     a.insert(i, i);
+    /// }
+    /// a.clear_and_shrink();
+    /// assert!(a.is_empty());
+    /// assert_eq!(a.capacity(), init_capacity);
+    /// ```
+    pub fn clear_and_shrink(&mut self) {
+        self.clear();
+        self.hash_table.reset_index();
+        self.entry_fastbin.shrink();
+        self.kv_fastbin.shrink();
+    }
+
     /// Returns the number of elements the map can hold without reallocating.
     ///
     /// This number is a lower bound; the `HashMap<K, V>` might be able to hold
@@ -973,14 +1465,58 @@ impl<K, V, S> HashMap<K, V, S> {
         self.hash_table.capacity()
     }
 
-    /// Returns the maximum node count under a simgle HashIndex
+    /// Returns the maximum average number of entries per hash index.
     #[inline]
-    pub fn get_max_node_of_single_index(&self) -> i32 {
-        self.hash_table.get_max_node_of_single_index()
+    pub fn load_factor(&self) -> f64 {
+        self.hash_table.load_factor()
     }
 
+    /// Returns an iterator over the node count of every non-empty HashIndex, which helps to
+    /// diagnose the distribution of hash values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// for i in 0..100 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.bucket_depths().sum::<i32>(), 100);
+    /// ```
     #[inline]
-    fn first(&self) -> *mut InternalHashEntry<K, V> {
+    pub fn bucket_depths<'a>(&'a self) -> impl Iterator<Item = i32> + 'a {
+        self.hash_table.bucket_depths()
+    }
+
+    /// Returns the maximum node count under a simgle HashIndex
+    #[inline]
+    pub fn get_max_node_of_single_index(&self) -> i32 {
+        self.hash_table.get_max_node_of_single_index()
+    }
+
+    /// Returns the number of bytes allocated on the heap by the map, including memory kept
+    /// for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// let empty = map.memory_usage();
+    /// map.insert(1, 2);
+    /// assert!(map.memory_usage() > empty);
+    /// ```
+    #[inline]
+    pub fn memory_usage(&self) -> usize {
+        self.entry_fastbin.allocated_bytes() + self.kv_fastbin.allocated_bytes()
+            + self.hash_table.allocated_bytes()
+    }
+
+    #[inline]
+    fn first(&self) -> *mut InternalHashEntry<K, V> {
         let hash_node = self.hash_table.node_first();
         if hash_node.is_null() {
             return ptr::null_mut();
@@ -1081,7 +1617,7 @@ impl<K, V, S> HashMap<K, V, S> {
     ///     println!("{}", key);
     /// }
     /// ```
-    pub fn keys(&self) -> Keys<K, V, S> {
+    pub fn keys(&self) -> Keys<K, V, S, A> {
         Keys { inner: self.iter() }
     }
 
@@ -1102,7 +1638,7 @@ impl<K, V, S> HashMap<K, V, S> {
     ///     println!("{}", val);
     /// }
     /// ```
-    pub fn values(&self) -> Values<K, V, S> {
+    pub fn values(&self) -> Values<K, V, S, A> {
         Values { inner: self.iter() }
     }
 
@@ -1128,15 +1664,48 @@ impl<K, V, S> HashMap<K, V, S> {
     ///     println!("{}", val);
     /// }
     /// ```
-    pub fn values_mut(&mut self) -> ValuesMut<K, V, S> {
+    pub fn values_mut(&mut self) -> ValuesMut<K, V, S, A> {
         ValuesMut {
             inner: self.iter_mut(),
         }
     }
 
+    /// A parallel iterator visiting all values mutably in arbitrary order.
+    /// The iterator element type is `&'a mut V`.
+    ///
+    /// Like `par_iter`, the work is split over the hash indexes. Every entry lives under exactly
+    /// one index, so the values handed out to different workers never alias.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate hash_ord;
+    /// extern crate rayon;
+    ///
+    /// use hash_ord::hash_map::HashMap;
+    /// use rayon::prelude::*;
+    ///
+    /// # fn main() {
+    /// let mut map: HashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+    /// map.par_values_mut().for_each(|v| *v *= 2);
+    /// assert_eq!(map[&21], 42);
+    /// # }
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_values_mut(&mut self) -> ParValuesMut<K, V> {
+        ParValuesMut {
+            index: self.hash_table.index_ptr(),
+            index_size: self.hash_table.index_size(),
+            _marker: marker::PhantomData,
+        }
+    }
+
     /// An iterator visiting all key-value pairs in arbitrary order.
     /// The iterator element type is `(&'a K, &'a V)`.
     ///
+    /// The map can still be searched with `get` or `contains_key` while the iterator is alive:
+    /// lookups do not change the map, and entries never move while they are in it.
+    ///
     /// # Examples
     ///
     /// ```
@@ -1151,7 +1720,7 @@ impl<K, V, S> HashMap<K, V, S> {
     ///     println!("key: {} val: {}", key, val);
     /// }
     /// ```
-    pub fn iter(&self) -> Iter<K, V, S> {
+    pub fn iter(&self) -> Iter<K, V, S, A> {
         Iter {
             inner: self.first(),
             map: self,
@@ -1159,6 +1728,30 @@ impl<K, V, S> HashMap<K, V, S> {
         }
     }
 
+    /// An iterator visiting all key-value pairs in incremental order of keys, which does not
+    /// depend on the hasher. Meant for debugging and snapshot tests.
+    ///
+    /// The pairs are collected and sorted up front, which allocates O(n) memory and takes
+    /// O(n log n) time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let map: HashMap<_, _> = vec![(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// let pairs: Vec<_> = map.iter_sorted().collect();
+    /// assert_eq!(pairs, [(&1, &"a"), (&2, &"b"), (&3, &"c")]);
+    /// ```
+    pub fn iter_sorted<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)> + 'a
+    where
+        K: Ord,
+    {
+        let mut pairs: Vec<_> = self.iter().collect();
+        pairs.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        pairs.into_iter()
+    }
+
     /// An iterator visiting all key-value pairs in arbitrary order,
     /// with mutable references to the values.
     /// The iterator element type is `(&'a K, &'a mut V)`.
@@ -1182,9 +1775,10 @@ impl<K, V, S> HashMap<K, V, S> {
     ///     println!("key: {} val: {}", key, val);
     /// }
     /// ```
-    pub fn iter_mut(&mut self) -> IterMut<K, V, S> {
+    pub fn iter_mut(&mut self) -> IterMut<K, V, S, A> {
         IterMut {
             inner: self.first(),
+            tail: self.last(),
             map: self,
             len: self.len(),
         }
@@ -1210,13 +1804,65 @@ impl<K, V, S> HashMap<K, V, S> {
     /// assert!(a.is_empty());
     /// ```
     #[inline]
-    pub fn drain(&mut self) -> Drain<K, V, S> {
+    pub fn drain(&mut self) -> Drain<K, V, S, A> {
+        Drain {
+            entry: self.first(),
+            shrink: false,
+            map: self,
+        }
+    }
+
+    /// Same as `drain`, but once the iterator is dropped, the index array falls back to the
+    /// small inline one of an empty map, and the pages of entries are released.
+    ///
+    /// This suits processing a big batch and then going back to a small map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let init_capacity = HashMap::<i32, i32>::new().capacity();
+    /// let mut a: HashMap<_, _> = (0..1000).map(|i| (i, i)).collect();
+    /// assert_eq!(a.drain_and_shrink().count(), 1000);
+    /// assert!(a.is_empty());
+    /// assert_eq!(a.capacity(), init_capacity);
+    /// ```
+    #[inline]
+    pub fn drain_and_shrink(&mut self) -> Drain<K, V, S, A> {
         Drain {
             entry: self.first(),
+            shrink: true,
             map: self,
         }
     }
 
+    /// Retains only the elements whose keys satisfy the predicate, removing the others.
+    ///
+    /// The values are never read to decide, so large values of retained elements stay out of
+    /// the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map: HashMap<_, _> = (0..8).map(|i| (i, i * 10)).collect();
+    /// map.retain_keys(|&k| k % 2 == 0);
+    /// assert_eq!(map.len(), 4);
+    /// assert_eq!(map[&6], 60);
+    /// ```
+    pub fn retain_keys<F: FnMut(&K) -> bool>(&mut self, mut f: F) {
+        let mut entry = self.first();
+        while !entry.is_null() {
+            let next = self.next(entry);
+            if !f(unsafe { &*entry.key() }) {
+                self.erase(entry);
+            }
+            entry = next;
+        }
+    }
+
     fn erase(&mut self, entry: *mut InternalHashEntry<K, V>) -> Option<(K, V)> {
         debug_assert!(!entry.is_null());
         debug_assert!(!entry.node_ptr().avl_node_ptr().empty());
@@ -1229,10 +1875,11 @@ impl<K, V, S> HashMap<K, V, S> {
     }
 }
 
-impl<K, V, S> HashMap<K, V, S>
+impl<K, V, S, A> HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     /// Gets the given key's corresponding entry in the map for in-place manipulation.
     ///
@@ -1253,11 +1900,11 @@ where
     /// assert_eq!(letters[&'u'], 1);
     /// assert_eq!(letters.get(&'y'), None);
     /// ```
-    pub fn entry(&mut self, mut key: K) -> Entry<K, V, S> {
+    pub fn entry(&mut self, key: K) -> Entry<K, V, S, A> {
         let hash_val = self.make_hash(&key);
         let link = self.hash_table.get_hash_index(hash_val).avl_root_node_ptr();
         let (duplicate, parent, link) =
-            unsafe { hash_table::find_duplicate_hash_node(link, &mut key as *mut K, hash_val) };
+            unsafe { hash_table::find_duplicate_hash_node::<K, K>(link, &key, hash_val) };
         if duplicate.is_null() {
             return Entry::Vacant(VacantEntry {
                 hash_value: hash_val,
@@ -1275,6 +1922,110 @@ where
         };
     }
 
+    /// Gets the entry of a borrowed key in the map for in-place manipulation. Unlike `entry`,
+    /// the key is only converted into an owned `K` if a value is inserted into a vacant entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut words: HashMap<String, usize> = HashMap::new();
+    ///
+    /// for word in "the cat saw the dog".split(' ') {
+    ///     *words.entry_ref(word).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(words["the"], 2);
+    /// assert_eq!(words["dog"], 1);
+    /// ```
+    pub fn entry_ref<'a, 'b, Q: ?Sized>(&'a mut self, q: &'b Q) -> EntryRef<'a, 'b, K, Q, V, S, A>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + ToOwned<Owned = K>,
+    {
+        let hash_val = self.make_hash(q);
+        let link = self.hash_table.get_hash_index(hash_val).avl_root_node_ptr();
+        let (duplicate, parent, link) =
+            unsafe { hash_table::find_duplicate_hash_node::<K, Q>(link, q, hash_val) };
+        if duplicate.is_null() {
+            EntryRef::Vacant(VacantEntryRef {
+                hash_value: hash_val,
+                key: q,
+                parent,
+                link,
+                hash_map_mut: self,
+            })
+        } else {
+            EntryRef::Occupied(OccupiedEntry {
+                key: None,
+                hash_entry: duplicate.deref_to_hash_entry(),
+                hash_map_mut: self,
+            })
+        }
+    }
+
+    /// Returns a mutable reference to the value of `key`, inserting the result of `f` first if
+    /// the key is absent. `f` is not called if the key is present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// *map.get_or_insert_with("a", || 1) += 10;
+    /// assert_eq!(*map.get_or_insert_with("a", || unreachable!()), 11);
+    /// ```
+    #[inline]
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        self.entry(key).or_insert_with(f)
+    }
+
+    /// Same as `get_or_insert_with`, but `f` may fail. If the key is absent and `f` returns an
+    /// error, the error is returned and the map is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// assert_eq!(map.try_get_or_insert_with("a", || "1".parse::<i32>()), Ok(&mut 1));
+    /// assert!(map.try_get_or_insert_with("b", || "x".parse::<i32>()).is_err());
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn try_get_or_insert_with<F, E>(&mut self, key: K, f: F) -> Result<&mut V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+    {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(f()?)),
+        }
+    }
+
+    /// Returns a mutable reference to the value of `key`, inserting `V::default()` first if
+    /// the key is absent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map: HashMap<i32, Vec<char>> = HashMap::new();
+    /// map.get_mut_or_insert_default(1).push('a');
+    /// map.get_mut_or_insert_default(1).push('b');
+    /// assert_eq!(map[&1], ['a', 'b']);
+    /// ```
+    #[inline]
+    pub fn get_mut_or_insert_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        self.entry(key).or_insert_with(Default::default)
+    }
+
     #[inline]
     fn make_hash<X: ?Sized>(&self, x: &X) -> HashUint
     where
@@ -1284,7 +2035,7 @@ where
     }
 
     /// Creates an empty `HashMap` which will use the given hash builder to hash
-    /// keys.
+    /// keys, and allocates memory from `allocator`.
     ///
     /// The created map has the default initial capacity.
     ///
@@ -1297,14 +2048,40 @@ where
     ///
     /// ```
     /// use hash_ord::hash_map::HashMap;
+    /// use hash_ord::Global;
     /// use std::collections::hash_map::RandomState;
     ///
     /// let s = RandomState::new();
-    /// let mut map = HashMap::with_hasher(s);
+    /// let mut map = HashMap::with_hasher_in(s, Global);
     /// map.insert(1, 2);
     /// ```
-    pub fn with_hasher(hash_builder: S) -> Self {
-        HashMap::with_capacity_and_hasher(0, hash_builder)
+    pub fn with_hasher_in(hash_builder: S, allocator: A) -> Self {
+        HashMap::with_capacity_and_hasher_in(0, hash_builder, allocator)
+    }
+
+    /// Returns a reference to the underlying allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        self.entry_fastbin.allocator()
+    }
+
+    /// Returns a reference to the map's [`BuildHasher`].
+    ///
+    /// [`BuildHasher`]: https://doc.rust-lang.org/std/hash/trait.BuildHasher.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let hasher = RandomState::new();
+    /// let map: HashMap<i32, i32, _> = HashMap::with_hasher(hasher);
+    /// let hasher: &RandomState = map.hasher();
+    /// ```
+    #[inline]
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
     }
 
     #[inline]
@@ -1350,6 +2127,120 @@ where
         unsafe { Some(&(*entry.value())) }
     }
 
+    /// Returns a handle to the entry of the key, which can read the entry again later without
+    /// another lookup.
+    ///
+    /// Entries are never moved while they are in the map, and the handle borrows the map, so
+    /// it stays valid across any number of lookups and iterations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// let handle = map.find_ref(&1).unwrap();
+    /// assert_eq!(map.get(&2), Some(&"b"));
+    /// assert_eq!(handle.get(), (&1, &"a"));
+    /// assert!(map.find_ref(&3).is_none());
+    /// ```
+    #[inline]
+    pub fn find_ref<Q: ?Sized>(&self, q: &Q) -> Option<EntryHandle<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord,
+    {
+        let entry = self.find(q);
+        if entry.is_null() {
+            return None;
+        }
+        Some(EntryHandle {
+            entry,
+            _marker: marker::PhantomData,
+        })
+    }
+
+    /// Returns raw pointers to the key and the value of the entry of the key, e.g. to hand the
+    /// value over to foreign code without copying it.
+    ///
+    /// The key and value live together in a cell allocated from the `Fastbin` of the map, and
+    /// the cell is never moved while the entry is in the map: rehashing, `reserve` and
+    /// `shrink_to_fit` only relink or release other memory. The pointers stay valid until the
+    /// entry is removed (`remove`, `clear`, `retain_keys`, ...), replaced by `insert` with an
+    /// equal key, or the map is dropped or consumed. Updating the value through `get_mut` or
+    /// an entry keeps them valid.
+    ///
+    /// # Safety
+    ///
+    /// The caller must not use the pointers after they are invalidated as described above, must
+    /// not write through the key pointer, and must not write through the value pointer while
+    /// any reference into the map is alive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert(1, "a");
+    /// let (k, v) = unsafe { map.entry_ptr(&1).unwrap() };
+    /// unsafe {
+    ///     assert_eq!(*k, 1);
+    ///     *v = "b";
+    /// }
+    /// assert_eq!(map.get(&1), Some(&"b"));
+    /// assert!(unsafe { map.entry_ptr(&2) }.is_none());
+    /// ```
+    #[inline]
+    pub unsafe fn entry_ptr<Q: ?Sized>(&self, q: &Q) -> Option<(*const K, *mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord,
+    {
+        let entry = self.find(q);
+        if entry.is_null() {
+            return None;
+        }
+        Some((entry.key() as *const K, entry.value()))
+    }
+
+    /// Looks up several keys at once, returning the stored key and value of each one.
+    ///
+    /// Nothing is borrowed mutably, so the same key may be passed more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// assert_eq!(
+    ///     map.get_many_key_value([&2, &3, &2]),
+    ///     [Some((&2, &"b")), None, Some((&2, &"b"))]
+    /// );
+    /// ```
+    pub fn get_many_key_value<Q: ?Sized, const N: usize>(
+        &self,
+        keys: [&Q; N],
+    ) -> [Option<(&K, &V)>; N]
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord,
+    {
+        keys.map(|q| {
+            let entry = self.find(q);
+            if entry.is_null() {
+                None
+            } else {
+                unsafe { Some((&*entry.key(), &*entry.value())) }
+            }
+        })
+    }
+
     /// Returns a mutable reference to the value corresponding to the key.
     ///
     /// The key may be any borrowed form of the map's key type, but
@@ -1399,19 +2290,39 @@ where
     /// map.reserve(10);
     /// ```
     pub fn reserve(&mut self, additional: usize) {
-        self.try_reserve(additional);
+        match self.try_reserve(additional) {
+            Ok(()) => {}
+            Err(TryReserveError::CapacityOverflow) => panic!("capacity overflow"),
+            Err(TryReserveError::AllocError { layout }) => alloc::handle_alloc_error(layout),
+        }
+        self.entry_fastbin.reserve(additional);
+        self.kv_fastbin.reserve(additional);
     }
 
-    pub fn try_reserve(&mut self, additional: usize) {
-        let remaining = self.capacity() - self.len();
+    /// Tries to reserve capacity for at least `additional` more elements to be inserted
+    /// in the `HashMap`. Unlike `reserve`, an error is returned instead of panicking if the
+    /// hash index can not be allocated, and the map is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map: HashMap<&str, isize> = HashMap::new();
+    /// assert!(map.try_reserve(10).is_ok());
+    /// assert!(map.try_reserve(usize::max_value()).is_err());
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let remaining = self.capacity().saturating_sub(self.len());
         if remaining < additional {
-            match self.len().checked_add(additional) {
-                None => panic!("capacity overflow"),
-                Some(min_cap) => self.rehash(min_cap),
-            };
+            let min_cap = self.len()
+                .checked_add(additional)
+                .ok_or(TryReserveError::CapacityOverflow)?;
+            self.hash_table.try_rehash(min_cap)?;
         }
         // we use BST to restore concrete data, so there is no need to do
         // any thing if capacity is equal to len
+        Ok(())
     }
 
     /// Returns true if the map contains a value for the specified key.
@@ -1460,8 +2371,13 @@ where
     /// ```
     #[inline]
     pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
-        self.reserve(1);
         let hash_value = self.make_hash(&key);
+        self.insert_hashed(key, value, hash_value)
+    }
+
+    /// Same as `insert`, with `hash_value` already computed from `key` by the same hasher.
+    fn insert_hashed(&mut self, key: K, value: V, hash_value: HashUint) -> Option<(K, V)> {
+        self.reserve(1);
         let kv_ptr = self.kv_alloc(key, value);
         let new_entry = unsafe {
             self.entry_alloc(
@@ -1482,6 +2398,49 @@ where
         }
     }
 
+    /// Consumes the map and splits it into two: the elements for which `f` returns true, and
+    /// the others. Both maps use a clone of the hasher.
+    ///
+    /// The hash stored with each element is reused, so no key is hashed again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let map: HashMap<_, _> = (0..10).map(|i| (i, i * 10)).collect();
+    /// let (even, odd) = map.split_by(|k, _| k % 2 == 0);
+    /// assert_eq!(even.len(), 5);
+    /// assert_eq!(odd[&3], 30);
+    /// ```
+    pub fn split_by<F>(mut self, mut f: F) -> (HashMap<K, V, S, A>, HashMap<K, V, S, A>)
+    where
+        F: FnMut(&K, &V) -> bool,
+        S: Clone,
+    {
+        let mut maps = (
+            HashMap::with_hasher_in(self.hash_builder.clone(), self.allocator().clone()),
+            HashMap::with_hasher_in(self.hash_builder.clone(), self.allocator().clone()),
+        );
+        maps.0.hash_table.set_load_factor(self.load_factor());
+        maps.1.hash_table.set_load_factor(self.load_factor());
+        let mut entry = self.first();
+        while !entry.is_null() {
+            let next = self.next(entry);
+            let hash_value = entry.node_ptr().hash_val();
+            let map = if f(unsafe { &*entry.key() }, unsafe { &*entry.value() }) {
+                &mut maps.0
+            } else {
+                &mut maps.1
+            };
+            if let Some((k, v)) = self.erase(entry) {
+                map.insert_hashed(k, v, hash_value);
+            }
+            entry = next;
+        }
+        maps
+    }
+
     /// Removes a key from the map, returning the stored key and value if the
     /// key was previously in the map.
     ///
@@ -1513,7 +2472,7 @@ where
     }
 
     /// Creates an empty `HashMap` with the specified capacity, using `hash_builder`
-    /// to hash the keys.
+    /// to hash the keys and allocating memory from `allocator`.
     ///
     /// The hash map will be able to hold at least `capacity` elements without
     /// reallocating. If `capacity` is 0, the hash map will not allocate.
@@ -1527,16 +2486,29 @@ where
     ///
     /// ```
     /// use hash_ord::hash_map::HashMap;
+    /// use hash_ord::Global;
     /// use std::collections::hash_map::RandomState;
     ///
     /// let s = RandomState::new();
-    /// let mut map = HashMap::with_capacity_and_hasher(10, s);
+    /// let mut map = HashMap::with_capacity_and_hasher_in(10, s, Global);
     /// map.insert(1, 2);
     /// ```
-    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> HashMap<K, V, S> {
+    pub fn with_capacity_and_hasher_in(
+        capacity: usize,
+        hash_builder: S,
+        allocator: A,
+    ) -> HashMap<K, V, S, A> {
         let mut hash_map = HashMap {
-            entry_fastbin: Fastbin::new(mem::size_of::<InternalHashEntry<K, V>>()),
-            kv_fastbin: Fastbin::new(mem::size_of::<(K, V)>()),
+            entry_fastbin: Fastbin::new_in(
+                mem::size_of::<InternalHashEntry<K, V>>(),
+                mem::align_of::<InternalHashEntry<K, V>>(),
+                allocator.clone(),
+            ),
+            kv_fastbin: Fastbin::new_in(
+                mem::size_of::<(K, V)>(),
+                mem::align_of::<(K, V)>(),
+                allocator,
+            ),
             hash_table: hash_table::HashTable::new_with_box(),
             hash_builder,
         };
@@ -1544,16 +2516,44 @@ where
         hash_map
     }
 
-    /// Shrinks the capacity of the map as much as possible. It will drop
-    /// down as much as possible while maintaining the internal rules
-    /// and possibly leaving some space in accordance with the resize policy.
+    /// Creates a map from the pairs of `iter`. Unlike `collect`, if a key appears more than once,
+    /// the first pair wins and the later ones are dropped.
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::hash_map::HashMap;
     ///
-    /// let mut map: HashMap<isize, isize> = HashMap::with_capacity(100);
+    /// let pairs = vec![(1, "a"), (2, "b"), (1, "c")];
+    /// let first: HashMap<_, _> = HashMap::from_iter_first_wins(pairs.clone());
+    /// let last: HashMap<_, _> = pairs.into_iter().collect();
+    /// assert_eq!(first[&1], "a");
+    /// assert_eq!(last[&1], "c");
+    /// ```
+    pub fn from_iter_first_wins<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self
+    where
+        S: Default,
+        A: Default,
+    {
+        let iter = iter.into_iter();
+        let mut map =
+            HashMap::with_capacity_and_hasher_in(iter.size_hint().0, S::default(), A::default());
+        for (k, v) in iter {
+            map.entry(k).or_insert(v);
+        }
+        map
+    }
+
+    /// Shrinks the capacity of the map as much as possible. It will drop
+    /// down as much as possible while maintaining the internal rules
+    /// and possibly leaving some space in accordance with the resize policy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map: HashMap<isize, isize> = HashMap::with_capacity(100);
     /// map.insert(1, 2);
     /// map.insert(3, 4);
     /// assert!(map.capacity() >= 100);
@@ -1561,58 +2561,71 @@ where
     /// assert!(map.capacity() >= 2);
     /// ```
     pub fn shrink_to_fit(&mut self) {
-        let limit = hash_table::calc_limit(self.len());
-        let old_cap = self.capacity();
-        let new_cap = {
-            let mut tmp = old_cap / 2;
-            let mut res = old_cap;
-            while tmp >= limit {
-                res = tmp;
-                tmp /= 2;
-            }
-            res
-        };
-        if new_cap >= old_cap {
-            return;
-        }
-        let mut new_entry_fastbin = Fastbin::new(mem::size_of::<InternalHashEntry<K, V>>());
-        let mut new_kv_fastbin = Fastbin::new(mem::size_of::<(K, V)>());
-        let mut new_hash_table = hash_table::HashTable::new_with_box();
-        new_hash_table.rehash(self.len());
-        let mut new_kv_vec = Vec::with_capacity(self.len());
-        {
-            let mut destroy_callback = |(k, v): (K, V)| {
-                let kv_ptr = kv_alloc(&mut new_kv_fastbin, k, v);
-                new_kv_vec.push(kv_ptr);
-            };
-            loop {
-                let node = self.hash_table.pop_first_index();
-                if node.is_null() {
-                    break;
-                }
-                self.recurse_destroy(node, &mut destroy_callback);
-            }
-            debug_assert_eq!(self.hash_table.size(), 0);
-        }
-        for kv_ptr in new_kv_vec {
-            unsafe {
-                let key_ptr = &mut (*kv_ptr).0 as *mut K;
-                let value_ptr = &mut (*kv_ptr).1 as *mut V;
-                let entry = entry_alloc(
-                    &mut new_entry_fastbin,
-                    key_ptr,
-                    value_ptr,
-                    self.make_hash(&(*key_ptr)),
-                );
-                hash_table_update(&mut new_hash_table, entry);
-            }
-        }
-        self.kv_fastbin = new_kv_fastbin;
-        self.entry_fastbin = new_entry_fastbin;
-        self.hash_table = new_hash_table;
+        let len = self.len();
+        self.hash_table.shrink_to(len);
+        self.entry_fastbin.shrink();
+        self.kv_fastbin.shrink();
     }
 }
 
+impl<K, V, S> HashMap<K, V, S>
+where
+    K: Ord + Hash,
+    S: BuildHasher,
+{
+    /// Creates an empty `HashMap` which will use the given hash builder to hash
+    /// keys.
+    ///
+    /// The created map has the default initial capacity.
+    ///
+    /// Warning: `hash_builder` is normally randomly generated, and
+    /// is designed to allow HashMaps to be resistant to attacks that
+    /// cause many collisions and very poor performance. Setting it
+    /// manually using this function can expose a DoS attack vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let s = RandomState::new();
+    /// let mut map = HashMap::with_hasher(s);
+    /// map.insert(1, 2);
+    /// ```
+    #[inline]
+    pub fn with_hasher(hash_builder: S) -> Self {
+        HashMap::with_hasher_in(hash_builder, Global)
+    }
+
+    /// Creates an empty `HashMap` with the specified capacity, using `hash_builder`
+    /// to hash the keys.
+    ///
+    /// The hash map will be able to hold at least `capacity` elements without
+    /// reallocating. If `capacity` is 0, the hash map will not allocate.
+    ///
+    /// Warning: `hash_builder` is normally randomly generated, and
+    /// is designed to allow HashMaps to be resistant to attacks that
+    /// cause many collisions and very poor performance. Setting it
+    /// manually using this function can expose a DoS attack vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let s = RandomState::new();
+    /// let mut map = HashMap::with_capacity_and_hasher(10, s);
+    /// map.insert(1, 2);
+    /// ```
+    #[inline]
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        HashMap::with_capacity_and_hasher_in(capacity, hash_builder, Global)
+    }
+}
+
+#[cfg(feature = "std")]
 impl<K, V> HashMap<K, V, RandomState>
 where
     K: Hash + Ord,
@@ -1633,26 +2646,75 @@ where
         Default::default()
     }
 
+    /// Creates an empty `HashMap` with the specified capacity.
+    ///
+    /// Both the index array and the pages of entries are allocated up front, so the map holds
+    /// `capacity` elements without allocating again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let mut map = HashMap::with_capacity(10);
+    /// let usage = map.memory_usage();
+    /// for i in 0..10 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.memory_usage(), usage);
+    /// ```
     #[inline]
     pub fn with_capacity(capacity: usize) -> HashMap<K, V, RandomState> {
         let mut hash_map = HashMap::<K, V, RandomState>::default();
         hash_map.reserve(capacity);
         hash_map
     }
+
+    /// Creates an empty `HashMap` which keeps at most `max` entries per hash index on average.
+    ///
+    /// A smaller factor uses more indexes and leads to fewer collisions. The default is
+    /// about 0.67, which keeps 1.5 indexes for each entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is not a positive finite number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    /// let mut map = HashMap::with_load_factor(0.5);
+    /// map.insert(1, 2);
+    /// assert_eq!(map.load_factor(), 0.5);
+    /// ```
+    pub fn with_load_factor(max: f64) -> HashMap<K, V, RandomState> {
+        assert!(
+            max > 0.0 && max.is_finite(),
+            "load factor must be a positive finite number"
+        );
+        let mut hash_map = HashMap::<K, V, RandomState>::default();
+        hash_map.hash_table.set_load_factor(max);
+        hash_map
+    }
 }
 
-impl<K, V, S> Default for HashMap<K, V, S>
+impl<K, V, S, A> Default for HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher + Default,
+    A: Alloc + Default,
 {
-    /// Creates an empty `HashMap<K, V, S>`, with the `Default` value for the hasher.
-    fn default() -> HashMap<K, V, S> {
-        HashMap::with_hasher(Default::default())
+    /// Creates an empty `HashMap<K, V, S, A>`, with the `Default` value for the hasher and the
+    /// allocator.
+    fn default() -> HashMap<K, V, S, A> {
+        HashMap::with_hasher_in(S::default(), A::default())
     }
 }
 
-impl<K, V, S> Drop for HashMap<K, V, S> {
+impl<K, V, S, A> Drop for HashMap<K, V, S, A>
+where
+    A: Alloc,
+{
     #[inline]
     fn drop(&mut self) {
         self.clear();
@@ -1666,28 +2728,37 @@ impl<K, V, S> Drop for HashMap<K, V, S> {
 ///
 /// [`drain`]: struct.HashMap.html#method.drain
 /// [`HashMap`]: struct.HashMap.html
-pub struct Drain<'a, K, V, S>
+pub struct Drain<'a, K, V, S, A = Global>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc + 'a,
 {
     entry: *mut InternalHashEntry<K, V>,
-    map: &'a mut HashMap<K, V, S>,
+    shrink: bool,
+    map: &'a mut HashMap<K, V, S, A>,
 }
 
-impl<'a, K, V, S> Drop for Drain<'a, K, V, S>
+impl<'a, K, V, S, A> Drop for Drain<'a, K, V, S, A>
 where
     K: 'a,
     V: 'a,
     S: 'a,
+    A: Alloc,
 {
     fn drop(&mut self) {
-        for _ in self {}
+        for _ in &mut *self {}
+        if self.shrink {
+            self.map.clear_and_shrink();
+        }
     }
 }
 
-impl<'a, K, V, S> Iterator for Drain<'a, K, V, S> {
+impl<'a, K, V, S, A> Iterator for Drain<'a, K, V, S, A>
+where
+    A: Alloc,
+{
     type Item = (K, V);
 
     #[inline]
@@ -1706,18 +2777,22 @@ impl<'a, K, V, S> Iterator for Drain<'a, K, V, S> {
     }
 }
 
-impl<'a, K, V, S> ExactSizeIterator for Drain<'a, K, V, S> {
+impl<'a, K, V, S, A> ExactSizeIterator for Drain<'a, K, V, S, A>
+where
+    A: Alloc,
+{
     #[inline]
     fn len(&self) -> usize {
         self.map.len()
     }
 }
 
-impl<'a, K, Q, V, S> Index<&'a Q> for HashMap<K, V, S>
+impl<'a, K, Q, V, S, A> Index<&'a Q> for HashMap<K, V, S, A>
 where
     Q: ?Sized + Hash + Ord,
     K: Hash + Ord + Borrow<Q>,
     S: BuildHasher,
+    A: Alloc,
 {
     type Output = V;
 
@@ -1732,69 +2807,70 @@ where
     }
 }
 
-impl<K, V, S> Extend<(K, V)> for HashMap<K, V, S>
+impl<K, V, S, A> Extend<(K, V)> for HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
         let iter = iter.into_iter();
-        let reserve = if self.is_empty() {
-            iter.size_hint().0
-        } else {
-            (iter.size_hint().0 + 1) / 2 + self.len()
-        };
-        self.reserve(reserve);
+        // reserve the whole lower bound, so that the index array is resized at most once
+        self.reserve(iter.size_hint().0);
         for (k, v) in iter {
             self.insert(k, v);
         }
     }
 }
 
-impl<'a, K, V, S> Extend<(&'a K, &'a V)> for HashMap<K, V, S>
+impl<'a, K, V, S, A> Extend<(&'a K, &'a V)> for HashMap<K, V, S, A>
 where
     K: Ord + Hash + Copy,
     V: Copy,
     S: BuildHasher,
+    A: Alloc,
 {
     fn extend<T: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: T) {
         self.extend(iter.into_iter().map(|(&key, &value)| (key, value)));
     }
 }
 
-impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S>
+impl<'a, K, V, S, A> IntoIterator for &'a HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     type Item = (&'a K, &'a V);
-    type IntoIter = Iter<'a, K, V, S>;
+    type IntoIter = Iter<'a, K, V, S, A>;
 
-    fn into_iter(self) -> Iter<'a, K, V, S> {
+    fn into_iter(self) -> Iter<'a, K, V, S, A> {
         self.iter()
     }
 }
 
-impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S>
+impl<'a, K, V, S, A> IntoIterator for &'a mut HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     type Item = (&'a K, &'a mut V);
-    type IntoIter = IterMut<'a, K, V, S>;
+    type IntoIter = IterMut<'a, K, V, S, A>;
 
-    fn into_iter(self) -> IterMut<'a, K, V, S> {
+    fn into_iter(self) -> IterMut<'a, K, V, S, A> {
         self.iter_mut()
     }
 }
 
-impl<K, V, S> IntoIterator for HashMap<K, V, S>
+impl<K, V, S, A> IntoIterator for HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     type Item = (K, V);
-    type IntoIter = IntoIter<K, V, S>;
+    type IntoIter = IntoIter<K, V, S, A>;
 
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
@@ -1811,29 +2887,32 @@ where
 ///
 /// [`into_iter`]: struct.HashMap.html#method.into_iter
 /// [`HashMap`]: struct.HashMap.html
-pub struct IntoIter<K, V, S>
+pub struct IntoIter<K, V, S, A = Global>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     entry: *mut InternalHashEntry<K, V>,
-    map: HashMap<K, V, S>,
+    map: HashMap<K, V, S, A>,
 }
 
-impl<K, V, S> Drop for IntoIter<K, V, S>
+impl<K, V, S, A> Drop for IntoIter<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     fn drop(&mut self) {
         for (_, _) in self {}
     }
 }
 
-impl<K, V, S> Iterator for IntoIter<K, V, S>
+impl<K, V, S, A> Iterator for IntoIter<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher,
+    A: Alloc,
 {
     type Item = (K, V);
 
@@ -1851,26 +2930,125 @@ where
     }
 }
 
-impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S>
+impl<K, V, S, A> FromIterator<(K, V)> for HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     S: BuildHasher + Default,
+    A: Alloc + Default,
 {
-    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> HashMap<K, V, S> {
-        let mut map = HashMap::with_hasher(Default::default());
+    /// Creates a map from the pairs of `iter`. If a key appears more than once, the last pair
+    /// wins, as if they were inserted one by one; see `from_iter_first_wins` for the opposite.
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> HashMap<K, V, S, A> {
+        let iter = iter.into_iter();
+        let mut map =
+            HashMap::with_capacity_and_hasher_in(iter.size_hint().0, S::default(), A::default());
         map.extend(iter);
         map
     }
 }
 
-impl<K, V, S> Clone for HashMap<K, V, S>
+impl<'a, K, V, S, A> FromIterator<(&'a K, &'a V)> for HashMap<K, V, S, A>
+where
+    K: Ord + Hash + Copy,
+    V: Copy,
+    S: BuildHasher + Default,
+    A: Alloc + Default,
+{
+    fn from_iter<T: IntoIterator<Item = (&'a K, &'a V)>>(iter: T) -> HashMap<K, V, S, A> {
+        let mut map = HashMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S, A> From<OrdMap<K, V, A>> for HashMap<K, V, S>
+where
+    K: Ord + Hash,
+    S: BuildHasher + Default,
+    A: Alloc,
+{
+    /// Moves all entries of an `OrdMap` into a new `HashMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let ord_map: OrdMap<_, _> = (0..10).map(|i| (i, -i)).collect();
+    /// let map: HashMap<_, _> = HashMap::from(ord_map);
+    /// assert_eq!(map.len(), 10);
+    /// assert_eq!(map[&3], -3);
+    /// ```
+    fn from(ord_map: OrdMap<K, V, A>) -> HashMap<K, V, S> {
+        let mut map = HashMap::with_capacity_and_hasher(ord_map.len(), Default::default());
+        for (k, v) in ord_map {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S, T, A> From<StdHashMap<K, V, T>> for HashMap<K, V, S, A>
+where
+    K: Ord + Hash,
+    S: BuildHasher + Default,
+    T: BuildHasher,
+    A: Alloc + Default,
+{
+    /// Moves all entries of a `std::collections::HashMap` into a new `HashMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    ///
+    /// let std_map: std::collections::HashMap<_, _> = (0..10).map(|i| (i, -i)).collect();
+    /// let map: HashMap<_, _> = HashMap::from(std_map);
+    /// assert_eq!(map.len(), 10);
+    /// assert_eq!(map[&3], -3);
+    /// ```
+    fn from(std_map: StdHashMap<K, V, T>) -> HashMap<K, V, S, A> {
+        let mut map =
+            HashMap::with_capacity_and_hasher_in(std_map.len(), S::default(), A::default());
+        for (k, v) in std_map {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+/// Builds the map by inserting an arbitrary sequence of key-value pairs.
+#[cfg(feature = "fuzz")]
+impl<'a, K, V, S, A> arbitrary::Arbitrary<'a> for HashMap<K, V, S, A>
+where
+    K: arbitrary::Arbitrary<'a> + Ord + Hash,
+    V: arbitrary::Arbitrary<'a>,
+    S: BuildHasher + Default,
+    A: Alloc + Default,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut map = HashMap::default();
+        for kv in u.arbitrary_iter::<(K, V)>()? {
+            let (k, v) = kv?;
+            map.insert(k, v);
+        }
+        Ok(map)
+    }
+}
+
+impl<K, V, S, A> Clone for HashMap<K, V, S, A>
 where
     K: Ord + Hash + Clone,
     V: Clone,
     S: BuildHasher + Clone,
+    A: Alloc,
 {
     fn clone(&self) -> Self {
-        let mut map = HashMap::with_capacity_and_hasher(self.len(), self.hash_builder.clone());
+        let mut map = HashMap::with_hasher_in(self.hash_builder.clone(), self.allocator().clone());
+        map.hash_table.set_load_factor(self.load_factor());
+        map.reserve(self.len());
         for (k, v) in self.iter() {
             map.insert(k.clone(), v.clone());
         }
@@ -1878,13 +3056,14 @@ where
     }
 }
 
-impl<K, V, S> PartialEq for HashMap<K, V, S>
+impl<K, V, S, A> PartialEq for HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     V: PartialEq,
     S: BuildHasher,
+    A: Alloc,
 {
-    fn eq(&self, other: &HashMap<K, V, S>) -> bool {
+    fn eq(&self, other: &HashMap<K, V, S, A>) -> bool {
         if self.len() != other.len() {
             return false;
         }
@@ -1893,12 +3072,34 @@ where
     }
 }
 
-impl<K, V, S> Eq for HashMap<K, V, S>
+impl<K, V, S, A> Eq for HashMap<K, V, S, A>
 where
     K: Ord + Hash,
     V: Eq,
     S: BuildHasher,
+    A: Alloc,
+{
+}
+
+impl<K, V, S, A> Hash for HashMap<K, V, S, A>
+where
+    K: Ord + Hash,
+    V: Hash,
+    S: BuildHasher,
+    A: Alloc,
 {
+    /// Entries are visited in an arbitrary order, so every (key, value) pair is hashed on its
+    /// own by a fixed hasher and the results are combined by XOR.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut res = 0u64;
+        for elt in self.iter() {
+            let mut entry_state = FnvHasher::default();
+            elt.hash(&mut entry_state);
+            res ^= entry_state.finish();
+        }
+        state.write_usize(self.len());
+        state.write_u64(res);
+    }
 }
 
 #[cfg(test)]
@@ -1906,6 +3107,9 @@ mod test {
     use hash_map::HashMap;
     use std::cell::RefCell;
     use hash_map::Entry::*;
+    use std::collections::HashMap as StdHashMap;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
 
     #[test]
     fn test_hash_map() {
@@ -2143,6 +3347,64 @@ mod test {
         assert_eq!(*cnt.borrow(), test_num);
     }
 
+    #[test]
+    fn test_hash_map_shrink_after_remove() {
+        let mut map = HashMap::new();
+        for i in 0..10000 {
+            map.insert(i, i.to_string());
+        }
+        let cap = map.capacity();
+        let usage = map.memory_usage();
+        for i in 0..10000 {
+            if i % 100 != 0 {
+                map.remove(&i);
+            }
+        }
+        map.shrink_to_fit();
+        assert!(map.capacity() < cap);
+        assert!(map.capacity() >= 100);
+        assert!(map.memory_usage() < usage);
+        assert_eq!(map.len(), 100);
+        for i in 0..10000 {
+            assert_eq!(map.get(&i).is_some(), i % 100 == 0);
+        }
+
+        map.clear();
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), 8);
+        map.insert(1, "1".to_string());
+        assert_eq!(map.get(&1).map(|v| v.as_str()), Some("1"));
+    }
+
+    #[test]
+    fn test_hash_map_shrink_to_fit_twice() {
+        let mut map = HashMap::new();
+        for i in 0..10000 {
+            map.insert(i, i);
+        }
+        for i in 100..10000 {
+            map.remove(&i);
+        }
+        map.shrink_to_fit();
+        let usage = map.memory_usage();
+        map.shrink_to_fit();
+        assert_eq!(map.memory_usage(), usage);
+        assert!((0..100).all(|i| map[&i] == i));
+        for i in 0..100 {
+            map.remove(&i);
+        }
+        map.shrink_to_fit();
+        assert!(map.memory_usage() < usage);
+
+        for i in 0..10000 {
+            map.insert(i, i);
+        }
+        map.clear_and_shrink();
+        map.shrink_to_fit();
+        map.insert(1, 1);
+        assert_eq!(map[&1], 1);
+    }
+
     #[test]
     fn test_hash_map_clone_equal() {
         let mut a = HashMap::new();
@@ -2252,4 +3514,710 @@ mod test {
         assert_eq!(map.get(&10).unwrap(), &1000);
         assert_eq!(map.len(), 6);
     }
+
+    #[test]
+    fn test_hash_map_hash() {
+        fn hash<T: Hash>(t: &T) -> u64 {
+            let mut s = DefaultHasher::new();
+            t.hash(&mut s);
+            s.finish()
+        }
+        let mut a = HashMap::new();
+        for i in 0..100 {
+            a.insert(i, -i);
+        }
+        let mut b = HashMap::with_capacity(1000);
+        for i in (0..100).rev() {
+            b.insert(i, -i);
+        }
+        assert!(a == b);
+        assert_eq!(hash(&a), hash(&b));
+        b.insert(0, 1);
+        assert_ne!(hash(&a), hash(&b));
+
+        let mut outer = StdHashMap::new();
+        outer.insert(a, 1);
+        b.insert(0, 0);
+        assert_eq!(outer.get(&b), Some(&1));
+    }
+
+    #[test]
+    fn test_hash_map_memory_usage() {
+        let mut m = HashMap::new();
+        let empty = m.memory_usage();
+        assert!(empty > 0);
+        let mut last = empty;
+        for i in 0..1000 {
+            m.insert(i, i);
+            assert!(m.memory_usage() >= last);
+            last = m.memory_usage();
+        }
+        assert!(last > empty + 1000 * 2 * ::std::mem::size_of::<i32>());
+        for i in 0..1000 {
+            m.remove(&i);
+        }
+        assert_eq!(m.entry_fastbin.free_list_len(), 1000);
+        assert_eq!(m.kv_fastbin.free_list_len(), 1000);
+        assert_eq!(m.memory_usage(), last);
+    }
+
+    #[test]
+    fn test_hash_map_rehash_many() {
+        let mut m = HashMap::new();
+        let mut last_cap = m.capacity();
+        let mut rehashes = 0;
+        for i in 0..20000 {
+            m.insert(i, i.to_string());
+            if m.capacity() != last_cap {
+                last_cap = m.capacity();
+                rehashes += 1;
+            }
+        }
+        assert!(rehashes > 3);
+        for i in 0..20000 {
+            assert_eq!(m.get(&i).map(|v| v.as_str()), Some(i.to_string().as_str()));
+        }
+        m.shrink_to_fit();
+        drop(m);
+    }
+
+    #[test]
+    fn test_hash_map_load_factor() {
+        let mut default = HashMap::new();
+        let mut sparse = HashMap::with_load_factor(0.5);
+        let mut dense = HashMap::with_load_factor(4.0);
+        for i in 0..10000 {
+            default.insert(i, i);
+            sparse.insert(i, i);
+            dense.insert(i, i);
+        }
+        assert_eq!(default.load_factor(), ::hash_table::DEFAULT_LOAD_FACTOR);
+        assert!(sparse.capacity() > default.capacity());
+        assert!(dense.capacity() < default.capacity());
+        assert!(sparse.get_max_node_of_single_index() <= default.get_max_node_of_single_index());
+        for i in 0..10000 {
+            assert_eq!(sparse.get(&i), Some(&i));
+            assert_eq!(dense.get(&i), Some(&i));
+        }
+        assert_eq!(sparse.clone().capacity(), sparse.capacity());
+        sparse.shrink_to_fit();
+        assert_eq!(sparse.load_factor(), 0.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hash_map_load_factor_zero() {
+        let _: HashMap<i32, i32> = HashMap::with_load_factor(0.0);
+    }
+
+    #[test]
+    fn test_hash_map_bucket_depths() {
+        use std::hash::{BuildHasherDefault, Hasher};
+
+        #[derive(Default)]
+        struct ConstHasher;
+        impl Hasher for ConstHasher {
+            fn finish(&self) -> u64 {
+                7
+            }
+            fn write(&mut self, _: &[u8]) {}
+        }
+
+        let mut m = HashMap::with_hasher(BuildHasherDefault::<ConstHasher>::default());
+        assert_eq!(m.bucket_depths().count(), 0);
+        for i in 0..1000 {
+            m.insert(i, i);
+        }
+        let depths: Vec<_> = m.bucket_depths().collect();
+        assert_eq!(depths, vec![1000]);
+        assert_eq!(m.get_max_node_of_single_index(), 1000);
+
+        let mut m = HashMap::new();
+        for i in 0..1000 {
+            m.insert(i, i);
+        }
+        assert_eq!(m.bucket_depths().sum::<i32>() as usize, m.len());
+        assert_eq!(m.bucket_depths().max(), Some(m.get_max_node_of_single_index()));
+    }
+
+    #[test]
+    fn test_hash_map_try_reserve_fail() {
+        use hash_table::FAIL_INDEX_ALLOC;
+        use super::TryReserveError;
+
+        let mut m = HashMap::new();
+        for i in 0..100 {
+            m.insert(i, i);
+        }
+        let cap = m.capacity();
+        FAIL_INDEX_ALLOC.with(|fail| fail.set(true));
+        match m.try_reserve(10000) {
+            Err(TryReserveError::AllocError { .. }) => {}
+            _ => panic!("try_reserve should fail"),
+        }
+        assert!(m.hash_table.try_rehash(10000).is_err());
+        FAIL_INDEX_ALLOC.with(|fail| fail.set(false));
+        assert_eq!(m.capacity(), cap);
+        assert_eq!(m.len(), 100);
+        for i in 0..100 {
+            assert_eq!(m.get(&i), Some(&i));
+        }
+        m.insert(100, 100);
+        assert_eq!(m.remove(&0), Some((0, 0)));
+        assert_eq!(m.len(), 100);
+        assert_eq!(
+            m.try_reserve(usize::max_value()),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert!(m.try_reserve(10000).is_ok());
+        assert!(m.capacity() > cap);
+    }
+
+    #[test]
+    fn test_hash_map_fold_hash() {
+        use hash_table::fold_hash;
+
+        assert_eq!(fold_hash(0x1234_5678_9abc_def0, 64), 0x1234_5678_9abc_def0);
+        // hash values which only differ in the high 32 bits
+        let mask = 1023u64;
+        let low = 0x9abc_def0u64;
+        let mut truncated = vec![];
+        let mut folded = vec![];
+        for high in 0..1000u64 {
+            let hash = (high.wrapping_mul(0x9e37_79b9) << 32) | low;
+            truncated.push(hash as u32 as u64 & mask);
+            folded.push(fold_hash(hash, 32) as u32 as u64 & mask);
+        }
+        truncated.sort();
+        truncated.dedup();
+        folded.sort();
+        folded.dedup();
+        assert_eq!(truncated.len(), 1);
+        assert!(folded.len() > 500);
+    }
+
+    #[test]
+    fn test_hash_map_reserve() {
+        let mut m = HashMap::new();
+        m.reserve(1000);
+        let usage = m.memory_usage();
+        for i in 0..1000 {
+            m.insert(i, i);
+        }
+        assert_eq!(m.memory_usage(), usage);
+    }
+
+    #[test]
+    fn test_hash_map_align() {
+        #[repr(align(64))]
+        struct Aligned(u8);
+        let mut m = HashMap::new();
+        for i in 0..1000 {
+            m.insert(i, Aligned(i as u8));
+        }
+        for (_, v) in m.iter() {
+            assert_eq!(v as *const Aligned as usize % 64, 0);
+        }
+        m.shrink_to_fit();
+        for (k, v) in m.iter() {
+            assert_eq!(v as *const Aligned as usize % 64, 0);
+            assert_eq!(v.0, *k as u8);
+        }
+    }
+
+    #[test]
+    fn test_hash_map_from_std() {
+        let std_map: StdHashMap<i32, i32> = (0..1000).map(|i| (i, -i)).collect();
+        let map: HashMap<i32, i32> = HashMap::from(std_map.clone());
+        assert_eq!(map.len(), std_map.len());
+        for (k, v) in std_map.iter() {
+            assert_eq!(map.get(k), Some(v));
+        }
+        assert_eq!(map.get(&1000), None);
+    }
+
+    #[test]
+    fn test_from_iter_ref() {
+        let xs = [(1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6)];
+        let map: HashMap<_, _> = xs.iter().cloned().collect();
+        let copied: HashMap<i32, i32> = map.iter().collect();
+        assert!(copied == map);
+        assert_eq!(copied.len(), 6);
+    }
+
+    #[test]
+    fn test_hash_map_get_or_insert_with() {
+        let mut map = HashMap::new();
+        let mut called = false;
+        *map.get_or_insert_with(1, || {
+            called = true;
+            10
+        }) += 1;
+        assert!(called);
+        assert_eq!(map[&1], 11);
+
+        called = false;
+        assert_eq!(
+            *map.get_or_insert_with(1, || {
+                called = true;
+                0
+            }),
+            11
+        );
+        assert!(!called);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_hash_map_extend_single_rehash() {
+        use hash_table::REHASH_COUNT;
+
+        let n = 100_000;
+        let mut m = HashMap::new();
+        let before = REHASH_COUNT.with(|count| count.get());
+        m.extend((0..n).map(|i| (i, i)));
+        assert!(REHASH_COUNT.with(|count| count.get()) - before <= 1);
+        assert_eq!(m.len(), n);
+
+        let before = REHASH_COUNT.with(|count| count.get());
+        let m: HashMap<_, _> = (0..n).map(|i| (i, i)).collect();
+        assert!(REHASH_COUNT.with(|count| count.get()) - before <= 1);
+        for i in 0..n {
+            assert_eq!(m.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_hash_map_hasher() {
+        use hash_table;
+        use std::collections::hash_map::RandomState;
+        use std::hash::BuildHasher;
+
+        let map: HashMap<&str, i32, _> = HashMap::with_hasher(RandomState::new());
+        let mut state = map.hasher().build_hasher();
+        "key".hash(&mut state);
+        let mut expected = map.hash_builder.build_hasher();
+        "key".hash(&mut expected);
+        assert_eq!(state.finish(), expected.finish());
+        assert_eq!(hash_table::make_hash(map.hasher(), "key"), map.make_hash("key"));
+
+        let mut other: HashMap<&str, i32, _> = HashMap::with_hasher(map.hasher().clone());
+        other.insert("key", 1);
+        assert_eq!(other.make_hash("key"), map.make_hash("key"));
+    }
+
+    #[test]
+    fn test_hash_map_clear_and_shrink() {
+        let mut map = HashMap::new();
+        let init_capacity = map.capacity();
+        let empty = map.memory_usage();
+        for i in 0..10000 {
+            map.insert(i, i.to_string());
+        }
+        assert!(map.capacity() > init_capacity);
+        map.clear();
+        assert!(map.capacity() > init_capacity);
+        map.clear_and_shrink();
+        assert!(map.is_empty());
+        assert_eq!(map.capacity(), init_capacity);
+        assert!(map.memory_usage() <= empty);
+        for i in 0..100 {
+            map.insert(i, i.to_string());
+        }
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.get(&42), Some(&"42".to_string()));
+    }
+
+    #[test]
+    fn test_hash_map_shrink_to_fit_tight() {
+        let n = 1_000_000;
+        let mut map = HashMap::with_capacity(n);
+        for i in 0..n {
+            map.insert(i, i);
+        }
+        for i in 1000..n {
+            map.remove(&i);
+        }
+        map.shrink_to_fit();
+        // the smallest power of two not below 1000 / DEFAULT_LOAD_FACTOR
+        assert_eq!(map.capacity(), 2048);
+        for i in 3..1000 {
+            map.remove(&i);
+        }
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), HashMap::<usize, usize>::new().capacity());
+        assert_eq!(map.len(), 3);
+        for i in 0..3 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        assert_eq!(map.get(&3), None);
+        assert_eq!(map.iter().count(), 3);
+        map.insert(3, 3);
+        assert_eq!(map.get(&3), Some(&3));
+    }
+
+    #[test]
+    fn test_hash_map_get_mut_or_insert_default() {
+        let mut map: HashMap<i32, Vec<i32>> = HashMap::new();
+        for i in 0..30 {
+            map.get_mut_or_insert_default(i % 3).push(i);
+        }
+        assert_eq!(map.len(), 3);
+        for (k, v) in map.iter() {
+            assert_eq!(*v, (0..30).filter(|i| i % 3 == *k).collect::<Vec<_>>());
+        }
+        assert!(map.get_mut_or_insert_default(7).is_empty());
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn test_hash_map_collect_vec_single_rehash() {
+        use hash_table::REHASH_COUNT;
+
+        let pairs: Vec<_> = (0..100_000).map(|i| (i, i * 2)).collect();
+        let before = REHASH_COUNT.with(|count| count.get());
+        let map: HashMap<_, _> = pairs.into_iter().collect();
+        assert!(REHASH_COUNT.with(|count| count.get()) - before <= 1);
+        assert_eq!(map.len(), 100_000);
+        assert!(map.capacity() >= 100_000);
+        assert_eq!(map.get(&4242), Some(&8484));
+    }
+
+    #[test]
+    fn test_hash_map_values_mut_rev() {
+        let mut a: HashMap<i32, i32> = (0..1000).map(|i| (i, i)).collect();
+        let mut b = a.clone();
+        assert_eq!(a.values_mut().len(), 1000);
+        for v in a.values_mut() {
+            *v *= 3;
+        }
+        for v in b.values_mut().rev() {
+            *v *= 3;
+        }
+        assert!(a == b);
+
+        // alternating both ends visits every value exactly once
+        {
+            let mut values = b.values_mut();
+            for cnt in 0..1000 {
+                let v = if cnt % 2 == 0 {
+                    values.next()
+                } else {
+                    values.next_back()
+                };
+                *v.unwrap() += 1;
+                assert_eq!(values.len(), 999 - cnt);
+            }
+            assert!(values.next().is_none());
+            assert!(values.next_back().is_none());
+        }
+        for i in 0..1000 {
+            assert_eq!(b[&i], i * 3 + 1);
+        }
+    }
+
+    #[test]
+    fn test_hash_map_find_ref() {
+        let map: HashMap<String, usize> = (0..1000).map(|i| (i.to_string(), i)).collect();
+        let handle = map.find_ref("42").unwrap();
+        assert_eq!(handle.get(), (&"42".to_string(), &42));
+
+        // lookups and iterations do not invalidate the handle nor the iterator
+        let mut iter = map.iter();
+        let (first_key, first_value) = iter.next().unwrap();
+        for i in 0..1000 {
+            assert_eq!(map.get(&i.to_string()), Some(&i));
+            assert!(map.contains_key(i.to_string().as_str()));
+        }
+        assert_eq!(iter.count(), 999);
+        assert_eq!(map.get(first_key), Some(first_value));
+        let copied = handle;
+        assert_eq!(copied.key(), "42");
+        assert_eq!(*handle.value(), 42);
+        assert!(map.find_ref("1000").is_none());
+    }
+
+    #[test]
+    fn test_hash_map_entry_rehash_count() {
+        use hash_table::{REHASH_CALL_COUNT, REHASH_COUNT};
+
+        let n = 1_000_000;
+        let mut m = HashMap::new();
+        let before = REHASH_COUNT.with(|count| count.get());
+        let calls_before = REHASH_CALL_COUNT.with(|count| count.get());
+        for i in 0..n {
+            *m.entry(i).or_insert(0) += i;
+        }
+        // the index array doubles each time, so growing from the inline index to `n`
+        // entries takes about log2(n) rehashes
+        let rehashes = REHASH_COUNT.with(|count| count.get()) - before;
+        assert!(rehashes > 0 && rehashes <= 20);
+        // and inserts below the grow threshold do not call into the table at all
+        let calls = REHASH_CALL_COUNT.with(|count| count.get()) - calls_before;
+        assert_eq!(calls, rehashes);
+        assert_eq!(m.len(), n);
+        for i in (0..n).step_by(997) {
+            assert_eq!(m[&i], i);
+        }
+    }
+
+    #[test]
+    fn test_hash_map_get_many_key_value() {
+        let mut map = HashMap::new();
+        for i in 0..100 {
+            map.insert(i.to_string(), i);
+        }
+        let [a, b, c] = map.get_many_key_value(["7", "100", "42"]);
+        assert_eq!(a, Some((&"7".to_string(), &7)));
+        assert_eq!(b, None);
+        assert_eq!(c.map(|(k, v)| (k.as_str(), *v)), Some(("42", 42)));
+        assert_eq!(map.get_many_key_value(["-1", "-2"]), [None, None]);
+        let empty: [&str; 0] = [];
+        assert_eq!(map.get_many_key_value(empty), []);
+    }
+
+    #[test]
+    fn test_hash_map_try_get_or_insert_with() {
+        let mut map = HashMap::new();
+        *map.try_get_or_insert_with(1, || Ok::<_, ()>(10)).unwrap() += 1;
+        assert_eq!(map[&1], 11);
+        assert_eq!(
+            map.try_get_or_insert_with(1, || -> Result<i32, ()> { unreachable!() }),
+            Ok(&mut 11)
+        );
+
+        let capacity = map.capacity();
+        assert_eq!(map.try_get_or_insert_with(2, || Err("fail")), Err("fail"));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.capacity(), capacity);
+        assert!(!map.contains_key(&2));
+        // the vacant slot is still usable afterwards
+        assert_eq!(map.try_get_or_insert_with(2, || Ok::<_, ()>(20)), Ok(&mut 20));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_hash_map_with_capacity_fastbin() {
+        let n = 10000;
+        let mut m = HashMap::with_capacity(n);
+        let entry_bytes = m.entry_fastbin.allocated_bytes();
+        let kv_bytes = m.kv_fastbin.allocated_bytes();
+        assert!(entry_bytes > 0 && kv_bytes > 0);
+        let capacity = m.capacity();
+        for i in 0..n {
+            m.insert(i, i.to_string());
+        }
+        assert_eq!(m.entry_fastbin.allocated_bytes(), entry_bytes);
+        assert_eq!(m.kv_fastbin.allocated_bytes(), kv_bytes);
+        assert_eq!(m.capacity(), capacity);
+
+        let m: HashMap<usize, String> = HashMap::with_capacity(0);
+        assert_eq!(m.entry_fastbin.allocated_bytes(), 0);
+        assert_eq!(m.kv_fastbin.allocated_bytes(), 0);
+    }
+
+    #[test]
+    fn test_hash_map_drain_and_shrink() {
+        let n: usize = 1_000_000;
+        let empty: HashMap<usize, usize> = HashMap::new();
+        let mut map: HashMap<_, _> = (0..n).map(|i| (i, i)).collect();
+        let capacity = map.capacity();
+        let mut sum = 0;
+        for (k, v) in map.drain_and_shrink() {
+            assert_eq!(k, v);
+            sum += v;
+        }
+        assert_eq!(sum, n * (n - 1) / 2);
+        assert!(map.is_empty());
+        assert!(map.capacity() < capacity);
+        assert_eq!(map.capacity(), empty.capacity());
+        assert_eq!(map.memory_usage(), empty.memory_usage());
+
+        // dropping the iterator early still drains and shrinks
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.drain_and_shrink().take(10).count(), 10);
+        assert!(map.is_empty());
+        assert_eq!(map.capacity(), empty.capacity());
+        map.insert(1, 1);
+        assert_eq!(map[&1], 1);
+
+        // plain drain keeps the memory
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        let capacity = map.capacity();
+        map.drain();
+        assert_eq!(map.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_hash_map_retain_keys() {
+        struct Node<'a> {
+            b: &'a RefCell<i32>,
+        }
+        impl<'a> Drop for Node<'a> {
+            fn drop(&mut self) {
+                *self.b.borrow_mut() += 1;
+            }
+        }
+        let cnt = RefCell::new(0);
+        let mut map = HashMap::new();
+        for i in 0..1000 {
+            map.insert(i, Node { b: &cnt });
+        }
+        let expected: Vec<_> = map.keys().cloned().filter(|k| k % 2 == 0).collect();
+        map.retain_keys(|k| k % 2 == 0);
+        assert_eq!(*cnt.borrow(), 500);
+        assert_eq!(map.len(), 500);
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), expected);
+        map.retain_keys(|_| true);
+        assert_eq!(map.len(), 500);
+        map.retain_keys(|_| false);
+        assert!(map.is_empty());
+        assert_eq!(*cnt.borrow(), 1000);
+    }
+
+    #[test]
+    fn test_hash_map_split_by() {
+        use std::cell::Cell;
+        use std::hash::BuildHasher;
+        use std::rc::Rc;
+
+        #[derive(Clone, Default)]
+        struct CountingState(Rc<Cell<usize>>);
+
+        impl BuildHasher for CountingState {
+            type Hasher = DefaultHasher;
+
+            fn build_hasher(&self) -> DefaultHasher {
+                self.0.set(self.0.get() + 1);
+                DefaultHasher::new()
+            }
+        }
+
+        let state = CountingState::default();
+        let mut map = HashMap::with_hasher(state.clone());
+        for i in 0..1000 {
+            map.insert(i, i.to_string());
+        }
+        let hashed = state.0.get();
+        let (even, odd) = map.split_by(|k, _| k % 2 == 0);
+        assert_eq!(state.0.get(), hashed);
+        assert_eq!(even.len(), 500);
+        assert_eq!(odd.len(), 500);
+        for i in 0..1000 {
+            let (half, other) = if i % 2 == 0 { (&even, &odd) } else { (&odd, &even) };
+            assert_eq!(half.get(&i), Some(&i.to_string()));
+            assert!(!other.contains_key(&i));
+        }
+
+        let (all, none) = even.split_by(|_, _| true);
+        assert_eq!(all.len(), 500);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_hash_map_from_iter_first_wins() {
+        let pairs = vec![(1, "a"), (1, "b")];
+        let last: HashMap<_, _> = pairs.iter().cloned().collect();
+        let first: HashMap<_, _> = HashMap::from_iter_first_wins(pairs);
+        assert_eq!(last.len(), 1);
+        assert_eq!(first.len(), 1);
+        assert_eq!(last[&1], "b");
+        assert_eq!(first[&1], "a");
+
+        let first: HashMap<_, _> =
+            HashMap::from_iter_first_wins((0..1000).chain(0..1000).map(|i| (i % 100, i)));
+        assert_eq!(first.len(), 100);
+        for i in 0..100 {
+            assert_eq!(first[&i], i);
+        }
+    }
+
+    #[test]
+    fn test_hash_map_iter_sorted() {
+        let mut pairs: Vec<_> = (0..1000).map(|i| ((i * 7919) % 1000, i.to_string())).collect();
+        let map: HashMap<_, _> = pairs.iter().cloned().collect();
+        pairs.sort();
+        let sorted: Vec<_> = map.iter_sorted().map(|(k, v)| (*k, v.clone())).collect();
+        assert_eq!(sorted, pairs);
+        assert_eq!(HashMap::<i32, i32>::new().iter_sorted().count(), 0);
+    }
+
+    #[test]
+    fn test_hash_map_entry_ptr() {
+        let mut map = HashMap::new();
+        for i in 0..100 {
+            map.insert(i, i.to_string());
+        }
+        let (k, v) = unsafe { map.entry_ptr(&50).unwrap() };
+        unsafe {
+            assert_eq!(*k, 50);
+            assert_eq!(*v, "50");
+        }
+
+        // growing the map rehashes, but the cell of the entry stays put
+        for i in 100..10_000 {
+            map.insert(i, i.to_string());
+        }
+        map.get_mut(&50).unwrap().push('!');
+        unsafe {
+            assert_eq!(*k, 50);
+            assert_eq!(*v, "50!");
+            (*v).push('?');
+        }
+        assert_eq!(map.get(&50).map(|v| v.as_str()), Some("50!?"));
+        assert_eq!(unsafe { map.entry_ptr(&50) }, Some((k, v)));
+        assert!(unsafe { map.entry_ptr(&-1) }.is_none());
+    }
+
+    #[test]
+    fn test_hash_map_allocator() {
+        use fastbin::{Alloc, Global};
+        use std::alloc::Layout;
+        use std::cell::Cell;
+        use std::collections::hash_map::RandomState;
+
+        #[derive(Clone)]
+        struct CountingAlloc<'a> {
+            alloc_cnt: &'a Cell<usize>,
+            dealloc_cnt: &'a Cell<usize>,
+        }
+
+        unsafe impl<'a> Alloc for CountingAlloc<'a> {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                self.alloc_cnt.set(self.alloc_cnt.get() + 1);
+                Global.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                self.dealloc_cnt.set(self.dealloc_cnt.get() + 1);
+                Global.dealloc(ptr, layout)
+            }
+        }
+
+        let (alloc_cnt, dealloc_cnt) = (Cell::new(0), Cell::new(0));
+        let allocator = CountingAlloc {
+            alloc_cnt: &alloc_cnt,
+            dealloc_cnt: &dealloc_cnt,
+        };
+        let mut map = HashMap::with_hasher_in(RandomState::new(), allocator);
+        for i in 0..1000 {
+            map.insert(i, -i);
+        }
+        assert!(alloc_cnt.get() > 0);
+        assert_eq!(dealloc_cnt.get(), 0);
+
+        let cloned = map.clone();
+        let (even, odd) = map.split_by(|k, _| k % 2 == 0);
+        assert_eq!(even.len(), 500);
+        assert_eq!(odd[&3], -3);
+        assert_eq!(cloned.len(), 1000);
+        drop(even);
+        drop(odd);
+        drop(cloned);
+        assert_eq!(alloc_cnt.get(), dealloc_cnt.get());
+    }
 }
@@ -1,21 +1,48 @@
-use std::marker;
-use std::mem;
-use std::ptr;
+use core::marker;
+use core::mem;
+use core::ptr;
 use avl_node::{AVLNode, AVLNodePtr, AVLNodePtrBase, AVLRoot, AVLRootPtr};
 use avl_node;
 use list::{ListHead, ListHeadPtr, ListHeadPtrFn};
-use std::hash::Hash;
-use std::hash::BuildHasher;
-use std::hash::Hasher;
-use std::cmp;
-use std::borrow::Borrow;
-use std::cmp::Ordering;
-use libc::{c_void, free, malloc};
+use core::hash::Hash;
+use core::hash::BuildHasher;
+use core::hash::Hasher;
+use core::cmp;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use alloc::alloc::{self, Layout};
+use alloc::boxed::Box;
+use fastbin::{Alloc, Global};
+#[cfg(test)]
+use core::cell::Cell;
 
 pub type HashUint = usize;
 
 const AVL_HASH_INIT_SIZE: usize = 8;
 
+/// The error type for `try_reserve` methods.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TryReserveError {
+    /// The required capacity exceeds the maximum of `usize`.
+    CapacityOverflow,
+    /// The allocator returned an error.
+    AllocError { layout: Layout },
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Make the index allocation of current thread fail, for testing purpose.
+    pub static FAIL_INDEX_ALLOC: Cell<bool> = Cell::new(false);
+    /// Number of times the index array of current thread grew, for testing purpose.
+    pub static REHASH_COUNT: Cell<usize> = Cell::new(0);
+    /// Number of calls of `try_rehash` in current thread, including those which do not grow the
+    /// index array, for testing purpose.
+    pub static REHASH_CALL_COUNT: Cell<usize> = Cell::new(0);
+}
+
+/// Default maximum number of entries per index, which keeps 1.5 indexes for each entry.
+pub const DEFAULT_LOAD_FACTOR: f64 = 2.0 / 3.0;
+
 const DEFAULT_AVL_NODE: AVLNode = AVLNode {
     left: ptr::null_mut(),
     right: ptr::null_mut(),
@@ -134,6 +161,7 @@ pub struct HashTable<K, V> {
     head: ListHead,
     index: *mut HashIndex,
     init: [HashIndex; AVL_HASH_INIT_SIZE],
+    load_factor: f64,
     _marker: marker::PhantomData<(K, V)>,
 }
 
@@ -156,22 +184,34 @@ where
 {
     let mut state = hash_state.build_hasher();
     t.hash(&mut state);
-    state.finish() as HashUint
+    fold_hash(state.finish(), mem::size_of::<HashUint>() * 8) as HashUint
 }
 
+/// Fold the upper bits of `hash` into the lower `bits` bits, so that every bit of the hasher
+/// output still influences the index when `HashUint` is narrower than 64 bits.
 #[inline]
-pub fn calc_limit(capacity: usize) -> usize {
-    capacity.saturating_mul(6usize) / 4usize
+pub fn fold_hash(hash: u64, bits: usize) -> u64 {
+    if bits >= 64 {
+        hash
+    } else {
+        hash ^ (hash >> bits)
+    }
 }
 
 #[inline]
-pub unsafe fn find_duplicate_hash_node<K>(
+pub fn calc_limit(capacity: usize, load_factor: f64) -> usize {
+    (capacity as f64 * (1.0 / load_factor)) as usize
+}
+
+#[inline]
+pub unsafe fn find_duplicate_hash_node<K, Q: ?Sized>(
     mut link: *mut AVLNodePtr,
-    new_key: *mut K,
+    new_key: *const Q,
     hash_val: HashUint,
 ) -> (*mut HashNode<K>, AVLNodePtr, *mut AVLNodePtr)
 where
-    K: Ord,
+    K: Borrow<Q>,
+    Q: Ord,
 {
     let mut parent = ptr::null_mut();
     while !(*link).is_null() {
@@ -185,7 +225,7 @@ where
                 &mut (*parent).right
             };
         } else {
-            match (*new_key).cmp(&(*snode.key_ptr())) {
+            match (*new_key).cmp((*snode.key_ptr()).borrow()) {
                 Ordering::Equal => {
                     return (snode, parent, link);
                 }
@@ -288,31 +328,59 @@ where
 
     #[inline]
     pub fn rehash(&mut self, len: usize) {
+        match self.try_rehash(len) {
+            Ok(()) => {}
+            Err(TryReserveError::CapacityOverflow) => panic!("capacity overflow"),
+            Err(TryReserveError::AllocError { layout }) => alloc::handle_alloc_error(layout),
+        }
+    }
+
+    /// Same as `rehash`, but returns an error instead of panicking if the index array can not
+    /// be allocated. The table is left untouched on error.
+    pub fn try_rehash(&mut self, len: usize) -> Result<(), TryReserveError> {
+        #[cfg(test)]
+        REHASH_CALL_COUNT.with(|count| count.set(count.get() + 1));
         let old_index_size = self.index_size;
-        let limit = calc_limit(len);
+        let limit = calc_limit(len, self.load_factor);
         if old_index_size >= limit {
-            return;
+            return Ok(());
         }
         let mut need = old_index_size;
         while need < limit {
-            need = need.saturating_mul(2usize);
+            need = need
+                .checked_mul(2usize)
+                .ok_or(TryReserveError::CapacityOverflow)?;
         }
-        let buffer = unsafe {
-            let (new_alloc_size, oflo) = need.overflowing_mul(mem::size_of::<HashIndex>());
-            if oflo {
-                panic!("capacity overflow");
-            }
-            let buffer = malloc(new_alloc_size) as *mut HashIndex;
-            if buffer.is_null() {
-                panic!("memory overflow");
-            }
-            buffer
+        let buffer = try_alloc_index(need)?;
+        #[cfg(test)]
+        REHASH_COUNT.with(|count| count.set(count.get() + 1));
+        let data_ptr = self.hash_swap(buffer, need);
+        if !data_ptr.is_null() {
+            dealloc_index(data_ptr, old_index_size);
+        }
+        Ok(())
+    }
+
+    /// Shrink the index array to the smallest size which is able to hold `target_len` entries,
+    /// but never below the current number of entries.
+    pub fn shrink_to(&mut self, target_len: usize) {
+        let old_index_size = self.index_size;
+        let limit = calc_limit(cmp::max(target_len, self.count), self.load_factor);
+        let mut need = AVL_HASH_INIT_SIZE;
+        while need < limit {
+            need *= 2;
+        }
+        if need >= old_index_size {
+            return;
+        }
+        let buffer = if need == AVL_HASH_INIT_SIZE {
+            self.init.as_mut_ptr()
+        } else {
+            alloc_index(need)
         };
         let data_ptr = self.hash_swap(buffer, need);
         if !data_ptr.is_null() {
-            unsafe {
-                free(data_ptr as *mut c_void);
-            }
+            dealloc_index(data_ptr, old_index_size);
         }
     }
 
@@ -344,7 +412,7 @@ impl<K, V> HashTable<K, V> {
             return ptr::null_mut();
         }
         let (duplicate, parent, link) =
-            find_duplicate_hash_node(link, new_node.key_ptr(), hash_val);
+            find_duplicate_hash_node::<K, K>(link, new_node.key_ptr(), hash_val);
         if !duplicate.is_null() {
             avl_node::avl_node_replace(
                 duplicate.avl_node_ptr(),
@@ -372,11 +440,52 @@ impl<K, V> HashTable<K, V> {
         self.index_size
     }
 
+    /// Return whether holding `len` entries needs a bigger index array. This is the same test
+    /// `try_rehash` starts with, kept inline so the insert path can skip the call entirely.
+    #[inline]
+    pub fn need_rehash(&self, len: usize) -> bool {
+        calc_limit(len, self.load_factor) > self.index_size
+    }
+
+    /// Return the start of the index array, which holds `index_size` indexes.
+    #[inline]
+    pub fn index_ptr(&self) -> *mut HashIndex {
+        self.index
+    }
+
     #[inline]
     pub fn capacity(&self) -> usize {
         self.index_size
     }
 
+    #[inline]
+    pub fn load_factor(&self) -> f64 {
+        self.load_factor
+    }
+
+    #[inline]
+    pub fn set_load_factor(&mut self, load_factor: f64) {
+        self.load_factor = load_factor;
+    }
+
+    /// Release the index array of an empty table, and fall back to the inline one.
+    pub fn reset_index(&mut self) {
+        debug_assert_eq!(self.count, 0);
+        if self.index != self.init.as_mut_ptr() {
+            dealloc_index(self.index, self.index_size);
+        }
+        self.init();
+    }
+
+    /// Return the size in bytes of the table, including the index array.
+    pub fn allocated_bytes(&self) -> usize {
+        let mut bytes = mem::size_of::<Self>();
+        if !ptr::eq(self.index, self.init.as_ptr()) {
+            bytes += self.index_size * mem::size_of::<HashIndex>();
+        }
+        bytes
+    }
+
     pub fn get_max_node_of_single_index(&self) -> i32 {
         let mut head = self.head.next;
         let mut num = 0;
@@ -390,6 +499,15 @@ impl<K, V> HashTable<K, V> {
         num
     }
 
+    /// Return an iterator over the node count of every non-empty `HashIndex`.
+    #[inline]
+    pub fn bucket_depths(&self) -> BucketDepths<K, V> {
+        BucketDepths {
+            head: self.head.next,
+            table: self,
+        }
+    }
+
     #[inline]
     pub fn pop_first_index(&mut self) -> AVLNodePtr {
         let head = self.head.next;
@@ -427,6 +545,7 @@ impl<K, V> HashTable<K, V> {
             head: Default::default(),
             index: ptr::null_mut(),
             init: [HashIndex::default(); AVL_HASH_INIT_SIZE],
+            load_factor: DEFAULT_LOAD_FACTOR,
             _marker: marker::PhantomData,
         }
     }
@@ -545,12 +664,64 @@ impl<K, V> HashTable<K, V> {
     }
 }
 
+#[inline]
+fn index_layout(index_size: usize) -> Result<Layout, TryReserveError> {
+    index_size
+        .checked_mul(mem::size_of::<HashIndex>())
+        .and_then(|size| Layout::from_size_align(size, mem::align_of::<HashIndex>()).ok())
+        .ok_or(TryReserveError::CapacityOverflow)
+}
+
+fn try_alloc_index(index_size: usize) -> Result<*mut HashIndex, TryReserveError> {
+    let layout = index_layout(index_size)?;
+    #[cfg(test)]
+    {
+        if FAIL_INDEX_ALLOC.with(|fail| fail.get()) {
+            return Err(TryReserveError::AllocError { layout });
+        }
+    }
+    let buffer = unsafe { Global.alloc(layout) } as *mut HashIndex;
+    if buffer.is_null() {
+        return Err(TryReserveError::AllocError { layout });
+    }
+    Ok(buffer)
+}
+
+fn alloc_index(index_size: usize) -> *mut HashIndex {
+    match try_alloc_index(index_size) {
+        Ok(buffer) => buffer,
+        Err(TryReserveError::CapacityOverflow) => panic!("capacity overflow"),
+        Err(TryReserveError::AllocError { layout }) => alloc::handle_alloc_error(layout),
+    }
+}
+
+fn dealloc_index(index: *mut HashIndex, index_size: usize) {
+    let layout = index_layout(index_size).unwrap();
+    unsafe { Global.dealloc(index as *mut u8, layout) }
+}
+
+pub struct BucketDepths<'a, K: 'a, V: 'a> {
+    head: ListHeadPtr,
+    table: &'a HashTable<K, V>,
+}
+
+impl<'a, K, V> Iterator for BucketDepths<'a, K, V> {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        if self.table.head.is_eq_ptr(self.head) {
+            return None;
+        }
+        let num = self.head.hash_index_deref_mut().avl_root_node().get_node_num();
+        self.head = self.head.next();
+        Some(num)
+    }
+}
+
 impl<K, V> Drop for HashTable<K, V> {
     fn drop(&mut self) {
         if self.index != self.init.as_mut_ptr() {
-            unsafe {
-                free(self.index as *mut c_void);
-            }
+            dealloc_index(self.index, self.index_size);
         }
     }
 }
@@ -1,12 +1,22 @@
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
 #![allow(dead_code)]
-#![feature(libc)]
 
-extern crate libc;
+#[cfg(any(feature = "std", test))]
+extern crate core;
+extern crate alloc;
+#[cfg(feature = "fuzz")]
+extern crate arbitrary;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
 #[macro_use]
 mod macros;
 pub mod ord_map;
+pub mod ord_set;
 mod hash_table;
 pub mod hash_map;
 mod avl_node;
 mod list;
 mod fastbin;
+
+pub use fastbin::{Alloc, Global};
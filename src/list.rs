@@ -1,4 +1,4 @@
-use std::ptr;
+use core::ptr;
 
 pub type ListHeadPtr = *mut ListHead;
 
@@ -1,14 +1,25 @@
-use std::cmp::Ordering;
-use std::{marker, mem, ptr};
-use std::ops::Index;
-use std::iter::FromIterator;
+use core::cmp;
+use core::cmp::Ordering;
+use core::{fmt, marker, mem, ptr};
+use core::ops::Index;
+use core::iter::{FromIterator, FusedIterator, Peekable};
 use avl_node::{AVLNode, AVLNodePtr, AVLNodePtrBase, AVLRoot, AVLRootPtr};
 use avl_node;
-use fastbin::{Fastbin, VoidPtr};
-use std::borrow::Borrow;
-
-use std::ops::Bound::{Excluded, Included, Unbounded};
-use std::ops::RangeBounds;
+use fastbin::{Alloc, Fastbin, Global, VoidPtr};
+use core::borrow::Borrow;
+use core::hash::{Hash, Hasher};
+
+use core::ops::Bound::{Excluded, Included, Unbounded};
+use core::ops::RangeBounds;
+use alloc::vec::Vec;
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use core::hash::BuildHasher;
+use hash_map::HashMap;
+#[cfg(feature = "rayon")]
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 struct AVLEntry<K, V> {
     node: AVLNode,
@@ -78,29 +89,89 @@ impl AVLTreeNodeOperation for *mut AVLNode {
     }
 }
 
+/// A handle to a node of a `OrdMap`, which is read with [`get_at`] and stepped in key order
+/// with [`successor`] and [`predecessor`] without searching the tree again.
+///
+/// This struct is constructed from the [`find_ref`] method on [`OrdMap`]. Nodes are never
+/// moved while they are in the map, and the handle borrows the map, so it stays valid as long
+/// as it exists.
+///
+/// [`OrdMap`]: struct.OrdMap.html
+/// [`find_ref`]: struct.OrdMap.html#method.find_ref
+/// [`get_at`]: struct.OrdMap.html#method.get_at
+/// [`successor`]: struct.OrdMap.html#method.successor
+/// [`predecessor`]: struct.OrdMap.html#method.predecessor
+pub struct NodeRef<'a, K: 'a, V: 'a> {
+    node: AVLNodePtr,
+    _marker: marker::PhantomData<&'a (K, V)>,
+}
+
+impl<'a, K, V> Clone for NodeRef<'a, K, V> {
+    fn clone(&self) -> NodeRef<'a, K, V> {
+        *self
+    }
+}
+
+impl<'a, K, V> Copy for NodeRef<'a, K, V> {}
+
+impl<'a, K, V> PartialEq for NodeRef<'a, K, V> {
+    fn eq(&self, other: &NodeRef<'a, K, V>) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<'a, K, V> Eq for NodeRef<'a, K, V> {}
+
+impl<'a, K, V> NodeRef<'a, K, V> {
+    #[inline]
+    fn from_node(node: AVLNodePtr) -> Option<NodeRef<'a, K, V>> {
+        if node.is_null() {
+            None
+        } else {
+            Some(NodeRef {
+                node,
+                _marker: marker::PhantomData,
+            })
+        }
+    }
+}
+
 /// An cursor of a `OrdMap`.
 ///
 /// This struct is constructed from the [`find_cursors`] method on [`OrdMap`].
 ///
 /// [`OrdMap`]: struct.OrdMap.html
 /// [`find_cursors`]: struct.OrdMap.html#method.find_cursors
-pub struct Cursors<'a, K, V>
+pub struct Cursors<'a, K, V, A = Global>
 where
     K: Ord + 'a,
     V: 'a,
+    A: Alloc + 'a,
 {
-    tree_mut: &'a mut OrdMap<K, V>,
+    tree_mut: &'a mut OrdMap<K, V, A>,
     pos: AVLNodePtr,
 }
 
+impl<'a, K, V, A> fmt::Debug for Cursors<'a, K, V, A>
+where
+    K: Ord + fmt::Debug,
+    V: fmt::Debug,
+    A: Alloc,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Cursors").field(&self.get()).finish()
+    }
+}
+
 enum CursorsOperation {
     NEXT,
     PREV,
 }
 
-impl<'a, K, V> Cursors<'a, K, V>
+impl<'a, K, V, A> Cursors<'a, K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     /// Move cursor to next pos.
     pub fn next(&mut self) {
@@ -157,6 +228,54 @@ where
         }
     }
 
+    /// Returns the (&Key, &Value) pair of next pos without moving the cursor.
+    ///
+    /// # Examples
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// use hash_ord::ord_map::Cursors;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, 1);
+    /// map.insert(2, 2);
+    /// map.insert(3, 3);
+    /// let cursors = map.find_cursors(&2);
+    /// assert_eq!(*cursors.peek_next().unwrap().0, 3);
+    /// assert_eq!(*cursors.get().unwrap().0, 2);
+    /// ```
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        let node = self.pos.next();
+        if node.not_null() {
+            Some((node.key_ref::<K, V>(), node.value_ref::<K, V>()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the (&Key, &Value) pair of prev pos without moving the cursor.
+    ///
+    /// # Examples
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// use hash_ord::ord_map::Cursors;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, 1);
+    /// map.insert(2, 2);
+    /// map.insert(3, 3);
+    /// let cursors = map.find_cursors(&2);
+    /// assert_eq!(*cursors.peek_prev().unwrap().0, 1);
+    /// assert_eq!(*cursors.get().unwrap().0, 2);
+    /// ```
+    pub fn peek_prev(&self) -> Option<(&K, &V)> {
+        let node = self.pos.prev();
+        if node.not_null() {
+            Some((node.key_ref::<K, V>(), node.value_ref::<K, V>()))
+        } else {
+            None
+        }
+    }
+
     fn erase(&mut self, op: CursorsOperation) -> Option<(K, V)> {
         if self.pos.is_null() {
             return None;
@@ -210,6 +329,113 @@ where
     pub fn erase_then_prev(&mut self) -> Option<(K, V)> {
         self.erase(CursorsOperation::PREV)
     }
+
+    /// Removes the current pair and returns it. Afterwards the cursor points to the successor
+    /// of the removed pair, i.e. the pair which now occupies its place in the order, or is
+    /// empty if the removed pair was the last one. Returns `None` and leaves the map untouched
+    /// if the cursor is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, 1);
+    /// map.insert(2, 2);
+    /// let mut cursors = map.find_cursors(&1);
+    /// assert_eq!(cursors.remove_current(), Some((1, 1)));
+    /// assert_eq!(*cursors.get().unwrap().0, 2);
+    /// assert_eq!(cursors.remove_current(), Some((2, 2)));
+    /// assert!(cursors.get().is_none());
+    /// assert_eq!(cursors.remove_current(), None);
+    /// ```
+    #[inline]
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        self.erase(CursorsOperation::NEXT)
+    }
+
+    /// Link a new node between two adjacent nodes `prev` and `next`.
+    fn insert_between(&mut self, prev: AVLNodePtr, next: AVLNodePtr, key: K, value: V) {
+        debug_assert!(prev.is_null() || *prev.key_ref::<K, V>() < key);
+        debug_assert!(next.is_null() || key < *next.key_ref::<K, V>());
+        let (parent, link) = if prev.not_null() && prev.right().is_null() {
+            (prev, prev.right_mut())
+        } else if next.not_null() && next.left().is_null() {
+            (next, next.left_mut())
+        } else {
+            debug_assert!(self.tree_mut.root.node.is_null());
+            (
+                ptr::null_mut(),
+                &mut self.tree_mut.root.node as *mut AVLNodePtr,
+            )
+        };
+        let new_node = self.tree_mut.entry_alloc(key, value).node_ptr();
+        self.tree_mut.link_post_insert(new_node, parent, link);
+    }
+
+    /// Insert a new (key, value) pair just before current pos, and the cursor does not move.
+    /// If current pos is empty, the pair becomes the last one of the map.
+    ///
+    /// The key must be greater than the key of the previous pos and less than the key of
+    /// current pos; this is only checked in debug builds.
+    ///
+    /// # Examples
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// use hash_ord::ord_map::Cursors;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, 1);
+    /// map.insert(3, 3);
+    /// {
+    ///     let mut cursors = map.find_cursors(&3);
+    ///     cursors.insert_before(2, 2);
+    ///     assert_eq!(*cursors.get().unwrap().0, 3);
+    ///     cursors.prev();
+    ///     assert_eq!(*cursors.get().unwrap().0, 2);
+    /// }
+    /// assert_eq!(map.len(), 3);
+    /// ```
+    pub fn insert_before(&mut self, key: K, value: V) {
+        let (prev, next) = if self.pos.is_null() {
+            (self.tree_mut.last_node(), ptr::null_mut())
+        } else {
+            (self.pos.prev(), self.pos)
+        };
+        self.insert_between(prev, next, key, value);
+    }
+
+    /// Insert a new (key, value) pair just after current pos, and the cursor does not move.
+    /// If current pos is empty, the pair becomes the first one of the map.
+    ///
+    /// The key must be greater than the key of current pos and less than the key of the next
+    /// pos; this is only checked in debug builds.
+    ///
+    /// # Examples
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// use hash_ord::ord_map::Cursors;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, 1);
+    /// map.insert(3, 3);
+    /// {
+    ///     let mut cursors = map.find_cursors(&1);
+    ///     cursors.insert_after(2, 2);
+    ///     assert_eq!(*cursors.get().unwrap().0, 1);
+    ///     cursors.next();
+    ///     assert_eq!(*cursors.get().unwrap().0, 2);
+    /// }
+    /// assert_eq!(map.len(), 3);
+    /// ```
+    pub fn insert_after(&mut self, key: K, value: V) {
+        let (prev, next) = if self.pos.is_null() {
+            (ptr::null_mut(), self.tree_mut.first_node())
+        } else {
+            (self.pos, self.pos.next())
+        };
+        self.insert_between(prev, next, key, value);
+    }
 }
 
 /// Optimized AVL.
@@ -322,10 +548,16 @@ where
 ///      ("Iceland", 10)]
 ///      .iter().cloned().collect();
 /// ```
-pub struct OrdMap<K, V> {
+pub struct OrdMap<K, V, A = Global>
+where
+    A: Alloc,
+{
     root: AVLRoot,
+    // cached first and last nodes of the tree, null if it is empty
+    first: AVLNodePtr,
+    last: AVLNodePtr,
     count: usize,
-    entry_fastbin: Fastbin,
+    entry_fastbin: Fastbin<A>,
     _marker: marker::PhantomData<(K, V)>,
 }
 
@@ -333,29 +565,31 @@ pub struct OrdMap<K, V> {
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-pub struct OccupiedEntry<'a, K, V>
+pub struct OccupiedEntry<'a, K, V, A = Global>
 where
     K: 'a,
     V: 'a,
+    A: Alloc + 'a,
 {
     key: Option<K>,
     node: AVLNodePtr,
-    ord_map_mut: &'a mut OrdMap<K, V>,
+    ord_map_mut: &'a mut OrdMap<K, V, A>,
 }
 
 /// A view into a vacant entry in a `OrdMap`.
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-pub struct VacantEntry<'a, K, V>
+pub struct VacantEntry<'a, K, V, A = Global>
 where
     K: 'a,
     V: 'a,
+    A: Alloc + 'a,
 {
     key: K,
     parent: AVLNodePtr,
     link: *mut AVLNodePtr,
-    ord_map_mut: &'a mut OrdMap<K, V>,
+    ord_map_mut: &'a mut OrdMap<K, V, A>,
 }
 
 /// A view into a single entry in a map, which may either be vacant or occupied.
@@ -364,21 +598,23 @@ where
 ///
 /// [`OrdMap`]: struct.OrdMap.html
 /// [`entry`]: struct.OrdMap.html#method.entry
-pub enum Entry<'a, K, V>
+pub enum Entry<'a, K, V, A = Global>
 where
     K: 'a,
     V: 'a,
+    A: Alloc + 'a,
 {
     /// An occupied entry.
-    Occupied(OccupiedEntry<'a, K, V>),
+    Occupied(OccupiedEntry<'a, K, V, A>),
 
     /// A vacant entry.
-    Vacant(VacantEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V, A>),
 }
 
-impl<'a, K, V> Entry<'a, K, V>
+impl<'a, K, V, A> Entry<'a, K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     pub fn or_insert(self, default: V) -> &'a mut V {
         match self {
@@ -394,6 +630,30 @@ where
         }
     }
 
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value in the entry. The default function is given
+    /// a reference to the key that would be inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<&str, usize> = OrdMap::new();
+    /// map.entry("poneyland").or_insert_with_key(|key| key.chars().count());
+    ///
+    /// assert_eq!(map[&"poneyland"], 9);
+    /// ```
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
     pub fn and_modify<F>(self, mut f: F) -> Self
     where
         F: FnMut(&mut V),
@@ -406,11 +666,49 @@ where
             Entry::Vacant(entry) => Entry::Vacant(entry),
         }
     }
+
+    /// Returns a reference to this entry's key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<&str, u32> = OrdMap::new();
+    /// assert_eq!(map.entry("poneyland").key(), &"poneyland");
+    /// ```
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<&str, Option<u32>> = OrdMap::new();
+    /// map.entry("poneyland").or_default();
+    ///
+    /// assert_eq!(map[&"poneyland"], None);
+    /// ```
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(Default::default)
+    }
 }
 
-impl<'a, K, V> OccupiedEntry<'a, K, V>
+impl<'a, K, V, A> OccupiedEntry<'a, K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     pub fn remove_entry(self) -> (K, V) {
         unsafe { self.ord_map_mut.remove_node(self.node).unwrap() }
@@ -428,6 +726,24 @@ where
         self.key.take()
     }
 
+    /// Gets a reference to the key and the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::{OrdMap, Entry};
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, "a");
+    ///
+    /// if let Entry::Occupied(o) = map.entry(1) {
+    ///     assert_eq!(o.get_key_value(), (&1, &"a"));
+    /// }
+    /// ```
+    pub fn get_key_value(&self) -> (&K, &V) {
+        (self.node.key_ref::<K, V>(), self.node.value_ref::<K, V>())
+    }
+
     pub fn replace_key(self) -> K {
         let old_key = self.node.key_mut::<K, V>();
         mem::replace(old_key, self.key.unwrap())
@@ -451,6 +767,28 @@ where
         value
     }
 
+    /// Sets the value of the entry and returns the old value. Unlike [`replace_entry`], the key
+    /// in the map is kept and the key used to create this entry is dropped.
+    ///
+    /// [`replace_entry`]: struct.OccupiedEntry.html#method.replace_entry
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::{OrdMap, Entry};
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, "a");
+    ///
+    /// if let Entry::Occupied(o) = map.entry(1) {
+    ///     assert_eq!(o.replace("b"), "a");
+    /// }
+    /// assert_eq!(map[&1], "b");
+    /// ```
+    pub fn replace(self, value: V) -> V {
+        mem::replace(self.node.value_mut::<K, V>(), value)
+    }
+
     pub fn replace_entry(self, value: V) -> (K, V) {
         let old_key = self.node.key_mut::<K, V>();
         let old_key = mem::replace(old_key, self.key.unwrap());
@@ -460,9 +798,10 @@ where
     }
 }
 
-impl<'a, K, V> VacantEntry<'a, K, V>
+impl<'a, K, V, A> VacantEntry<'a, K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     pub fn key(&self) -> &K {
         &self.key
@@ -475,10 +814,7 @@ where
     unsafe fn _internal_insert(self, value: V) -> &'a mut V {
         let key = self.key;
         let new_entry = self.ord_map_mut.entry_alloc(key, value);
-        let new_node = new_entry.node_ptr();
-        avl_node::link_node(new_node, self.parent, self.link);
-        avl_node::node_post_insert(new_node, self.ord_map_mut.get_root_ptr());
-        self.ord_map_mut.count += 1;
+        self.ord_map_mut.link_post_insert(new_entry.node_ptr(), self.parent, self.link);
         &mut *new_entry.value()
     }
 
@@ -487,128 +823,485 @@ where
     }
 }
 
-impl<K, V> OrdMap<K, V> {
-    fn recursive_drop_node(&mut self, node: AVLNodePtr) {
-        if node.left().not_null() {
-            self.recursive_drop_node(node.left());
-        }
-        if node.right().not_null() {
-            self.recursive_drop_node(node.right());
-        }
-        let entry = node.avl_node_deref_to_entry::<K, V>();
-        if mem::needs_drop::<AVLEntry<K, V>>() {
-            unsafe {
-                ptr::drop_in_place(entry);
-            }
-        }
-        self.entry_fastbin.del(entry as VoidPtr);
-    }
+/// A view into a single entry of a borrowed key in a map, which may either be vacant or
+/// occupied.
+///
+/// This `enum` is constructed from the [`entry_ref`] method on [`OrdMap`].
+///
+/// [`OrdMap`]: struct.OrdMap.html
+/// [`entry_ref`]: struct.OrdMap.html#method.entry_ref
+pub enum EntryRef<'a, 'b, K, Q: ?Sized, V, A = Global>
+where
+    K: 'a,
+    Q: 'b,
+    V: 'a,
+    A: Alloc + 'a,
+{
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V, A>),
 
-    /// Clears the map, removing all key-value pairs. Keeps the allocated memory
-    /// for reuse.
+    /// A vacant entry.
+    Vacant(VacantEntryRef<'a, 'b, K, Q, V, A>),
+}
+
+impl<'a, 'b, K, Q: ?Sized, V, A> EntryRef<'a, 'b, K, Q, V, A>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ToOwned<Owned = K>,
+    A: Alloc,
+{
+    /// Ensures a value is in the entry by inserting the default if empty, and returns
+    /// a mutable reference to the value in the entry.
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
     ///
-    /// let mut a = OrdMap::new();
-    /// a.insert(1, "a");
-    /// a.clear();
-    /// assert!(a.is_empty());
+    /// let mut map: OrdMap<String, u32> = OrdMap::new();
+    /// map.entry_ref("poneyland").or_insert(12);
+    ///
+    /// *map.entry_ref("poneyland").or_insert(12) += 10;
+    /// assert_eq!(map.get("poneyland"), Some(&22));
     /// ```
-    #[inline]
-    pub fn clear(&mut self) {
-        let node = self.root.node;
-        if node.not_null() {
-            self.recursive_drop_node(node);
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default),
         }
-        self.root.node = ptr::null_mut();
-        self.count = 0;
     }
 
-    #[inline]
-    fn destroy(&mut self) {
-        self.clear();
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value in the entry.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the map.
+    pub fn and_modify<F>(self, mut f: F) -> Self
+    where
+        F: FnMut(&mut V),
+    {
+        match self {
+            EntryRef::Occupied(mut entry) => {
+                f(entry.get_mut());
+                EntryRef::Occupied(entry)
+            }
+            EntryRef::Vacant(entry) => EntryRef::Vacant(entry),
+        }
     }
 }
 
-/// An iterator over a sub-range of entries in a `OrdMap`.
-///
-/// This `struct` is created by the [`range`] method on [`OrdMap`]. See its
-/// documentation for more.
+/// A view into a vacant entry of a borrowed key in a `OrdMap`.
+/// It is part of the [`EntryRef`] enum.
 ///
-/// [`range`]: struct.OrdMap.html#method.range
-/// [`OrdMap`]: struct.OrdMap.html
-pub struct Range<'a, K, V>
+/// [`EntryRef`]: enum.EntryRef.html
+pub struct VacantEntryRef<'a, 'b, K, Q: ?Sized, V, A = Global>
 where
     K: 'a,
+    Q: 'b,
     V: 'a,
+    A: Alloc + 'a,
 {
-    front: AVLNodePtr,
-    back: AVLNodePtr,
-    end: AVLNodePtr,
-    _marker: marker::PhantomData<&'a (K, V)>,
+    key: &'b Q,
+    parent: AVLNodePtr,
+    link: *mut AVLNodePtr,
+    ord_map_mut: &'a mut OrdMap<K, V, A>,
 }
 
-impl<'a, K, V> Range<'a, K, V> {
-    unsafe fn next_unchecked(&mut self) -> (&'a K, &'a V) {
-        let node = self.front;
-        self.front = self.front.next();
-        (node.key_ref::<K, V>(), node.value_ref::<K, V>())
+impl<'a, 'b, K, Q: ?Sized, V, A> VacantEntryRef<'a, 'b, K, Q, V, A>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ToOwned<Owned = K>,
+    A: Alloc,
+{
+    /// Gets a reference to the key that would be used when inserting a value
+    /// through the `VacantEntryRef`.
+    pub fn key(&self) -> &'b Q {
+        self.key
     }
 
-    unsafe fn next_back_unchecked(&mut self) -> (&'a K, &'a V) {
-        self.back = if self.back.is_null() {
-            self.end
-        } else {
-            self.back.prev()
+    /// Sets the value of the entry with an owned copy of the VacantEntryRef's key,
+    /// and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let entry = VacantEntry {
+            key: self.key.to_owned(),
+            parent: self.parent,
+            link: self.link,
+            ord_map_mut: self.ord_map_mut,
         };
-        (self.back.key_ref::<K, V>(), self.back.value_ref::<K, V>())
+        entry.insert(value)
     }
 }
 
-impl<'a, K, V> Iterator for Range<'a, K, V> {
-    type Item = (&'a K, &'a V);
+impl<'a, K, V, A> fmt::Debug for Entry<'a, K, V, A>
+where
+    K: Ord + fmt::Debug,
+    V: fmt::Debug,
+    A: Alloc,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Entry::Vacant(ref v) => f.debug_tuple("Entry").field(v).finish(),
+            Entry::Occupied(ref o) => f.debug_tuple("Entry").field(o).finish(),
+        }
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.front == self.back {
-            None
-        } else {
-            unsafe { Some(self.next_unchecked()) }
+impl<'a, K, V, A> fmt::Debug for OccupiedEntry<'a, K, V, A>
+where
+    K: Ord + fmt::Debug,
+    V: fmt::Debug,
+    A: Alloc,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+impl<'a, K, V, A> fmt::Debug for VacantEntry<'a, K, V, A>
+where
+    K: Ord + fmt::Debug,
+    A: Alloc,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(self.key()).finish()
+    }
+}
+
+/// Entries allocated from `entry_fastbin` but not linked into a tree, kept in lists chained by
+/// `right`. Entries still in `lists` are dropped and freed when it is dropped, so nothing leaks
+/// if a user `cmp` panics while they are moved around.
+struct UnlinkedEntries<'a, K, V, A>
+where
+    A: Alloc + 'a,
+{
+    lists: [AVLNodePtr; 3],
+    entry_fastbin: &'a Fastbin<A>,
+    _marker: marker::PhantomData<(K, V)>,
+}
+
+impl<'a, K, V, A> Drop for UnlinkedEntries<'a, K, V, A>
+where
+    A: Alloc,
+{
+    fn drop(&mut self) {
+        for head in self.lists.iter_mut() {
+            while head.not_null() {
+                let entry = head.avl_node_deref_to_entry::<K, V>();
+                *head = head.right();
+                unsafe {
+                    ptr::drop_in_place(entry);
+                }
+                self.entry_fastbin.del(entry as VoidPtr);
+            }
         }
     }
 }
 
-impl<'a, K, V> Clone for Range<'a, K, V> {
-    fn clone(&self) -> Range<'a, K, V> {
-        Range {
-            front: self.front,
-            back: self.back,
-            end: self.end,
-            _marker: marker::PhantomData,
+/// How `merge_sorted_list` resolves a key present in both `self` and `other`.
+enum OnConflict<'a, K: 'a, V: 'a> {
+    /// Keep the entry of `other`, and drop the one of `self`.
+    Replace,
+    /// Keep the entry of `self`, and drop the one of `other`.
+    KeepExisting,
+    /// Keep the entry of `other`, and move the pair of `self` into the vector.
+    Report(&'a mut Vec<(K, V)>),
+    /// Keep the key of `self`, with the value returned by the closure from both values.
+    Combine(&'a mut dyn FnMut(&K, V, V) -> V),
+}
+
+/// An entry whose value was moved out. If it is dropped, which only happens while unwinding,
+/// the key is dropped and the entry is freed.
+struct ValueMovedOut<'a, K, V, A>
+where
+    A: Alloc + 'a,
+{
+    entry: *mut AVLEntry<K, V>,
+    entry_fastbin: &'a Fastbin<A>,
+}
+
+impl<'a, K, V, A> Drop for ValueMovedOut<'a, K, V, A>
+where
+    A: Alloc,
+{
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.entry.key());
         }
+        self.entry_fastbin.del(self.entry as VoidPtr);
     }
 }
 
-impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        if self.front == self.back {
+/// A map whose tree is being rebuilt in place. If it is dropped, which only happens while
+/// unwinding, the map is cleared, so it is never left with a tree that its count and bounds do
+/// not describe.
+struct ClearOnUnwind<'a, K: 'a, V: 'a, A>
+where
+    A: Alloc + 'a,
+{
+    map: &'a mut OrdMap<K, V, A>,
+}
+
+impl<'a, K, V, A> Drop for ClearOnUnwind<'a, K, V, A>
+where
+    A: Alloc,
+{
+    fn drop(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<K, V, A> OrdMap<K, V, A>
+where
+    A: Alloc,
+{
+    fn drop_tree(&mut self, mut root: AVLRoot) {
+        let entry_fastbin = &mut self.entry_fastbin;
+        unsafe {
+            avl_node::avl_for_each_post_order(&mut root, &mut |node| {
+                let entry = node.avl_node_deref_to_entry::<K, V>();
+                if mem::needs_drop::<AVLEntry<K, V>>() {
+                    ptr::drop_in_place(entry);
+                }
+                entry_fastbin.del(entry as VoidPtr);
+            });
+        }
+    }
+
+    /// Clears the map, removing all key-value pairs. Keeps the allocated memory
+    /// for reuse.
+    ///
+    /// Nodes are torn off the tree one by one without recursion, so clearing never
+    /// overflows the stack. Freed entries go back to the pages of the `Fastbin`, which are
+    /// retained, so refilling the map up to its former size allocates nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut a = OrdMap::new();
+    /// a.insert(1, "a");
+    /// let usage = a.memory_usage();
+    /// a.clear();
+    /// assert!(a.is_empty());
+    /// a.insert(2, "b");
+    /// assert_eq!(a.memory_usage(), usage);
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        let root = mem::take(&mut self.root);
+        self.reset_bounds();
+        self.drop_tree(root);
+        self.count = 0;
+    }
+
+    #[inline]
+    fn destroy(&mut self) {
+        self.clear();
+    }
+
+    /// Recompute the cached first and last nodes by descending from the root, after the tree
+    /// was rebuilt as a whole.
+    #[inline]
+    fn reset_bounds(&mut self) {
+        self.first = self.root.node.first_node();
+        self.last = self.root.node.last_node();
+    }
+
+    /// Creates an empty `OrdMap` which allocates memory from `allocator`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// use hash_ord::Global;
+    ///
+    /// let mut map = OrdMap::new_in(Global);
+    /// map.insert(1, "a");
+    /// assert_eq!(map[&1], "a");
+    /// ```
+    #[inline]
+    pub fn new_in(allocator: A) -> Self {
+        OrdMap {
+            root: AVLRoot {
+                node: ptr::null_mut(),
+            },
+            first: ptr::null_mut(),
+            last: ptr::null_mut(),
+            count: 0,
+            entry_fastbin: Fastbin::new_lazy_in(
+                mem::size_of::<AVLEntry<K, V>>(),
+                mem::align_of::<AVLEntry<K, V>>(),
+                allocator,
+            ),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        self.entry_fastbin.allocator()
+    }
+
+    #[inline]
+    fn entry_alloc(&mut self, key: K, value: V) -> *mut AVLEntry<K, V> {
+        let entry = self.entry_fastbin.alloc() as *mut AVLEntry<K, V>;
+        debug_assert!(!entry.is_null());
+        unsafe {
+            ptr::write(entry.key(), key);
+            ptr::write(entry.value(), value);
+        }
+        entry
+    }
+
+    /// Descend from the root to the node for which `cmp`, comparing the searched key against
+    /// the key of a node, returns `Ordering::Equal`. Returns the duplicate node (or null), the
+    /// parent and the link where a new node would be attached.
+    #[inline]
+    unsafe fn find_duplicate_by<F>(&mut self, mut cmp: F) -> (AVLNodePtr, AVLNodePtr, *mut AVLNodePtr)
+    where
+        F: FnMut(&K) -> Ordering,
+    {
+        let mut cmp_node_ref = &mut self.root.node as *mut AVLNodePtr;
+        let mut parent = ptr::null_mut();
+        while (*cmp_node_ref).not_null() {
+            parent = *cmp_node_ref;
+            match cmp(parent.key_ref::<K, V>()) {
+                Ordering::Less => {
+                    cmp_node_ref = parent.left_mut();
+                }
+                Ordering::Equal => {
+                    return (parent, parent, cmp_node_ref);
+                }
+                Ordering::Greater => {
+                    cmp_node_ref = parent.right_mut();
+                }
+            }
+        }
+        (ptr::null_mut(), parent, cmp_node_ref)
+    }
+
+    #[inline]
+    fn find_node_by<F>(&self, mut cmp: F) -> AVLNodePtr
+    where
+        F: FnMut(&K) -> Ordering,
+    {
+        let mut node = self.root.node;
+        while node.not_null() {
+            match cmp(node.key_ref::<K, V>()) {
+                Ordering::Equal => {
+                    return node;
+                }
+                Ordering::Less => {
+                    node = node.left();
+                }
+                Ordering::Greater => {
+                    node = node.right();
+                }
+            }
+        }
+        ptr::null_mut()
+    }
+
+    /// Insert the pair at the position found by `find_duplicate_by`, replacing `duplicate` if
+    /// it is not null.
+    #[inline]
+    fn insert_at(
+        &mut self,
+        key: K,
+        value: V,
+        duplicate: AVLNodePtr,
+        parent: AVLNodePtr,
+        cmp_node_ref: *mut AVLNodePtr,
+    ) -> Option<(K, V)> {
+        let entry = self.entry_alloc(key, value);
+        if duplicate.is_null() {
+            self.link_post_insert(entry.node_ptr(), parent, cmp_node_ref);
             None
         } else {
-            unsafe { Some(self.next_back_unchecked()) }
+            unsafe {
+                let old_entry = duplicate.avl_node_deref_to_entry::<K, V>();
+                avl_node::avl_node_replace(duplicate, entry.node_ptr(), self.get_root_ptr());
+                if duplicate == self.first {
+                    self.first = entry.node_ptr();
+                }
+                if duplicate == self.last {
+                    self.last = entry.node_ptr();
+                }
+                let res = Some((ptr::read(old_entry.key()), ptr::read(old_entry.value())));
+                self.entry_fastbin.del(old_entry as VoidPtr);
+                res
+            }
+        }
+    }
+
+    #[inline]
+    fn link_post_insert(
+        &mut self,
+        new_node: AVLNodePtr,
+        parent: AVLNodePtr,
+        cmp_node_ref: *mut AVLNodePtr,
+    ) {
+        // a new first (last) node can only be linked as the left (right) child of the old one
+        if self.first.is_null() || cmp_node_ref == self.first.left_mut() {
+            self.first = new_node;
+        }
+        if self.last.is_null() || cmp_node_ref == self.last.right_mut() {
+            self.last = new_node;
+        }
+        unsafe {
+            avl_node::link_node(new_node, parent, cmp_node_ref);
+        }
+        unsafe {
+            avl_node::node_post_insert(new_node, self.get_root_ptr());
+        }
+        self.count += 1;
+    }
+
+    #[inline]
+    fn get_root_ptr(&mut self) -> AVLRootPtr {
+        &mut self.root as AVLRootPtr
+    }
+
+    #[inline]
+    unsafe fn remove_node(&mut self, node: AVLNodePtr) -> Option<(K, V)> {
+        if node.is_null() || node.empty() {
+            return None;
+        }
+        if node == self.first {
+            self.first = node.next();
         }
+        if node == self.last {
+            self.last = node.prev();
+        }
+        avl_node::erase_node(node, self.get_root_ptr());
+        node.set_parent(node);
+        self.count -= 1;
+        let old_entry = node.avl_node_deref_to_entry::<K, V>();
+        let res = Some((ptr::read(old_entry.key()), ptr::read(old_entry.value())));
+        self.entry_fastbin.del(old_entry as VoidPtr);
+        res
     }
 }
 
-/// A mutable iterator over a sub-range of entries in a `OrdMap`.
+/// An iterator over a sub-range of entries in a `OrdMap`.
 ///
-/// This `struct` is created by the [`range_mut`] method on [`OrdMap`]. See its
+/// This `struct` is created by the [`range`] method on [`OrdMap`]. See its
 /// documentation for more.
 ///
-/// [`range_mut`]: struct.OrdMap.html#method.range_mut
+/// [`range`]: struct.OrdMap.html#method.range
 /// [`OrdMap`]: struct.OrdMap.html
-pub struct RangeMut<'a, K, V>
+pub struct Range<'a, K, V>
 where
     K: 'a,
     V: 'a,
@@ -616,28 +1309,28 @@ where
     front: AVLNodePtr,
     back: AVLNodePtr,
     end: AVLNodePtr,
-    _marker: marker::PhantomData<&'a mut (K, V)>,
+    _marker: marker::PhantomData<&'a (K, V)>,
 }
 
-impl<'a, K, V> RangeMut<'a, K, V> {
-    unsafe fn next_unchecked(&mut self) -> (&'a K, &'a mut V) {
+impl<'a, K, V> Range<'a, K, V> {
+    unsafe fn next_unchecked(&mut self) -> (&'a K, &'a V) {
         let node = self.front;
         self.front = self.front.next();
-        (node.key_ref::<K, V>(), node.value_mut::<K, V>())
+        (node.key_ref::<K, V>(), node.value_ref::<K, V>())
     }
 
-    unsafe fn next_back_unchecked(&mut self) -> (&'a K, &'a mut V) {
+    unsafe fn next_back_unchecked(&mut self) -> (&'a K, &'a V) {
         self.back = if self.back.is_null() {
             self.end
         } else {
             self.back.prev()
         };
-        (self.back.key_ref::<K, V>(), self.back.value_mut::<K, V>())
+        (self.back.key_ref::<K, V>(), self.back.value_ref::<K, V>())
     }
 }
 
-impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
-    type Item = (&'a K, &'a mut V);
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.front == self.back {
@@ -648,9 +1341,87 @@ impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
     }
 }
 
-impl<'a, K, V> Clone for RangeMut<'a, K, V> {
-    fn clone(&self) -> RangeMut<'a, K, V> {
-        RangeMut {
+impl<'a, K, V> Clone for Range<'a, K, V> {
+    fn clone(&self) -> Range<'a, K, V> {
+        Range {
+            front: self.front,
+            back: self.back,
+            end: self.end,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> fmt::Debug for Range<'a, K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            None
+        } else {
+            unsafe { Some(self.next_back_unchecked()) }
+        }
+    }
+}
+
+/// A mutable iterator over a sub-range of entries in a `OrdMap`.
+///
+/// This `struct` is created by the [`range_mut`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`range_mut`]: struct.OrdMap.html#method.range_mut
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct RangeMut<'a, K, V>
+where
+    K: 'a,
+    V: 'a,
+{
+    front: AVLNodePtr,
+    back: AVLNodePtr,
+    end: AVLNodePtr,
+    _marker: marker::PhantomData<&'a mut (K, V)>,
+}
+
+impl<'a, K, V> RangeMut<'a, K, V> {
+    unsafe fn next_unchecked(&mut self) -> (&'a K, &'a mut V) {
+        let node = self.front;
+        self.front = self.front.next();
+        (node.key_ref::<K, V>(), node.value_mut::<K, V>())
+    }
+
+    unsafe fn next_back_unchecked(&mut self) -> (&'a K, &'a mut V) {
+        self.back = if self.back.is_null() {
+            self.end
+        } else {
+            self.back.prev()
+        };
+        (self.back.key_ref::<K, V>(), self.back.value_mut::<K, V>())
+    }
+}
+
+impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            None
+        } else {
+            unsafe { Some(self.next_unchecked()) }
+        }
+    }
+}
+
+impl<'a, K, V> Clone for RangeMut<'a, K, V> {
+    fn clone(&self) -> RangeMut<'a, K, V> {
+        RangeMut {
             front: self.front,
             back: self.back,
             end: self.end,
@@ -669,12 +1440,87 @@ impl<'a, K, V> DoubleEndedIterator for RangeMut<'a, K, V> {
     }
 }
 
-impl<K, V> OrdMap<K, V>
+/// An iterator over the keys of a sub-range of entries in a `OrdMap`.
+///
+/// This `struct` is created by the [`range_keys`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`range_keys`]: struct.OrdMap.html#method.range_keys
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct RangeKeys<'a, K: 'a, V: 'a> {
+    inner: Range<'a, K, V>,
+}
+
+impl<'a, K, V> Clone for RangeKeys<'a, K, V> {
+    fn clone(&self) -> RangeKeys<'a, K, V> {
+        RangeKeys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for RangeKeys<'a, K, V> {
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for RangeKeys<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a K> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+/// An iterator over the values of a sub-range of entries in a `OrdMap`.
+///
+/// This `struct` is created by the [`range_values`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`range_values`]: struct.OrdMap.html#method.range_values
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct RangeValues<'a, K: 'a, V: 'a> {
+    inner: Range<'a, K, V>,
+}
+
+impl<'a, K, V> Clone for RangeValues<'a, K, V> {
+    fn clone(&self) -> RangeValues<'a, K, V> {
+        RangeValues {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for RangeValues<'a, K, V> {
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for RangeValues<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V, A> OrdMap<K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     /// Splits the collection into two at the given key. Returns everything after the given key,
     /// including the key.
+    /// O(log n + min(m, n - m)) time complexity, where m is the number of returned elements
+    ///
+    /// The larger half keeps its entries where they are, together with all pages of memory,
+    /// and only the entries of the smaller half are moved.
     ///
     /// # Examples
     ///
@@ -707,41 +1553,124 @@ where
         K: Borrow<Q>,
     {
         if self.is_empty() {
-            return Self::new();
+            return Self::new_in(self.allocator().clone());
+        }
+        // Look up the path of `key` before touching the tree, so a panicking `Ord` leaves the map
+        // intact.
+        let mut path = Vec::new();
+        let mut node = self.root.node;
+        while node.not_null() {
+            let node_key: &Q = node.key_ref::<K, V>().borrow();
+            let order = node_key.cmp(key);
+            path.push(order);
+            node = match order {
+                Ordering::Less => node.right(),
+                Ordering::Greater => node.left(),
+                Ordering::Equal => break,
+            };
+        }
+        let tol_cnt = self.len();
+        let mut other_map = Self::new_in(self.allocator().clone());
+        let mut path = path.into_iter();
+        let (left, split_node, mut right) =
+            unsafe { avl_node::avl_split(self.root.node, |_| path.next().unwrap()) };
+        if split_node.not_null() {
+            right = unsafe { avl_node::avl_join(&mut AVLRoot::default(), split_node, &mut right) };
+        }
+        self.root = left;
+
+        // Walk both halves in step, which stops at the end of the smaller one.
+        let (mut left_node, mut right_node) = (self.root.node.first_node(), right.node.first_node());
+        let mut small_cnt = 0;
+        while left_node.not_null() && right_node.not_null() {
+            left_node = left_node.next();
+            right_node = right_node.next();
+            small_cnt += 1;
+        }
+        if right_node.is_null() {
+            let head = unsafe {
+                let list = avl_node::avl_tree_convert_to_list(&mut right);
+                other_map.move_list_from(&mut self.entry_fastbin, list)
+            };
+            other_map.inner_init_from_sorted_list(head, small_cnt);
+            self.count = tol_cnt - small_cnt;
+            self.reset_bounds();
+        } else {
+            // the pages go along with the right half, and the left half is moved out of them
+            mem::swap(&mut self.entry_fastbin, &mut other_map.entry_fastbin);
+            let head = unsafe {
+                let list = avl_node::avl_tree_convert_to_list(&mut self.root);
+                self.move_list_from(&mut other_map.entry_fastbin, list)
+            };
+            self.inner_init_from_sorted_list(head, small_cnt);
+            other_map.root = right;
+            other_map.count = tol_cnt - small_cnt;
+            other_map.reset_bounds();
+        }
+        other_map
+    }
+
+    /// Splits the collection into two at the given rank. Keeps the `n` smallest elements in
+    /// `Self` and returns everything else.
+    /// O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut a: OrdMap<_, _> = (0..5).map(|i| (i, -i)).collect();
+    /// let b = a.split_off_at_rank(2);
+    ///
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(b.len(), 3);
+    /// assert_eq!(a[&1], -1);
+    /// assert_eq!(b[&2], -2);
+    /// ```
+    pub fn split_off_at_rank(&mut self, n: usize) -> Self {
+        if n >= self.len() {
+            return Self::new_in(self.allocator().clone());
+        }
+        if n == 0 {
+            return mem::replace(self, Self::new_in(self.allocator().clone()));
         }
         let tol_cnt = self.len();
-        let mut other_map = Self::new();
-        let split_node = self.lower_bound_find_node(key);
+        let mut other_map = Self::new_in(self.allocator().clone());
         let self_head = unsafe { avl_node::avl_tree_convert_to_list(&mut self.root) };
-        let (other_head, other_cnt) = unsafe {
-            let mut cnt = 0;
-            let mut node = split_node;
-            let mut prev = ptr::null_mut();
-            let mut head = ptr::null_mut();
-            while node.not_null() {
-                let entry = node.avl_node_deref_to_entry::<K, V>();
-                let other_entry = other_map.entry_fastbin.alloc() as *mut AVLEntry<K, V>;
-                ptr::copy_nonoverlapping(entry, other_entry, 1);
-                self.entry_fastbin.del(entry as VoidPtr);
-                let other_avl_node = other_entry.node_ptr();
-                other_avl_node.set_left(prev);
-                other_avl_node.set_right(ptr::null_mut());
-                cnt += 1;
-                if prev.not_null() {
-                    prev.set_right(other_avl_node);
-                } else {
-                    head = other_avl_node;
-                }
-                prev = other_avl_node;
-                node = node.right();
-            }
-            (head, cnt)
-        };
-        other_map.inner_init_from_sorted_list(other_head, other_cnt);
-        self.inner_init_from_sorted_list(self_head, tol_cnt - other_cnt);
+        let mut split_node = self_head;
+        for _ in 0..n {
+            split_node = split_node.right();
+        }
+        let other_head = unsafe { other_map.move_list_from(&mut self.entry_fastbin, split_node) };
+        other_map.inner_init_from_sorted_list(other_head, tol_cnt - n);
+        self.inner_init_from_sorted_list(self_head, n);
         other_map
     }
 
+    /// Move the entries of the list starting at `node` out of the pages of `from` into those of
+    /// `self`, and return the list of the moved entries.
+    unsafe fn move_list_from(&mut self, from: &mut Fastbin<A>, mut node: AVLNodePtr) -> AVLNodePtr {
+        let mut prev = ptr::null_mut();
+        let mut head = ptr::null_mut();
+        while node.not_null() {
+            let entry = node.avl_node_deref_to_entry::<K, V>();
+            let new_entry = self.entry_fastbin.alloc() as *mut AVLEntry<K, V>;
+            ptr::copy_nonoverlapping(entry, new_entry, 1);
+            node = node.right();
+            from.del(entry as VoidPtr);
+            let new_node = new_entry.node_ptr();
+            new_node.set_left(prev);
+            new_node.set_right(ptr::null_mut());
+            if prev.not_null() {
+                prev.set_right(new_node);
+            } else {
+                head = new_node;
+            }
+            prev = new_node;
+        }
+        head
+    }
+
     fn inner_range<T: ?Sized, R>(&self, range: R) -> (AVLNodePtr, AVLNodePtr, AVLNodePtr)
     where
         T: Ord,
@@ -781,6 +1710,9 @@ where
     /// `range((Excluded(4), Included(10)))` will yield a left-exclusive, right-inclusive
     /// range from 4 to 10.
     ///
+    /// The bounds may be any borrowed form of the map's key type, but `Ord` on the borrowed
+    /// form *must* match those for the key type.
+    ///
     /// # Panics
     ///
     /// Panics if range `start > end`.
@@ -802,6 +1734,11 @@ where
     ///     println!("{}: {}", key, value);
     /// }
     /// assert_eq!(Some((&5, &"b")), map.range(4..).next());
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert("b".to_string(), 1);
+    /// map.insert("c".to_string(), 2);
+    /// assert_eq!(map.range::<str, _>((Included("a"), Included("b"))).count(), 1);
     /// ```
     pub fn range<T: ?Sized, R>(&self, range: R) -> Range<K, V>
     where
@@ -862,121 +1799,526 @@ where
         }
     }
 
-    /// Moves all elements from `other` into `Self`, leaving `other` empty.
-    /// O(n) time complexity
+    /// Constructs a double-ended iterator over the keys of a sub-range of elements in the map,
+    /// in incremental order. The range is given as for [`range`].
+    ///
+    /// [`range`]: #method.range
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
     ///
-    /// let mut a = OrdMap::new();
-    /// a.insert(1, "a");
-    /// a.insert(2, "b");
-    /// a.insert(3, "c");
+    /// let map: OrdMap<_, _> = (0..10).map(|i| (i, i * 10)).collect();
+    /// let keys: Vec<_> = map.range_keys(3..6).cloned().collect();
+    /// assert_eq!(keys, [3, 4, 5]);
+    /// ```
+    pub fn range_keys<T: ?Sized, R>(&self, range: R) -> RangeKeys<K, V>
+    where
+        T: Ord,
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+    {
+        RangeKeys {
+            inner: self.range(range),
+        }
+    }
+
+    /// Constructs a double-ended iterator over the values of a sub-range of elements in the
+    /// map, in incremental order of their keys. The range is given as for [`range`].
     ///
-    /// let mut b = OrdMap::new();
-    /// b.insert(3, "d");
-    /// b.insert(4, "e");
-    /// b.insert(5, "f");
+    /// [`range`]: #method.range
     ///
-    /// a.append(&mut b);
+    /// # Panics
     ///
-    /// assert_eq!(a.len(), 5);
-    /// assert_eq!(b.len(), 0);
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
     ///
-    /// assert_eq!(a[&1], "a");
-    /// assert_eq!(a[&2], "b");
-    /// assert_eq!(a[&3], "d");
-    /// assert_eq!(a[&4], "e");
-    /// assert_eq!(a[&5], "f");
     /// ```
-    pub fn append(&mut self, other: &mut Self) {
-        if other.len() == 0 {
-            return;
-        }
-
-        if self.len() == 0 {
-            mem::swap(self, other);
-            return;
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = (0..10).map(|i| (i, i * 10)).collect();
+    /// let values: Vec<_> = map.range_values(3..6).cloned().collect();
+    /// assert_eq!(values, [30, 40, 50]);
+    /// ```
+    pub fn range_values<T: ?Sized, R>(&self, range: R) -> RangeValues<K, V>
+    where
+        T: Ord,
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+    {
+        RangeValues {
+            inner: self.range(range),
         }
+    }
 
-        let (head, tol_cnt) = {
-            let other_sorted_list = mem::replace(other, OrdMap::new())
-                .into_iter()
-                .into_sorted_list();
-            let self_head = unsafe { avl_node::avl_tree_convert_to_list(&mut self.root) };
-            let other_head = {
-                let mut prev = ptr::null_mut();
-                let mut head = ptr::null_mut();
-                for (k, v) in other_sorted_list {
-                    let node_ptr = self.entry_alloc(k, v).node_ptr();
-                    node_ptr.set_left(prev);
-                    node_ptr.set_right(ptr::null_mut());
-                    if prev.not_null() {
-                        prev.set_right(node_ptr);
-                    } else {
-                        head = node_ptr;
-                    }
-                    prev = node_ptr;
-                }
-                head
-            };
-            unsafe { self.merge_sorted_list(self_head, other_head) }
-        };
-        self.inner_init_from_sorted_list(head, tol_cnt);
+    /// Returns the number of elements whose keys fall in the range. The range is given as for
+    /// [`range`].
+    ///
+    /// Nodes carry no subtree sizes, so this walks the range: O(log n + k) time complexity,
+    /// where k is the returned count.
+    ///
+    /// [`range`]: #method.range
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = (0..10).map(|i| (i, i)).collect();
+    /// assert_eq!(map.count_range(3..6), 3);
+    /// assert_eq!(map.count_range(..), 10);
+    /// assert_eq!(map.count_range(20..), 0);
+    /// ```
+    pub fn count_range<T: ?Sized, R>(&self, range: R) -> usize
+    where
+        T: Ord,
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+    {
+        self.range(range).count()
     }
 
-    /// Merge two sorted lists into one list. Drop the element of `self_head` if keys collide.
-    unsafe fn merge_sorted_list(
-        &mut self,
-        mut self_head: AVLNodePtr,
-        mut other_head: AVLNodePtr,
-    ) -> (AVLNodePtr, usize) {
-        let mut cnt = 0;
-        let mut head = ptr::null_mut();
-        let mut prev = ptr::null_mut() as AVLNodePtr;
-        while self_head.not_null() || other_head.not_null() {
-            let left_entry = self_head.avl_node_deref_to_entry::<K, V>();
-            let right_entry = other_head.avl_node_deref_to_entry::<K, V>();
-            let res = if self_head.not_null() && other_head.not_null() {
-                (*left_entry.key()).cmp(&*right_entry.key())
+    /// Returns the number of leading keys, in incremental order, for which `pred` returns true.
+    /// `pred` must be monotone: true for every key before some point and false after it, as
+    /// for `slice::partition_point`.
+    ///
+    /// The boundary is found by descending the tree in O(log n) time. Nodes carry no subtree
+    /// sizes, so its rank is then counted by walking from the closer end of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = (0..10).map(|i| (i * 10, i)).collect();
+    /// assert_eq!(map.partition_point(|k: &i32| *k < 35), 4);
+    /// assert_eq!(map.partition_point(|_: &i32| true), 10);
+    /// ```
+    pub fn partition_point<T: ?Sized, P>(&self, mut pred: P) -> usize
+    where
+        K: Borrow<T>,
+        P: FnMut(&T) -> bool,
+    {
+        let mut node = self.root.node;
+        let mut boundary = ptr::null_mut();
+        while node.not_null() {
+            if pred(node.key_ref::<K, V>().borrow()) {
+                node = node.right();
+            } else {
+                boundary = node;
+                node = node.left();
+            }
+        }
+        if boundary.is_null() {
+            return self.len();
+        }
+        let (mut front, mut back) = (self.first_node(), boundary);
+        let mut steps = 0;
+        loop {
+            if front == boundary {
+                return steps;
+            }
+            if back.is_null() {
+                return self.len() - steps;
+            }
+            front = front.next();
+            back = back.next();
+            steps += 1;
+        }
+    }
+
+    /// Removes all elements whose keys fall in the range, and returns the number of removed
+    /// elements. The range may be entered in the same ways as [`range`].
+    ///
+    /// [`range`]: struct.OrdMap.html#method.range
+    ///
+    /// # Panics
+    ///
+    /// Panics if range `start > end`.
+    /// Panics if range `start == end` and both bounds are `Excluded`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<_, _> = (0..10).map(|i| (i, -i)).collect();
+    /// assert_eq!(map.remove_range(3..7), 4);
+    /// assert_eq!(map.len(), 6);
+    /// assert!(!map.contains_key(&3));
+    /// assert!(map.contains_key(&7));
+    /// ```
+    pub fn remove_range<T: ?Sized, R>(&mut self, range: R) -> usize
+    where
+        T: Ord,
+        K: Borrow<T>,
+        R: RangeBounds<T>,
+    {
+        let (front, back, _) = self.inner_range(range);
+        let mut cnt = 0;
+        let mut node = front;
+        while node != back {
+            let next = node.next();
+            unsafe {
+                self.remove_node(node);
+            }
+            cnt += 1;
+            node = next;
+        }
+        cnt
+    }
+
+    /// Moves all elements from `other` into `Self`, leaving `other` empty.
+    /// O(n) time complexity. If the key ranges of both maps do not overlap, the entries are
+    /// concatenated without comparing keys.
+    ///
+    /// If the allocators of both maps are interchangeable, as `Global` is, the entries are not
+    /// reallocated: the memory pages of `other` are handed over to `self`, and are released by
+    /// the allocator of `self` from now on. Otherwise the entries are moved into pages of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut a = OrdMap::new();
+    /// a.insert(1, "a");
+    /// a.insert(2, "b");
+    /// a.insert(3, "c");
+    ///
+    /// let mut b = OrdMap::new();
+    /// b.insert(3, "d");
+    /// b.insert(4, "e");
+    /// b.insert(5, "f");
+    ///
+    /// a.append(&mut b);
+    ///
+    /// assert_eq!(a.len(), 5);
+    /// assert_eq!(b.len(), 0);
+    ///
+    /// assert_eq!(a[&1], "a");
+    /// assert_eq!(a[&2], "b");
+    /// assert_eq!(a[&3], "d");
+    /// assert_eq!(a[&4], "e");
+    /// assert_eq!(a[&5], "f");
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        self.inner_append(other, OnConflict::Replace);
+    }
+
+    /// Moves all elements from `other` into `Self`, leaving `other` empty. Unlike `append`, the
+    /// entries of `self` are kept if keys collide, and those of `other` are dropped.
+    /// O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut a = OrdMap::new();
+    /// a.insert(1, "a");
+    /// a.insert(2, "b");
+    ///
+    /// let mut b = OrdMap::new();
+    /// b.insert(2, "c");
+    /// b.insert(3, "d");
+    ///
+    /// a.append_keep_existing(&mut b);
+    ///
+    /// assert_eq!(a.len(), 3);
+    /// assert_eq!(b.len(), 0);
+    /// assert_eq!(a[&2], "b");
+    /// assert_eq!(a[&3], "d");
+    /// ```
+    pub fn append_keep_existing(&mut self, other: &mut Self) {
+        self.inner_append(other, OnConflict::KeepExisting);
+    }
+
+    /// Moves all elements from `other` into `Self`, leaving `other` empty, and returns the
+    /// entries of `self` displaced by colliding keys, in ascending key order.
+    /// O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut a = OrdMap::new();
+    /// a.insert(1, "a");
+    /// a.insert(2, "b");
+    /// a.insert(3, "c");
+    ///
+    /// let mut b = OrdMap::new();
+    /// b.insert(2, "d");
+    /// b.insert(3, "e");
+    /// b.insert(4, "f");
+    ///
+    /// let conflicts = a.append_report_conflicts(&mut b);
+    ///
+    /// assert_eq!(conflicts, vec![(2, "b"), (3, "c")]);
+    /// assert_eq!(a.len(), 4);
+    /// assert_eq!(a[&2], "d");
+    /// assert_eq!(a[&3], "e");
+    /// ```
+    pub fn append_report_conflicts(&mut self, other: &mut Self) -> Vec<(K, V)> {
+        let mut conflicts = Vec::new();
+        self.inner_append(other, OnConflict::Report(&mut conflicts));
+        conflicts
+    }
+
+    /// Moves all elements from `other` into `Self`. If a key is present in both maps, the key
+    /// of `self` is kept, and its value is replaced by `combine(&key, self_value, other_value)`.
+    /// O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut a: OrdMap<_, _> = vec![("x", 1), ("y", 2)].into_iter().collect();
+    /// let b: OrdMap<_, _> = vec![("y", 10), ("z", 20)].into_iter().collect();
+    ///
+    /// a.union_with(b, |_, a, b| a + b);
+    ///
+    /// assert_eq!(a.len(), 3);
+    /// assert_eq!(a[&"y"], 12);
+    /// assert_eq!(a[&"z"], 20);
+    /// ```
+    pub fn union_with<F: FnMut(&K, V, V) -> V>(&mut self, mut other: Self, mut combine: F) {
+        self.inner_append(&mut other, OnConflict::Combine(&mut combine));
+    }
+
+    /// Inserts all pairs of `iter`, which may come in any order. As with `insert`, a later pair
+    /// replaces an earlier one with the same key, and both replace a present element.
+    ///
+    /// The pairs are sorted first, merged with the elements of the map, and the tree is built
+    /// once, so there is no rebalancing per element. O(m log m + n) time complexity, where m is
+    /// the number of pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<_, _> = (0..5).map(|i| (i, 0)).collect();
+    /// map.bulk_insert(vec![(7, 1), (3, 1), (7, 2)]);
+    ///
+    /// assert_eq!(map.len(), 6);
+    /// assert_eq!(map[&3], 1);
+    /// assert_eq!(map[&7], 2);
+    /// ```
+    pub fn bulk_insert<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let mut pairs: Vec<(K, V)> = iter.into_iter().collect();
+        if pairs.is_empty() {
+            return;
+        }
+        // after reversing, the stable sort puts the last of equal keys first, and `dedup_by`
+        // keeps the first one
+        pairs.reverse();
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        pairs.dedup_by(|a, b| a.0.cmp(&b.0) == Ordering::Equal);
+
+        let mut other = Self::new_in(self.allocator().clone());
+        other.entry_fastbin.reserve(pairs.len());
+        let cnt = pairs.len();
+        let mut head = ptr::null_mut();
+        let mut prev: AVLNodePtr = ptr::null_mut();
+        for (k, v) in pairs {
+            let node_ptr = other.entry_alloc(k, v).node_ptr();
+            node_ptr.set_right(ptr::null_mut());
+            if prev.not_null() {
+                prev.set_right(node_ptr);
+            } else {
+                head = node_ptr;
+            }
+            prev = node_ptr;
+        }
+        other.inner_init_from_sorted_list(head, cnt);
+        self.append(&mut other);
+    }
+
+    fn inner_append(&mut self, other: &mut Self, on_conflict: OnConflict<K, V>) {
+        if other.len() == 0 {
+            return;
+        }
+
+        if self.len() == 0 {
+            mem::swap(self, other);
+            return;
+        }
+
+        // If the key ranges do not overlap, the two sorted lists can simply be concatenated.
+        let disjoint = {
+            let (self_first, self_last) = (
+                self.first_node().key_ref::<K, V>(),
+                self.last_node().key_ref::<K, V>(),
+            );
+            let (other_first, other_last) = (
+                other.first_node().key_ref::<K, V>(),
+                other.last_node().key_ref::<K, V>(),
+            );
+            if self_last.cmp(other_first) == Ordering::Less {
+                Some(Ordering::Less)
+            } else if other_last.cmp(self_first) == Ordering::Less {
+                Some(Ordering::Greater)
+            } else {
+                None
+            }
+        };
+
+        let tol_cnt = self.len() + other.len();
+        let other_cnt = other.len();
+        let mut other_tail = other.last_node();
+        let mut other_head = unsafe { avl_node::avl_tree_convert_to_list(&mut other.root) };
+        other.count = 0;
+        other.reset_bounds();
+        if self.allocator().is_interchangeable(other.allocator()) {
+            // The entries of `other` are linked into `self` where they are, and its pages are
+            // handed over to the `Fastbin` of `self`, so nothing is allocated or copied.
+            self.entry_fastbin.absorb(&mut other.entry_fastbin);
+        } else {
+            // The allocator of `self` may not free the pages of `other`, so the entries are moved
+            // into pages of `self`.
+            other_head = unsafe { self.move_list_from(&mut other.entry_fastbin, other_head) };
+            other_tail = other_head;
+            while other_tail.right().not_null() {
+                other_tail = other_tail.right();
+            }
+        }
+
+        if let Some(order) = disjoint {
+            // Build a tree from `other` without the element closest to `self`, and join them
+            // with that element in the middle, so the nodes of `self` are left in place.
+            let (mid, mut head) = if order == Ordering::Less {
+                (other_head, other_head.right())
+            } else {
+                (other_tail, other_head)
+            };
+            unsafe {
+                let mut other_root = AVLRoot {
+                    node: self.recursive_build_from_sorted_list(
+                        &mut head as *mut AVLNodePtr,
+                        0,
+                        other_cnt as isize - 1,
+                    ),
+                };
+                self.root = if order == Ordering::Less {
+                    avl_node::avl_join(&mut self.root, mid, &mut other_root)
+                } else {
+                    avl_node::avl_join(&mut other_root, mid, &mut self.root)
+                };
+            }
+            self.count = tol_cnt;
+            self.reset_bounds();
+            return;
+        }
+
+        let self_head = unsafe { avl_node::avl_tree_convert_to_list(&mut self.root) };
+        self.count = 0;
+        self.reset_bounds();
+        let (head, tol_cnt) = {
+            let mut unlinked = UnlinkedEntries {
+                lists: [self_head, other_head, ptr::null_mut()],
+                entry_fastbin: &self.entry_fastbin,
+                _marker: marker::PhantomData,
+            };
+            unsafe { Self::merge_sorted_list(&mut unlinked, on_conflict) }
+        };
+        self.inner_init_from_sorted_list(head, tol_cnt);
+    }
+
+    /// Merge the sorted lists `lists[0]` of `self` and `lists[1]` of `other` into `lists[2]`,
+    /// and return the merged list. Colliding keys are resolved as `on_conflict` says.
+    ///
+    /// Every entry stays in one of the lists of `unlinked`, so all of them are freed if `cmp`
+    /// panics midway.
+    unsafe fn merge_sorted_list(
+        unlinked: &mut UnlinkedEntries<K, V, A>,
+        mut on_conflict: OnConflict<K, V>,
+    ) -> (AVLNodePtr, usize) {
+        let mut cnt = 0;
+        let mut prev = ptr::null_mut() as AVLNodePtr;
+        while unlinked.lists[0].not_null() || unlinked.lists[1].not_null() {
+            let (self_head, other_head) = (unlinked.lists[0], unlinked.lists[1]);
+            let left_entry = self_head.avl_node_deref_to_entry::<K, V>();
+            let right_entry = other_head.avl_node_deref_to_entry::<K, V>();
+            let res = if self_head.not_null() && other_head.not_null() {
+                (*left_entry.key()).cmp(&*right_entry.key())
             } else if self_head.not_null() {
                 Ordering::Less
             } else {
                 Ordering::Greater
             };
             cnt += 1;
-            let node = match res {
+            // the key of a combined entry of `other`, dropped once the merged entry is linked
+            let mut other_key = None;
+            let (node, collided) = match res {
                 Ordering::Less => {
-                    let ret = self_head;
-                    self_head = self_head.right();
-                    ret
+                    unlinked.lists[0] = self_head.right();
+                    (self_head, None)
                 }
                 Ordering::Greater => {
-                    let ret = other_head;
-                    other_head = other_head.right();
-                    ret
+                    unlinked.lists[1] = other_head.right();
+                    (other_head, None)
                 }
                 Ordering::Equal => {
-                    let ret = other_head;
-                    self_head = self_head.right();
-                    other_head = other_head.right();
-                    ptr::drop_in_place(left_entry);
-                    self.entry_fastbin.del(left_entry as VoidPtr);
-                    ret
+                    unlinked.lists[0] = self_head.right();
+                    unlinked.lists[1] = other_head.right();
+                    match on_conflict {
+                        OnConflict::KeepExisting => (self_head, Some(right_entry)),
+                        OnConflict::Replace | OnConflict::Report(_) => {
+                            (other_head, Some(left_entry))
+                        }
+                        OnConflict::Combine(ref mut combine) => {
+                            other_key = Some(ptr::read(right_entry.key()));
+                            let other_value = ptr::read(right_entry.value());
+                            unlinked.entry_fastbin.del(right_entry as VoidPtr);
+                            let moved = ValueMovedOut {
+                                entry: left_entry,
+                                entry_fastbin: unlinked.entry_fastbin,
+                            };
+                            let self_value = ptr::read(left_entry.value());
+                            let value = combine(&*left_entry.key(), self_value, other_value);
+                            mem::forget(moved);
+                            ptr::write(left_entry.value(), value);
+                            (self_head, None)
+                        }
+                    }
                 }
             };
             node.set_left(prev);
             node.set_right(ptr::null_mut());
             if prev.is_null() {
-                head = node;
+                unlinked.lists[2] = node;
             } else {
                 prev.set_right(node);
             }
             prev = node;
+            if let Some(entry) = collided {
+                match on_conflict {
+                    OnConflict::Report(ref mut conflicts) => {
+                        conflicts.push((ptr::read(entry.key()), ptr::read(entry.value())))
+                    }
+                    _ => ptr::drop_in_place(entry),
+                }
+                unlinked.entry_fastbin.del(entry as VoidPtr);
+            }
+            drop(other_key);
         }
-        (head, cnt)
+        (mem::replace(&mut unlinked.lists[2], ptr::null_mut()), cnt)
     }
 
     /// recursive build AVL from a sorted list which does not contain duplicate keys.
@@ -989,6 +2331,7 @@ where
                 count as isize,
             );
         }
+        self.reset_bounds();
     }
 
     unsafe fn recursive_build_from_sorted_list(
@@ -1037,7 +2380,7 @@ where
     /// assert_eq!(letters[&'u'], 1);
     /// assert_eq!(letters.get(&'y'), None);
     /// ```
-    pub fn entry(&mut self, key: K) -> Entry<K, V> {
+    pub fn entry(&mut self, key: K) -> Entry<K, V, A> {
         let (duplicate, parent, link) = unsafe { self.find_duplicate(&key) };
         if duplicate.is_null() {
             return Entry::Vacant(VacantEntry {
@@ -1055,9 +2398,86 @@ where
         };
     }
 
+    /// Gets the entry of a borrowed key in the map for in-place manipulation. Unlike `entry`,
+    /// the key is only converted into an owned `K` if a value is inserted into a vacant entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut words: OrdMap<String, usize> = OrdMap::new();
+    ///
+    /// for word in "the cat saw the dog".split(' ') {
+    ///     *words.entry_ref(word).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(words.get("the"), Some(&2));
+    /// assert_eq!(words.get("dog"), Some(&1));
+    /// ```
+    pub fn entry_ref<'a, 'b, Q: ?Sized>(&'a mut self, q: &'b Q) -> EntryRef<'a, 'b, K, Q, V, A>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ToOwned<Owned = K>,
+    {
+        let (duplicate, parent, link) = unsafe { self.find_duplicate(q) };
+        if duplicate.is_null() {
+            EntryRef::Vacant(VacantEntryRef {
+                key: q,
+                parent,
+                link,
+                ord_map_mut: self,
+            })
+        } else {
+            EntryRef::Occupied(OccupiedEntry {
+                key: None,
+                node: duplicate,
+                ord_map_mut: self,
+            })
+        }
+    }
+
+    /// Returns a mutable reference to the value of `key`, inserting the result of `f` first if
+    /// the key is absent. `f` is not called if the key is present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// *map.get_or_insert_with("a", || 1) += 10;
+    /// assert_eq!(*map.get_or_insert_with("a", || unreachable!()), 11);
+    /// ```
+    #[inline]
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        self.entry(key).or_insert_with(f)
+    }
+
+    /// Returns a mutable reference to the value of `key`, inserting `V::default()` first if
+    /// the key is absent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<i32, Vec<char>> = OrdMap::new();
+    /// map.get_mut_or_insert_default(1).push('a');
+    /// map.get_mut_or_insert_default(1).push('b');
+    /// assert_eq!(map[&1], ['a', 'b']);
+    /// ```
+    #[inline]
+    pub fn get_mut_or_insert_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        self.entry(key).or_default()
+    }
+
     /// Returns the cursors of a found pos.
     #[inline]
-    pub fn find_cursors<Q>(&mut self, q: &Q) -> Cursors<K, V>
+    pub fn find_cursors<Q>(&mut self, q: &Q) -> Cursors<K, V, A>
     where
         K: Borrow<Q>,
         Q: Ord,
@@ -1075,153 +2495,256 @@ where
         self.root.node.height()
     }
 
-    /// Returns true if the map contains no element.
+    /// Returns the number of bytes allocated on the heap by the map, including memory kept
+    /// for reuse.
     ///
     /// # Examples
+    ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
     ///
     /// let mut map = OrdMap::new();
-    /// assert!(map.is_empty());
-    /// map.insert(1, 1);
-    /// assert!(!map.is_empty());
+    /// assert_eq!(map.memory_usage(), 0);
+    /// map.insert(1, 2);
+    /// assert!(map.memory_usage() > 0);
     /// ```
     #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.count == 0
+    pub fn memory_usage(&self) -> usize {
+        self.entry_fastbin.allocated_bytes()
     }
 
-    /// Returns the number of elements in the map.
+    /// Reserves memory for at least `additional` more elements, so inserting them does not
+    /// allocate.
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
     ///
-    /// let mut a = OrdMap::new();
-    /// assert_eq!(a.len(), 0);
-    /// a.insert(1, "a");
-    /// assert_eq!(a.len(), 1);
+    /// let mut map = OrdMap::new();
+    /// map.reserve(100);
+    /// let usage = map.memory_usage();
+    /// for i in 0..100 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.memory_usage(), usage);
     /// ```
-    #[inline]
-    pub fn len(&self) -> usize {
-        self.count
+    pub fn reserve(&mut self, additional: usize) {
+        self.entry_fastbin.reserve(additional);
     }
 
+    /// Preallocate entries for an `extend` of `additional` elements, so the pages are
+    /// allocated at once instead of growing during the insert loop.
     #[inline]
-    fn first_node(&self) -> AVLNodePtr {
-        self.root.node.first_node()
+    fn extend_reserve(&mut self, additional: usize) {
+        self.reserve(additional);
     }
 
+    /// Returns true if the map contains no element.
+    ///
+    /// # Examples
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// assert!(map.is_empty());
+    /// map.insert(1, 1);
+    /// assert!(!map.is_empty());
+    /// ```
     #[inline]
-    fn last_node(&self) -> AVLNodePtr {
-        self.root.node.last_node()
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
     }
 
-    /// Creates an empty `OrdMap`.
-    ///
-    /// The hash map is initially created with a capacity of 0, so it will not allocate until it
-    /// is first inserted into.
+    /// Returns the number of elements in the map.
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
-    /// let mut map: OrdMap<&str, isize> = OrdMap::new();
+    ///
+    /// let mut a = OrdMap::new();
+    /// assert_eq!(a.len(), 0);
+    /// a.insert(1, "a");
+    /// assert_eq!(a.len(), 1);
     /// ```
     #[inline]
-    pub fn new() -> Self {
-        OrdMap {
-            root: Default::default(),
-            count: 0,
-            entry_fastbin: Fastbin::new(mem::size_of::<AVLEntry<K, V>>()),
-            _marker: marker::PhantomData,
-        }
+    pub fn len(&self) -> usize {
+        self.count
     }
 
     #[inline]
-    fn entry_alloc(&mut self, key: K, value: V) -> *mut AVLEntry<K, V> {
-        let entry = self.entry_fastbin.alloc() as *mut AVLEntry<K, V>;
-        debug_assert!(!entry.is_null());
-        unsafe {
-            ptr::write(entry.key(), key);
-            ptr::write(entry.value(), value);
-        }
-        entry
+    fn first_node(&self) -> AVLNodePtr {
+        self.first
     }
 
-    fn deep_clone_node(&mut self, parent: AVLNodePtr, other_node: AVLNodePtr) -> AVLNodePtr
-    where
+    #[inline]
+    fn last_node(&self) -> AVLNodePtr {
+        self.last
+    }
+
+
+    /// Clone the subtree of `other_node` into `*link`. Every node is linked into the tree as
+    /// soon as it is allocated, so the tree can be dropped without leaking if `clone` panics.
+    fn deep_clone_node(
+        &mut self,
+        link: *mut AVLNodePtr,
+        parent: AVLNodePtr,
+        other_node: AVLNodePtr,
+    ) where
         K: Clone,
         V: Clone,
     {
         if other_node.is_null() {
-            return ptr::null_mut();
+            return;
         }
         let entry = self.entry_alloc(
             (*other_node.key_ref::<K, V>()).clone(),
             (*other_node.value_ref::<K, V>()).clone(),
         );
         let node = entry.node_ptr();
-        node.reset(
-            self.deep_clone_node(node, other_node.left()),
-            self.deep_clone_node(node, other_node.right()),
-            parent,
-            other_node.height(),
-        );
-        node
+        node.reset(ptr::null_mut(), ptr::null_mut(), parent, other_node.height());
+        unsafe {
+            *link = node;
+        }
+        self.deep_clone_node(node.left_mut(), node, other_node.left());
+        self.deep_clone_node(node.right_mut(), node, other_node.right());
     }
 
-    fn clone_from(t: &OrdMap<K, V>) -> Self
+    fn deep_clone(t: &OrdMap<K, V, A>) -> Self
     where
         K: Clone,
         V: Clone,
     {
         let mut tree = OrdMap {
             root: Default::default(),
+            first: ptr::null_mut(),
+            last: ptr::null_mut(),
             count: 0,
-            entry_fastbin: Fastbin::new(mem::size_of::<AVLEntry<K, V>>()),
+            entry_fastbin: Fastbin::new_in(
+                mem::size_of::<AVLEntry<K, V>>(),
+                mem::align_of::<AVLEntry<K, V>>(),
+                t.allocator().clone(),
+            ),
             _marker: marker::PhantomData,
         };
-        tree.root.node = tree.deep_clone_node(ptr::null_mut(), t.root.node);
+        let link = &mut tree.root.node as *mut AVLNodePtr;
+        tree.deep_clone_node(link, ptr::null_mut(), t.root.node);
         tree.count = t.count;
+        tree.reset_bounds();
         tree
     }
 
-    #[inline]
-    unsafe fn find_duplicate(&mut self, key: &K) -> (AVLNodePtr, AVLNodePtr, *mut AVLNodePtr) {
-        let mut cmp_node_ref = &mut self.root.node as *mut AVLNodePtr;
+    /// Returns a clone of the map rebuilt with minimal height, whatever the shape of `self` is.
+    /// Entries are cloned in a single in-order pass, which is friendlier to the cache than the
+    /// recursive shape-preserving `clone`.
+    /// O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// for i in 0..100 {
+    ///     map.insert(i, i);
+    /// }
+    /// let clone = map.clone_balanced();
+    /// assert!(clone == map);
+    /// assert!(clone.check_balanced());
+    /// assert!(clone.height() <= map.height());
+    /// ```
+    pub fn clone_balanced(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let mut tree = OrdMap::new_in(self.allocator().clone());
+        // chain the cloned entries by `right` under the root first, so they are all dropped
+        // if `clone` panics
+        let mut link = &mut tree.root.node as *mut AVLNodePtr;
         let mut parent = ptr::null_mut();
-        while (*cmp_node_ref).not_null() {
-            parent = *cmp_node_ref;
-            match key.cmp(parent.key_ref::<K, V>()) {
-                Ordering::Less => {
-                    cmp_node_ref = parent.left_mut();
-                }
+        let mut node = self.first_node();
+        while node.not_null() {
+            let entry = tree.entry_alloc(
+                (*node.key_ref::<K, V>()).clone(),
+                (*node.value_ref::<K, V>()).clone(),
+            );
+            let new_node = entry.node_ptr();
+            new_node.reset(ptr::null_mut(), ptr::null_mut(), parent, 1);
+            unsafe {
+                *link = new_node;
+            }
+            link = new_node.right_mut();
+            parent = new_node;
+            node = node.next();
+        }
+        let head = mem::replace(&mut tree.root.node, ptr::null_mut());
+        tree.inner_init_from_sorted_list(head, self.count);
+        tree
+    }
+
+    #[inline]
+    unsafe fn find_duplicate<Q: ?Sized>(
+        &mut self,
+        key: &Q,
+    ) -> (AVLNodePtr, AVLNodePtr, *mut AVLNodePtr)
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        self.find_duplicate_by(|k| key.cmp(k.borrow()))
+    }
+
+    #[inline]
+    fn find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        self.find_node_by(|k| q.cmp(k.borrow()))
+    }
+
+    #[inline]
+    fn lower_bound_find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        let mut node = self.root.node;
+        let mut res = ptr::null_mut();
+        while node.not_null() {
+            match q.cmp(node.key_ref::<K, V>().borrow()) {
                 Ordering::Equal => {
-                    return (parent, parent, cmp_node_ref);
+                    return node;
+                }
+                Ordering::Less => {
+                    res = node;
+                    node = node.left();
                 }
                 Ordering::Greater => {
-                    cmp_node_ref = parent.right_mut();
+                    node = node.right();
                 }
             }
         }
-        (ptr::null_mut(), parent, cmp_node_ref)
+        res
     }
 
     #[inline]
-    fn find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
+    fn upper_bound_find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
     where
         K: Borrow<Q>,
         Q: Ord,
     {
         let mut node = self.root.node;
+        let mut res = ptr::null_mut();
         while node.not_null() {
             match q.cmp(node.key_ref::<K, V>().borrow()) {
                 Ordering::Equal => {
-                    return node;
+                    node = node.right();
                 }
                 Ordering::Less => {
+                    res = node;
                     node = node.left();
                 }
                 Ordering::Greater => {
@@ -1229,11 +2752,12 @@ where
                 }
             }
         }
-        ptr::null_mut()
+        res
     }
 
+    /// Find the last node whose key is not greater than `q`.
     #[inline]
-    fn lower_bound_find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
+    fn rev_lower_bound_find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
     where
         K: Borrow<Q>,
         Q: Ord,
@@ -1246,10 +2770,10 @@ where
                     return node;
                 }
                 Ordering::Less => {
-                    res = node;
                     node = node.left();
                 }
                 Ordering::Greater => {
+                    res = node;
                     node = node.right();
                 }
             }
@@ -1257,8 +2781,9 @@ where
         res
     }
 
+    /// Find the last node whose key is less than `q`.
     #[inline]
-    fn upper_bound_find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
+    fn rev_upper_bound_find_node<Q: ?Sized>(&self, q: &Q) -> AVLNodePtr
     where
         K: Borrow<Q>,
         Q: Ord,
@@ -1268,13 +2793,13 @@ where
         while node.not_null() {
             match q.cmp(node.key_ref::<K, V>().borrow()) {
                 Ordering::Equal => {
-                    node = node.right();
+                    node = node.left();
                 }
                 Ordering::Less => {
-                    res = node;
                     node = node.left();
                 }
                 Ordering::Greater => {
+                    res = node;
                     node = node.right();
                 }
             }
@@ -1282,9 +2807,27 @@ where
         res
     }
 
+    #[inline]
+    fn node_to_key_value<'a>(node: AVLNodePtr) -> Option<(&'a K, &'a V)> {
+        if node.is_null() {
+            None
+        } else {
+            Some((node.key_ref::<K, V>(), node.value_ref::<K, V>()))
+        }
+    }
+
+    #[inline]
+    fn node_to_key_value_mut<'a>(node: AVLNodePtr) -> Option<(&'a K, &'a mut V)> {
+        if node.is_null() {
+            None
+        } else {
+            Some((node.key_ref::<K, V>(), node.value_mut::<K, V>()))
+        }
+    }
+
     /// Return true if two tree are isomorphic.
     #[inline]
-    pub fn isomorphic(&self, other: &OrdMap<K, V>) -> bool {
+    pub fn isomorphic(&self, other: &OrdMap<K, V, A>) -> bool {
         if self.len() != other.len() {
             return false;
         }
@@ -1296,11 +2839,79 @@ where
         self.root.node.check_valid()
     }
 
+    /// Returns the height of the tree, which is 0 for an empty map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// assert_eq!(map.height(), 0);
+    /// for i in 0..7 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.height(), 3);
+    /// ```
+    #[inline]
+    pub fn height(&self) -> i32 {
+        self.root.node.height()
+    }
+
+    /// Returns the number of nodes by walking the whole tree, which always equals `len()` unless
+    /// the tree is corrupted.
+    /// O(n) time complexity
+    pub fn node_count(&self) -> usize {
+        self.root.node.get_node_num() as usize
+    }
+
+    /// Return true if the height of every node is correct and the heights of its two subtrees
+    /// differ by at most one.
+    #[inline]
+    pub fn is_balanced(&self) -> bool {
+        self.root.node.check_valid()
+    }
+
     /// Return true if tree is a BST.
     pub fn check_ord_valid(&self) -> bool {
         self.bst_check() && self.bst_check_reverse()
     }
 
+    /// Check every structural invariant of the map, and panic with a message naming the first
+    /// one violated: the AVL balance and heights, the order of keys in both directions, the
+    /// number of nodes against `len()`, and the cached first and last nodes.
+    ///
+    /// Meant as a single oracle for fuzzers and tests. O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<_, _> = (0..100).map(|i| (i, i)).collect();
+    /// map.remove(&42);
+    /// map.assert_invariants();
+    /// ```
+    pub fn assert_invariants(&self) {
+        assert!(
+            self.is_balanced(),
+            "OrdMap invariant violated: node heights are wrong or a node is unbalanced"
+        );
+        assert!(
+            self.check_ord_valid(),
+            "OrdMap invariant violated: keys are not in strictly incremental order"
+        );
+        assert_eq!(
+            self.node_count(),
+            self.len(),
+            "OrdMap invariant violated: len() does not match the number of nodes"
+        );
+        assert!(
+            self.first == self.root.node.first_node() && self.last == self.root.node.last_node(),
+            "OrdMap invariant violated: cached first or last node is stale"
+        );
+    }
+
     fn bst_check(&self) -> bool {
         let mut iter = self.iter();
         let first = iter.next();
@@ -1351,20 +2962,6 @@ where
         cnt == self.len()
     }
 
-    #[inline]
-    unsafe fn remove_node(&mut self, node: AVLNodePtr) -> Option<(K, V)> {
-        if node.is_null() || node.empty() {
-            return None;
-        }
-        avl_node::erase_node(node, self.get_root_ptr());
-        node.set_parent(node);
-        self.count -= 1;
-        let old_entry = node.avl_node_deref_to_entry::<K, V>();
-        let res = Some((ptr::read(old_entry.key()), ptr::read(old_entry.value())));
-        self.entry_fastbin.del(old_entry as VoidPtr);
-        res
-    }
-
     /// Removes a key from the map, returning the stored key and value if the
     /// key was previously in the map.
     ///
@@ -1391,108 +2988,473 @@ where
         unsafe { self.remove_node(node) }
     }
 
-    /// Returns true if the map contains a value for the specified key.
-    ///
-    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
-    /// form *must* match those for the key type.
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, remove all pairs `(k, v)` such that `f(&k, &mut v)` returns `false`.
+    /// The elements are visited in ascending key order.
+    /// O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
+    /// map.retain(|&k, _| k % 2 == 0);
+    /// assert_eq!(map.len(), 4);
+    /// assert_eq!(map.keys().cloned().collect::<Vec<_>>(), [0, 2, 4, 6]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let mut node = self.first_node();
+        while node.not_null() {
+            let next = node.next();
+            if !f(node.key_ref::<K, V>(), node.value_mut::<K, V>()) {
+                unsafe {
+                    self.remove_node(node);
+                }
+            }
+            node = next;
+        }
+    }
+
+    /// Consumes the map and returns its (key, value) pairs in incremental order of keys.
+    ///
+    /// Unlike collecting from `into_iter`, the tree is not torn down node by node: the pairs
+    /// are moved out in a single in-order walk, and the memory of all entries is released at
+    /// once with the map.
+    /// O(n) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = vec![(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+    /// assert_eq!(map.into_vec(), [(1, "a"), (2, "b"), (3, "c")]);
+    /// ```
+    pub fn into_vec(mut self) -> Vec<(K, V)> {
+        let mut vec = Vec::with_capacity(self.len());
+        let mut node = self.first_node();
+        while node.not_null() {
+            let entry = node.avl_node_deref_to_entry::<K, V>();
+            node = node.next();
+            unsafe {
+                vec.push((ptr::read(entry.key()), ptr::read(entry.value())));
+            }
+        }
+        // every pair was moved out, so only the pages are left to free
+        self.root.node = ptr::null_mut();
+        self.reset_bounds();
+        self.count = 0;
+        vec
+    }
+
+    /// Returns true if the map contains a value for the specified key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
+    /// form *must* match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.contains_key(&1), true);
+    /// assert_eq!(map.contains_key(&2), false);
+    /// ```
+    #[inline]
+    pub fn contains_key<Q: ?Sized>(&self, q: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        self.find_node(q).not_null()
+    }
+
+    /// Returns true if every key of `self` is also a key of `other`. Values are not compared.
+    /// Both key sequences are walked once in order, stopping at the first missing key.
+    /// O(n + m) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let a: OrdMap<_, _> = vec![(1, "a"), (3, "c")].into_iter().collect();
+    /// let b: OrdMap<_, _> = vec![(1, "x"), (2, "y"), (3, "z")].into_iter().collect();
+    /// assert!(a.keys_subset_of(&b));
+    /// assert!(!b.keys_subset_of(&a));
+    /// ```
+    pub fn keys_subset_of(&self, other: &OrdMap<K, V, A>) -> bool {
+        if self.len() > other.len() {
+            return false;
+        }
+        let mut other_keys = other.keys();
+        for key in self.keys() {
+            loop {
+                match other_keys.next().map(|other_key| other_key.cmp(key)) {
+                    Some(Ordering::Less) => continue,
+                    Some(Ordering::Equal) => break,
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns true if every key of `other` is also a key of `self`. Values are not compared.
+    /// O(n + m) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let a: OrdMap<_, _> = vec![(1, "a"), (3, "c")].into_iter().collect();
+    /// let b: OrdMap<_, _> = vec![(1, "x"), (2, "y"), (3, "z")].into_iter().collect();
+    /// assert!(b.keys_superset_of(&a));
+    /// assert!(!a.keys_superset_of(&b));
+    /// ```
+    #[inline]
+    pub fn keys_superset_of(&self, other: &OrdMap<K, V, A>) -> bool {
+        other.keys_subset_of(self)
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
+    /// form *must* match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.get(&2), None);
+    /// ```
+    #[inline]
+    pub fn get<Q: ?Sized>(&self, q: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        let node = self.find_node(q);
+        if node.is_null() {
+            None
+        } else {
+            Some(node.value_ref::<K, V>())
+        }
+    }
+
+    /// Returns a handle to the node of the key, which can be read with `get_at` and stepped to
+    /// its neighbours with `successor` and `predecessor` without another lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, "a");
+    /// let handle = map.find_ref(&1).unwrap();
+    /// assert_eq!(map.get_at(handle), (&1, &"a"));
+    /// assert!(map.find_ref(&2).is_none());
+    /// ```
+    #[inline]
+    pub fn find_ref<Q: ?Sized>(&self, q: &Q) -> Option<NodeRef<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        NodeRef::from_node(self.find_node(q))
+    }
+
+    /// Returns the key and the value of the node behind the handle.
+    #[inline]
+    pub fn get_at<'a>(&'a self, h: NodeRef<'a, K, V>) -> (&'a K, &'a V) {
+        (h.node.key_ref::<K, V>(), h.node.value_ref::<K, V>())
+    }
+
+    /// Returns a handle to the node following `h` in key order, or `None` if `h` holds the
+    /// largest key.
+    /// O(1) amortized time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = (0..10).map(|i| (i, i * 10)).collect();
+    /// let handle = map.find_ref(&3).unwrap();
+    /// let next = map.successor(handle).unwrap();
+    /// assert_eq!(map.get_at(next), (&4, &40));
+    /// assert!(map.successor(map.find_ref(&9).unwrap()).is_none());
+    /// ```
+    #[inline]
+    pub fn successor<'a>(&'a self, h: NodeRef<'a, K, V>) -> Option<NodeRef<'a, K, V>> {
+        NodeRef::from_node(h.node.next())
+    }
+
+    /// Returns a handle to the node preceding `h` in key order, or `None` if `h` holds the
+    /// smallest key.
+    /// O(1) amortized time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = (0..10).map(|i| (i, i * 10)).collect();
+    /// let handle = map.find_ref(&3).unwrap();
+    /// let prev = map.predecessor(handle).unwrap();
+    /// assert_eq!(map.get_at(prev), (&2, &20));
+    /// assert!(map.predecessor(map.find_ref(&0).unwrap()).is_none());
+    /// ```
+    #[inline]
+    pub fn predecessor<'a>(&'a self, h: NodeRef<'a, K, V>) -> Option<NodeRef<'a, K, V>> {
+        NodeRef::from_node(h.node.prev())
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but
+    /// `Ord` on the borrowed form *must* match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert(1, "a");
+    /// if let Some(x) = map.get_mut(&1) {
+    ///     *x = "b";
+    /// }
+    /// assert_eq!(map[&1], "b");
+    /// ```
+    pub fn get_mut<Q: ?Sized>(&mut self, q: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        let node = self.find_node(q);
+        if node.is_null() {
+            None
+        } else {
+            Some(node.value_mut::<K, V>())
+        }
+    }
+
+    /// Returns the key-value pair with the greatest key less than or equal to `q`.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
+    /// form *must* match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = vec![(1, "a"), (3, "c"), (5, "e")].into_iter().collect();
+    /// assert_eq!(map.get_le(&3), Some((&3, &"c")));
+    /// assert_eq!(map.get_le(&4), Some((&3, &"c")));
+    /// assert_eq!(map.get_le(&0), None);
+    /// ```
+    pub fn get_le<Q: ?Sized>(&self, q: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        Self::node_to_key_value(self.rev_lower_bound_find_node(q))
+    }
+
+    /// Returns the key-value pair with the greatest key strictly less than `q`.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
+    /// form *must* match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = vec![(1, "a"), (3, "c"), (5, "e")].into_iter().collect();
+    /// assert_eq!(map.get_lt(&3), Some((&1, &"a")));
+    /// assert_eq!(map.get_lt(&6), Some((&5, &"e")));
+    /// assert_eq!(map.get_lt(&1), None);
+    /// ```
+    pub fn get_lt<Q: ?Sized>(&self, q: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        Self::node_to_key_value(self.rev_upper_bound_find_node(q))
+    }
+
+    /// Returns the key-value pair with the least key greater than or equal to `q`.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
+    /// form *must* match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = vec![(1, "a"), (3, "c"), (5, "e")].into_iter().collect();
+    /// assert_eq!(map.get_ge(&3), Some((&3, &"c")));
+    /// assert_eq!(map.get_ge(&2), Some((&3, &"c")));
+    /// assert_eq!(map.get_ge(&6), None);
+    /// ```
+    pub fn get_ge<Q: ?Sized>(&self, q: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        Self::node_to_key_value(self.lower_bound_find_node(q))
+    }
+
+    /// Returns the key-value pair with the least key strictly greater than `q`.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
+    /// form *must* match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = vec![(1, "a"), (3, "c"), (5, "e")].into_iter().collect();
+    /// assert_eq!(map.get_gt(&3), Some((&5, &"e")));
+    /// assert_eq!(map.get_gt(&0), Some((&1, &"a")));
+    /// assert_eq!(map.get_gt(&5), None);
+    /// ```
+    pub fn get_gt<Q: ?Sized>(&self, q: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord,
+    {
+        Self::node_to_key_value(self.upper_bound_find_node(q))
+    }
+
+    /// Returns the key-value pair with the least key in the map.
+    /// O(1) time complexity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: OrdMap<_, _> = vec![(3, "c"), (1, "a"), (5, "e")].into_iter().collect();
+    /// assert_eq!(map.first_key_value(), Some((&1, &"a")));
+    /// assert_eq!(map.last_key_value(), Some((&5, &"e")));
+    /// ```
+    #[inline]
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        Self::node_to_key_value(self.first_node())
+    }
+
+    /// Returns the key-value pair with the greatest key in the map.
+    /// O(1) time complexity
+    #[inline]
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        Self::node_to_key_value(self.last_node())
+    }
+
+    /// Returns the key-value pair with the least key in the map, with a mutable reference to
+    /// the value.
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
     ///
-    /// let mut map = OrdMap::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.contains_key(&1), true);
-    /// assert_eq!(map.contains_key(&2), false);
+    /// let mut map: OrdMap<_, _> = vec![(3, 30), (1, 10), (5, 50)].into_iter().collect();
+    /// if let Some((_, v)) = map.first_key_value_mut() {
+    ///     *v += 1;
+    /// }
+    /// assert_eq!(map[&1], 11);
     /// ```
     #[inline]
-    pub fn contains_key<Q: ?Sized>(&self, q: &Q) -> bool
-    where
-        K: Borrow<Q>,
-        Q: Ord,
-    {
-        self.find_node(q).not_null()
+    pub fn first_key_value_mut(&mut self) -> Option<(&K, &mut V)> {
+        Self::node_to_key_value_mut(self.first_node())
     }
 
-    /// Returns a reference to the value corresponding to the key.
+    /// Returns the key-value pair with the greatest key in the map, with a mutable reference
+    /// to the value.
+    #[inline]
+    pub fn last_key_value_mut(&mut self) -> Option<(&K, &mut V)> {
+        Self::node_to_key_value_mut(self.last_node())
+    }
+
+    /// Returns the first entry in the map for in-place manipulation. The key of this entry is
+    /// the minimum key in the map, so it can be inspected and then removed only if needed.
     ///
-    /// The key may be any borrowed form of the map's key type, but `Ord` on the borrowed
-    /// form *must* match those for the key type.
+    /// The entry is not created from an owned key, so `replace_entry` and `replace_key` panic
+    /// on it.
+    /// O(1) time complexity
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
     ///
-    /// let mut map = OrdMap::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.get(&1), Some(&"a"));
-    /// assert_eq!(map.get(&2), None);
+    /// let mut map: OrdMap<_, _> = vec![(1, "a"), (2, "b")].into_iter().collect();
+    /// if let Some(entry) = map.first_entry() {
+    ///     if *entry.key() < 2 {
+    ///         entry.remove();
+    ///     }
+    /// }
+    /// assert_eq!(map.first_key_value(), Some((&2, &"b")));
     /// ```
-    #[inline]
-    pub fn get<Q: ?Sized>(&self, q: &Q) -> Option<&V>
-    where
-        K: Borrow<Q>,
-        Q: Ord,
-    {
-        let node = self.find_node(q);
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<K, V, A>> {
+        let node = self.first_node();
         if node.is_null() {
-            None
-        } else {
-            Some(node.value_ref::<K, V>())
+            return None;
         }
+        Some(OccupiedEntry {
+            key: None,
+            node,
+            ord_map_mut: self,
+        })
     }
 
-    /// Returns a mutable reference to the value corresponding to the key.
+    /// Returns the last entry in the map for in-place manipulation. The key of this entry is
+    /// the maximum key in the map.
     ///
-    /// The key may be any borrowed form of the map's key type, but
-    /// `Ord` on the borrowed form *must* match those for the key type.
+    /// The entry is not created from an owned key, so `replace_entry` and `replace_key` panic
+    /// on it.
+    /// O(1) time complexity
     ///
     /// # Examples
     ///
     /// ```
     /// use hash_ord::ord_map::OrdMap;
     ///
-    /// let mut map = OrdMap::new();
-    /// map.insert(1, "a");
-    /// if let Some(x) = map.get_mut(&1) {
-    ///     *x = "b";
+    /// let mut map: OrdMap<_, _> = vec![(1, "a"), (2, "b")].into_iter().collect();
+    /// if let Some(mut entry) = map.last_entry() {
+    ///     *entry.get_mut() = "c";
     /// }
-    /// assert_eq!(map[&1], "b");
+    /// assert_eq!(map[&2], "c");
     /// ```
-    pub fn get_mut<Q: ?Sized>(&mut self, q: &Q) -> Option<&mut V>
-    where
-        K: Borrow<Q>,
-        Q: Ord,
-    {
-        let node = self.find_node(q);
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<K, V, A>> {
+        let node = self.last_node();
         if node.is_null() {
-            None
-        } else {
-            Some(node.value_mut::<K, V>())
-        }
-    }
-
-    #[inline]
-    fn link_post_insert(
-        &mut self,
-        new_node: AVLNodePtr,
-        parent: AVLNodePtr,
-        cmp_node_ref: *mut AVLNodePtr,
-    ) {
-        unsafe {
-            avl_node::link_node(new_node, parent, cmp_node_ref);
-        }
-        unsafe {
-            avl_node::node_post_insert(new_node, self.get_root_ptr());
+            return None;
         }
-        self.count += 1;
-    }
-
-    #[inline]
-    fn get_root_ptr(&mut self) -> AVLRootPtr {
-        &mut self.root as AVLRootPtr
+        Some(OccupiedEntry {
+            key: None,
+            node,
+            ord_map_mut: self,
+        })
     }
 
     /// Inserts a key-value pair into the map.
@@ -1518,17 +3480,34 @@ where
     #[inline]
     pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
         let (duplicate, parent, cmp_node_ref) = unsafe { self.find_duplicate(&key) };
-        let entry = self.entry_alloc(key, value);
-        if duplicate.is_null() {
-            self.link_post_insert(entry.node_ptr(), parent, cmp_node_ref);
-            None
-        } else {
-            unsafe {
-                let old_entry = duplicate.avl_node_deref_to_entry::<K, V>();
-                avl_node::avl_node_replace(duplicate, entry.node_ptr(), self.get_root_ptr());
-                let res = Some((ptr::read(old_entry.key()), ptr::read(old_entry.value())));
-                self.entry_fastbin.del(old_entry as VoidPtr);
-                res
+        self.insert_at(key, value, duplicate, parent, cmp_node_ref)
+    }
+
+    /// Inserts a key-value pair into the map, replacing both the stored key and value if the
+    /// key was already present, and returns the previous key and value.
+    ///
+    /// The given key always survives: even if it is only equal to the stored one, not
+    /// identical, the stored key is moved out and returned. Unlike [`insert`], the existing
+    /// node is updated in place, so no allocation happens on replacement.
+    ///
+    /// [`insert`]: #method.insert
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// assert_eq!(map.replace(37, "a"), None);
+    /// assert_eq!(map.replace(37, "b"), Some((37, "a")));
+    /// assert_eq!(map[&37], "b");
+    /// ```
+    pub fn replace(&mut self, key: K, value: V) -> Option<(K, V)> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Some(entry.replace_entry(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
             }
         }
     }
@@ -1672,30 +3651,363 @@ where
             _marker: marker::PhantomData,
         }
     }
+
+    /// Calls `f` on all key-value pairs in incremental order.
+    /// It walks the tree directly and is faster than driving an iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// let mut keys = Vec::new();
+    /// map.for_each(|key, _| keys.push(*key));
+    /// assert_eq!(keys, ["a", "b", "c"]);
+    /// ```
+    pub fn for_each<F: FnMut(&K, &V)>(&self, mut f: F) {
+        Self::recursive_for_each(self.root.node, &mut |node| {
+            f(node.key_ref::<K, V>(), node.value_ref::<K, V>())
+        });
+    }
+
+    /// Calls `f` on all key-value pairs in incremental order, with mutable references to the
+    /// values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map = OrdMap::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// map.for_each_mut(|_, val| *val *= 2);
+    /// assert_eq!(map[&"c"], 6);
+    /// ```
+    pub fn for_each_mut<F: FnMut(&K, &mut V)>(&mut self, mut f: F) {
+        Self::recursive_for_each(self.root.node, &mut |node| {
+            f(node.key_ref::<K, V>(), node.value_mut::<K, V>())
+        });
+    }
+
+    /// Calls `f` on all key-value pairs in incremental order, threading the state `init`
+    /// through the calls, and returns the final state. Like `for_each_mut`, it walks the tree
+    /// directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut map: OrdMap<_, _> = (1..5).map(|i| (i, i)).collect();
+    /// let total = map.scan_mut(0, |sum, _, val| {
+    ///     *sum += *val;
+    ///     *val = *sum;
+    /// });
+    /// assert_eq!(total, 10);
+    /// assert_eq!(map.values().cloned().collect::<Vec<_>>(), [1, 3, 6, 10]);
+    /// ```
+    pub fn scan_mut<St, F: FnMut(&mut St, &K, &mut V)>(&mut self, init: St, mut f: F) -> St {
+        let mut state = init;
+        Self::recursive_for_each(self.root.node, &mut |node| {
+            f(&mut state, node.key_ref::<K, V>(), node.value_mut::<K, V>())
+        });
+        state
+    }
+
+    fn recursive_for_each<F: FnMut(AVLNodePtr)>(node: AVLNodePtr, f: &mut F) {
+        if node.is_null() {
+            return;
+        }
+        Self::recursive_for_each(node.left(), f);
+        f(node);
+        Self::recursive_for_each(node.right(), f);
+    }
+
+    /// Clears the map, returning all key-value pairs as an iterator in incremental order.
+    /// Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let mut a = OrdMap::new();
+    /// a.insert(1, "a");
+    /// a.insert(2, "b");
+    ///
+    /// for (k, v) in a.drain().take(1) {
+    ///     assert_eq!(k, 1);
+    ///     assert_eq!(v, "a");
+    /// }
+    ///
+    /// assert!(a.is_empty());
+    /// ```
+    #[inline]
+    pub fn drain(&mut self) -> Drain<K, V, A> {
+        Drain { map: self }
+    }
+
+    /// Visits the (key, value) pairs whose keys are both in `self` and `other`, in incremental
+    /// order. Values are taken from `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let a: OrdMap<_, _> = (0..4).map(|i| (i, "a")).collect();
+    /// let b: OrdMap<_, _> = (2..6).map(|i| (i, "b")).collect();
+    /// let v: Vec<_> = a.intersection(&b).collect();
+    /// assert_eq!(v, vec![(&2, &"a"), (&3, &"a")]);
+    /// ```
+    #[inline]
+    pub fn intersection<'a>(&'a self, other: &'a OrdMap<K, V, A>) -> Intersection<'a, K, V> {
+        Intersection {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    /// Visits the (key, value) pairs whose keys are in `self` but not in `other`, in
+    /// incremental order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let a: OrdMap<_, _> = (0..4).map(|i| (i, "a")).collect();
+    /// let b: OrdMap<_, _> = (2..6).map(|i| (i, "b")).collect();
+    /// let v: Vec<_> = a.difference(&b).collect();
+    /// assert_eq!(v, vec![(&0, &"a"), (&1, &"a")]);
+    /// ```
+    #[inline]
+    pub fn difference<'a>(&'a self, other: &'a OrdMap<K, V, A>) -> Difference<'a, K, V> {
+        Difference {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    /// Visits the (key, value) pairs whose keys are in `self` or in `other`, but not in both,
+    /// in incremental order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let a: OrdMap<_, _> = (0..4).map(|i| (i, "a")).collect();
+    /// let b: OrdMap<_, _> = (2..6).map(|i| (i, "b")).collect();
+    /// let v: Vec<_> = a.symmetric_difference(&b).collect();
+    /// assert_eq!(v, vec![(&0, &"a"), (&1, &"a"), (&4, &"b"), (&5, &"b")]);
+    /// ```
+    #[inline]
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a OrdMap<K, V, A>,
+    ) -> SymmetricDifference<'a, K, V> {
+        SymmetricDifference {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    /// Visits the keys of `self` and `other` in incremental order, like a full outer join.
+    /// Each key is yielded once, with its value in `self`, in `other`, or in both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::{MergeSide, OrdMap};
+    ///
+    /// let a: OrdMap<_, _> = (0..3).map(|i| (i, "a")).collect();
+    /// let b: OrdMap<_, _> = (2..4).map(|i| (i, "b")).collect();
+    /// let v: Vec<_> = a.merge_join(&b).collect();
+    /// assert_eq!(
+    ///     v,
+    ///     vec![
+    ///         (&0, MergeSide::Left(&"a")),
+    ///         (&1, MergeSide::Left(&"a")),
+    ///         (&2, MergeSide::Both(&"a", &"b")),
+    ///         (&3, MergeSide::Right(&"b")),
+    ///     ]
+    /// );
+    /// ```
+    #[inline]
+    pub fn merge_join<'a>(&'a self, other: &'a OrdMap<K, V, A>) -> MergeJoin<'a, K, V> {
+        MergeJoin {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+}
+
+impl<K, V> OrdMap<K, V>
+where
+    K: Ord,
+{
+    /// Creates an empty `OrdMap`.
+    ///
+    /// The hash map is initially created with a capacity of 0, so it will not allocate until it
+    /// is first inserted into.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// let mut map: OrdMap<&str, isize> = OrdMap::new();
+    ///
+    /// const EMPTY: OrdMap<i32, i32> = OrdMap::new();
+    /// let mut map = EMPTY;
+    /// map.insert(1, 1);
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        OrdMap {
+            root: AVLRoot {
+                node: ptr::null_mut(),
+            },
+            first: ptr::null_mut(),
+            last: ptr::null_mut(),
+            count: 0,
+            entry_fastbin: Fastbin::new_lazy(
+                mem::size_of::<AVLEntry<K, V>>(),
+                mem::align_of::<AVLEntry<K, V>>(),
+            ),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Builds a balanced `OrdMap` from an iterator which yields keys in strictly incremental
+    /// order. O(n) time complexity.
+    ///
+    /// The input must be sorted and must not contain duplicate keys; this is only checked in
+    /// debug builds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map = OrdMap::from_sorted_iter((0..100).map(|i| (i, -i)));
+    /// assert_eq!(map.len(), 100);
+    /// assert_eq!(map[&42], -42);
+    /// assert!(map.check_balanced());
+    /// ```
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> OrdMap<K, V> {
+        let mut map = OrdMap::new();
+        let iter = iter.into_iter();
+        let mut nodes: Vec<AVLNodePtr> = Vec::with_capacity(iter.size_hint().0);
+        for (k, v) in iter {
+            debug_assert!(nodes.last().map_or(true, |prev| *prev.key_ref::<K, V>() < k));
+            nodes.push(map.entry_alloc(k, v).node_ptr());
+        }
+        map.count = nodes.len();
+        map.root = unsafe { avl_node::avl_build_from_sorted(&mut nodes) };
+        map.reset_bounds();
+        map
+    }
+
+    /// Consumes the map and rebuilds it with the key order reversed, so that iteration yields
+    /// keys in decremental order of the original keys. O(n) time complexity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// use std::cmp::Reverse;
+    ///
+    /// let map: OrdMap<_, _> = vec![(1, "a"), (3, "c"), (2, "b")].into_iter().collect();
+    /// let reversed = map.into_reversed();
+    /// assert_eq!(reversed.first_key_value(), Some((&Reverse(3), &"c")));
+    /// assert_eq!(reversed[&Reverse(1)], "a");
+    /// ```
+    pub fn into_reversed(self) -> OrdMap<cmp::Reverse<K>, V> {
+        OrdMap::from_sorted_iter(
+            self.into_vec()
+                .into_iter()
+                .rev()
+                .map(|(k, v)| (cmp::Reverse(k), v)),
+        )
+    }
+}
+
+impl<K, V, A> Default for OrdMap<K, V, A>
+where
+    K: Ord,
+    A: Alloc + Default,
+{
+    /// Creates an empty `OrdMap<K, V, A>`.
+    #[inline]
+    fn default() -> OrdMap<K, V, A> {
+        OrdMap::new_in(A::default())
+    }
 }
 
-impl<K, V> Drop for OrdMap<K, V> {
+impl<K, V, A> Drop for OrdMap<K, V, A>
+where
+    A: Alloc,
+{
     fn drop(&mut self) {
         self.destroy();
     }
 }
 
-impl<K, V> Clone for OrdMap<K, V>
+impl<K, V, A> Clone for OrdMap<K, V, A>
 where
     K: Ord + Clone,
     V: Clone,
+    A: Alloc,
 {
     fn clone(&self) -> Self {
-        OrdMap::clone_from(self)
+        OrdMap::deep_clone(self)
+    }
+
+    /// Performs copy-assignment from `source`, reusing the memory of `self`.
+    ///
+    /// The entries of `self` are dropped back into its `Fastbin` first, and the clones of
+    /// `source` are allocated from the freed cells. New pages are only requested when `source`
+    /// holds more entries than `self` can keep; if it holds fewer, the spare cells stay free.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let source: OrdMap<_, _> = (0..100).map(|i| (i, i)).collect();
+    /// let mut target: OrdMap<_, _> = (0..100).map(|i| (-i, i)).collect();
+    /// let usage = target.memory_usage();
+    /// target.clone_from(&source);
+    /// assert!(target == source);
+    /// assert_eq!(target.memory_usage(), usage);
+    /// ```
+    fn clone_from(&mut self, source: &Self) {
+        self.clear();
+        let guard = ClearOnUnwind { map: self };
+        let link = &mut guard.map.root.node as *mut AVLNodePtr;
+        guard.map.deep_clone_node(link, ptr::null_mut(), source.root.node);
+        mem::forget(guard);
+        self.count = source.count;
+        self.reset_bounds();
     }
 }
 
-impl<K, V> PartialEq for OrdMap<K, V>
+impl<K, V, A> PartialEq for OrdMap<K, V, A>
 where
     K: Eq + Ord,
     V: PartialEq,
+    A: Alloc,
 {
-    fn eq(&self, other: &OrdMap<K, V>) -> bool {
+    fn eq(&self, other: &OrdMap<K, V, A>) -> bool {
         if self.len() != other.len() {
             return false;
         }
@@ -1705,16 +4017,67 @@ where
     }
 }
 
-impl<K, V> Eq for OrdMap<K, V>
+impl<K, V, A> Eq for OrdMap<K, V, A>
 where
     K: Eq + Ord,
     V: Eq,
+    A: Alloc,
+{
+}
+
+impl<K, V, A> Hash for OrdMap<K, V, A>
+where
+    K: Hash + Ord,
+    V: Hash,
+    A: Alloc,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for elt in self.iter() {
+            elt.hash(state);
+        }
+    }
+}
+
+impl<K, V, A> PartialOrd for OrdMap<K, V, A>
+where
+    K: Ord,
+    V: PartialOrd,
+    A: Alloc,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &OrdMap<K, V, A>) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<K, V, A> Ord for OrdMap<K, V, A>
+where
+    K: Ord,
+    V: Ord,
+    A: Alloc,
 {
+    #[inline]
+    fn cmp(&self, other: &OrdMap<K, V, A>) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<K, V, A> fmt::Debug for OrdMap<K, V, A>
+where
+    K: Ord + fmt::Debug,
+    V: fmt::Debug,
+    A: Alloc,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
 }
 
-impl<'a, K, V> Index<&'a K> for OrdMap<K, V>
+impl<'a, K, V, A> Index<&'a K> for OrdMap<K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     type Output = V;
 
@@ -1729,29 +4092,110 @@ where
     }
 }
 
-impl<K, V> FromIterator<(K, V)> for OrdMap<K, V>
+impl<K, V, A> FromIterator<(K, V)> for OrdMap<K, V, A>
 where
     K: Ord,
+    A: Alloc + Default,
 {
-    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> OrdMap<K, V> {
-        let mut tree = OrdMap::new();
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> OrdMap<K, V, A> {
+        let mut tree = OrdMap::new_in(A::default());
         tree.extend(iter);
         tree
     }
 }
 
-impl<K, V> Extend<(K, V)> for OrdMap<K, V>
+impl<'a, K, V, A> FromIterator<(&'a K, &'a V)> for OrdMap<K, V, A>
+where
+    K: Ord + Copy,
+    V: Copy,
+    A: Alloc + Default,
+{
+    fn from_iter<T: IntoIterator<Item = (&'a K, &'a V)>>(iter: T) -> OrdMap<K, V, A> {
+        iter.into_iter().map(|(&key, &value)| (key, value)).collect()
+    }
+}
+
+impl<K, V, A> Extend<(K, V)> for OrdMap<K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
         let iter = iter.into_iter();
+        self.extend_reserve(iter.size_hint().0);
         for (k, v) in iter {
             self.insert(k, v);
         }
     }
 }
 
+impl<K, V, S> From<HashMap<K, V, S>> for OrdMap<K, V>
+where
+    K: Ord + Hash,
+    S: BuildHasher,
+{
+    /// Moves all entries of a `HashMap` into a new `OrdMap`. The entries are sorted first, so
+    /// the tree is built in O(n) by `from_sorted_iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::hash_map::HashMap;
+    /// use hash_ord::ord_map::OrdMap;
+    ///
+    /// let map: HashMap<_, _> = (0..10).map(|i| (i, -i)).collect();
+    /// let ord_map = OrdMap::from(map);
+    /// assert_eq!(ord_map.len(), 10);
+    /// assert_eq!(ord_map.keys().cloned().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+    /// ```
+    fn from(map: HashMap<K, V, S>) -> OrdMap<K, V> {
+        let mut entries: Vec<(K, V)> = map.into_iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        OrdMap::from_sorted_iter(entries)
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for OrdMap<K, V>
+where
+    K: Ord,
+{
+    /// Moves all entries of a `BTreeMap` into a new `OrdMap`. The entries of a `BTreeMap` are
+    /// already sorted, so the tree is built in O(n) by `from_sorted_iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMap;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let btree_map: BTreeMap<_, _> = (0..10).map(|i| (i, -i)).collect();
+    /// let map = OrdMap::from(btree_map);
+    /// assert_eq!(map.len(), 10);
+    /// assert_eq!(map[&3], -3);
+    /// ```
+    fn from(btree_map: BTreeMap<K, V>) -> OrdMap<K, V> {
+        OrdMap::from_sorted_iter(btree_map)
+    }
+}
+
+/// Builds the map by inserting an arbitrary sequence of key-value pairs.
+#[cfg(feature = "fuzz")]
+impl<'a, K, V, A> arbitrary::Arbitrary<'a> for OrdMap<K, V, A>
+where
+    K: arbitrary::Arbitrary<'a> + Ord,
+    V: arbitrary::Arbitrary<'a>,
+    A: Alloc + Default,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut map = OrdMap::new_in(A::default());
+        for kv in u.arbitrary_iter::<(K, V)>()? {
+            let (k, v) = kv?;
+            map.insert(k, v);
+        }
+        Ok(map)
+    }
+}
+
 /// An iterator over the keys of a `OrdMap`.
 ///
 /// This `struct` is created by the [`keys`] method on [`OrdMap`]. See its
@@ -1787,6 +4231,22 @@ impl<'a, K: Ord, V> Iterator for Keys<'a, K, V> {
     }
 }
 
+impl<'a, K: Ord, V> DoubleEndedIterator for Keys<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a K> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<'a, K: Ord, V> ExactSizeIterator for Keys<'a, K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K: Ord, V> FusedIterator for Keys<'a, K, V> {}
+
 /// An iterator over the values of a `OrdMap`.
 ///
 /// This `struct` is created by the [`values`] method on [`OrdMap`]. See its
@@ -1822,6 +4282,22 @@ impl<'a, K: Ord, V> Iterator for Values<'a, K, V> {
     }
 }
 
+impl<'a, K: Ord, V> DoubleEndedIterator for Values<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K: Ord, V> ExactSizeIterator for Values<'a, K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K: Ord, V> FusedIterator for Values<'a, K, V> {}
+
 /// A mutable iterator over the values of a `OrdMap`.
 ///
 /// This `struct` is created by the [`values_mut`] method on [`OrdMap`]. See its
@@ -1857,6 +4333,22 @@ impl<'a, K: Ord, V> Iterator for ValuesMut<'a, K, V> {
     }
 }
 
+impl<'a, K: Ord, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<'a, K: Ord, V> ExactSizeIterator for ValuesMut<'a, K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<'a, K: Ord, V> FusedIterator for ValuesMut<'a, K, V> {}
+
 /// An owning iterator over the entries of a `OrdMap`.
 ///
 /// This `struct` is created by the [`into_iter`] method on [`OrdMap`][`OrdMap`]
@@ -1864,20 +4356,22 @@ impl<'a, K: Ord, V> Iterator for ValuesMut<'a, K, V> {
 ///
 /// [`into_iter`]: struct.OrdMap.html#method.into_iter
 /// [`OrdMap`]: struct.OrdMap.html
-pub struct IntoIter<K, V>
+pub struct IntoIter<K, V, A = Global>
 where
     K: Ord,
+    A: Alloc,
 {
     root: AVLRoot,
     next: AVLNodePtr,
     len: usize,
-    entry_fastbin: Fastbin,
+    entry_fastbin: Fastbin<A>,
     _marker: marker::PhantomData<(K, V)>,
 }
 
-impl<K, V> IntoIter<K, V>
+impl<K, V, A> IntoIter<K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     /// Convert self into `SortedList`.
     ///
@@ -1899,7 +4393,7 @@ where
     /// assert_eq!(sum, 6);
     /// ```
     #[inline]
-    pub fn into_sorted_list(mut self) -> SortedList<K, V> {
+    pub fn into_sorted_list(mut self) -> SortedList<K, V, A> {
         SortedList {
             head: unsafe { avl_node::avl_tree_convert_to_list(&mut self.root) },
             len: self.len,
@@ -1916,10 +4410,13 @@ where
 ///
 /// [`into_sorted_list`]: struct.IntoIter.html#method.into_sorted_list
 /// [`IntoIter`]: struct.IntoIter.html
-pub struct SortedList<K, V> {
+pub struct SortedList<K, V, A = Global>
+where
+    A: Alloc,
+{
     head: AVLNodePtr,
     len: usize,
-    entry_fastbin: Fastbin,
+    entry_fastbin: Fastbin<A>,
     _marker: marker::PhantomData<(K, V)>,
 }
 
@@ -1940,7 +4437,10 @@ where
     _marker: marker::PhantomData<&'a (K, V)>,
 }
 
-impl<K, V> SortedList<K, V> {
+impl<K, V, A> SortedList<K, V, A>
+where
+    A: Alloc,
+{
     pub fn iter(&self) -> SortedListIter<K, V> {
         SortedListIter {
             head: self.head,
@@ -1968,7 +4468,10 @@ where
 }
 
 /// Implement owning iterator trait over (key, value) of a `SortedList`
-impl<K, V> Iterator for SortedList<K, V> {
+impl<K, V, A> Iterator for SortedList<K, V, A>
+where
+    A: Alloc,
+{
     type Item = (K, V);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -1982,78 +4485,330 @@ impl<K, V> Iterator for SortedList<K, V> {
         self.entry_fastbin.del(old_entry as VoidPtr);
         res
     }
-}
 
-impl<K, V> Drop for SortedList<K, V> {
-    fn drop(&mut self) {
-        for (_, _) in self {}
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V, A> Drop for SortedList<K, V, A>
+where
+    A: Alloc,
+{
+    fn drop(&mut self) {
+        for (_, _) in self {}
+    }
+}
+
+impl<K, V, A> Drop for IntoIter<K, V, A>
+where
+    K: Ord,
+    A: Alloc,
+{
+    fn drop(&mut self) {
+        for (_, _) in self {}
+    }
+}
+
+impl<K, V, A> Iterator for IntoIter<K, V, A>
+where
+    K: Ord,
+    A: Alloc,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 || self.root.node.is_null() {
+            return None;
+        }
+        let node =
+            unsafe { avl_node::avl_node_tear(&mut self.root, &mut self.next as *mut AVLNodePtr) };
+        self.len -= 1;
+        let old_entry = node.avl_node_deref_to_entry::<K, V>();
+        let res = unsafe { Some((ptr::read(old_entry.key()), ptr::read(old_entry.value()))) };
+        self.entry_fastbin.del(old_entry as VoidPtr);
+        res
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V, A> IntoIterator for OrdMap<K, V, A>
+where
+    K: Ord,
+    A: Alloc,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A>;
+
+    #[inline]
+    fn into_iter(mut self) -> IntoIter<K, V, A> {
+        let res = IntoIter {
+            root: self.root,
+            next: ptr::null_mut(),
+            len: self.len(),
+            entry_fastbin: self.entry_fastbin.move_to(),
+            _marker: marker::PhantomData,
+        };
+        self.root.node = ptr::null_mut();
+        self.count = 0;
+        res
+    }
+}
+
+/// A lazy iterator producing the (key, value) pairs of the intersection of two `OrdMap`s.
+///
+/// This `struct` is created by the [`intersection`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`intersection`]: struct.OrdMap.html#method.intersection
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct Intersection<'a, K: 'a, V: 'a> {
+    a: Peekable<Iter<'a, K, V>>,
+    b: Peekable<Iter<'a, K, V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for Intersection<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            let res = match (self.a.peek(), self.b.peek()) {
+                (Some(&(ka, _)), Some(&(kb, _))) => ka.cmp(kb),
+                _ => return None,
+            };
+            match res {
+                Ordering::Less => {
+                    self.a.next();
+                }
+                Ordering::Greater => {
+                    self.b.next();
+                }
+                Ordering::Equal => {
+                    self.b.next();
+                    return self.a.next();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_len, _) = self.a.size_hint();
+        let (b_len, _) = self.b.size_hint();
+        (0, Some(cmp::min(a_len, b_len)))
+    }
+}
+
+/// A lazy iterator producing the (key, value) pairs of the difference of two `OrdMap`s.
+///
+/// This `struct` is created by the [`difference`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`difference`]: struct.OrdMap.html#method.difference
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct Difference<'a, K: 'a, V: 'a> {
+    a: Peekable<Iter<'a, K, V>>,
+    b: Peekable<Iter<'a, K, V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for Difference<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            let res = match (self.a.peek(), self.b.peek()) {
+                (None, _) => return None,
+                (Some(_), None) => Ordering::Less,
+                (Some(&(ka, _)), Some(&(kb, _))) => ka.cmp(kb),
+            };
+            match res {
+                Ordering::Less => {
+                    return self.a.next();
+                }
+                Ordering::Greater => {
+                    self.b.next();
+                }
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.a.size_hint().1)
+    }
+}
+
+/// A lazy iterator producing the (key, value) pairs of the symmetric difference of two
+/// `OrdMap`s.
+///
+/// This `struct` is created by the [`symmetric_difference`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`symmetric_difference`]: struct.OrdMap.html#method.symmetric_difference
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct SymmetricDifference<'a, K: 'a, V: 'a> {
+    a: Peekable<Iter<'a, K, V>>,
+    b: Peekable<Iter<'a, K, V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for SymmetricDifference<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            let res = match (self.a.peek(), self.b.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(&(ka, _)), Some(&(kb, _))) => ka.cmp(kb),
+            };
+            match res {
+                Ordering::Less => {
+                    return self.a.next();
+                }
+                Ordering::Greater => {
+                    return self.b.next();
+                }
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_len, _) = self.a.size_hint();
+        let (b_len, _) = self.b.size_hint();
+        (0, a_len.checked_add(b_len))
+    }
+}
+
+/// Where a key visited by [`merge_join`] is found, with the corresponding values.
+///
+/// [`merge_join`]: struct.OrdMap.html#method.merge_join
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MergeSide<T> {
+    /// The key is only in the left map.
+    Left(T),
+    /// The key is only in the right map.
+    Right(T),
+    /// The key is in both maps.
+    Both(T, T),
+}
+
+/// A lazy iterator producing the keys of two `OrdMap`s with the side they are found in.
+///
+/// This `struct` is created by the [`merge_join`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`merge_join`]: struct.OrdMap.html#method.merge_join
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct MergeJoin<'a, K: 'a, V: 'a> {
+    a: Peekable<Iter<'a, K, V>>,
+    b: Peekable<Iter<'a, K, V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for MergeJoin<'a, K, V> {
+    type Item = (&'a K, MergeSide<&'a V>);
+
+    fn next(&mut self) -> Option<(&'a K, MergeSide<&'a V>)> {
+        let res = match (self.a.peek(), self.b.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(&(ka, _)), Some(&(kb, _))) => ka.cmp(kb),
+        };
+        match res {
+            Ordering::Less => self.a.next().map(|(k, v)| (k, MergeSide::Left(v))),
+            Ordering::Greater => self.b.next().map(|(k, v)| (k, MergeSide::Right(v))),
+            Ordering::Equal => match (self.a.next(), self.b.next()) {
+                (Some((k, va)), Some((_, vb))) => Some((k, MergeSide::Both(va, vb))),
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_len, _) = self.a.size_hint();
+        let (b_len, _) = self.b.size_hint();
+        (cmp::max(a_len, b_len), a_len.checked_add(b_len))
     }
 }
 
-impl<K, V> Drop for IntoIter<K, V>
+impl<'a, K: Ord, V> FusedIterator for MergeJoin<'a, K, V> {}
+
+/// A draining iterator over the entries of a `OrdMap`.
+///
+/// This `struct` is created by the [`drain`] method on [`OrdMap`]. See its
+/// documentation for more.
+///
+/// [`drain`]: struct.OrdMap.html#method.drain
+/// [`OrdMap`]: struct.OrdMap.html
+pub struct Drain<'a, K, V, A = Global>
 where
-    K: Ord,
+    K: Ord + 'a,
+    V: 'a,
+    A: Alloc + 'a,
+{
+    map: &'a mut OrdMap<K, V, A>,
+}
+
+impl<'a, K, V, A> Drop for Drain<'a, K, V, A>
+where
+    K: Ord + 'a,
+    V: 'a,
+    A: Alloc,
 {
     fn drop(&mut self) {
-        for (_, _) in self {}
+        for _ in self {}
     }
 }
 
-impl<K, V> Iterator for IntoIter<K, V>
+impl<'a, K, V, A> Iterator for Drain<'a, K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     type Item = (K, V);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.len == 0 || self.root.node.is_null() {
-            return None;
-        }
-        let node =
-            unsafe { avl_node::avl_node_tear(&mut self.root, &mut self.next as *mut AVLNodePtr) };
-        self.len -= 1;
-        let old_entry = node.avl_node_deref_to_entry::<K, V>();
-        let res = unsafe { Some((ptr::read(old_entry.key()), ptr::read(old_entry.value()))) };
-        self.entry_fastbin.del(old_entry as VoidPtr);
-        res
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        let node = self.map.first_node();
+        unsafe { self.map.remove_node(node) }
     }
 
+    #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.len, Some(self.len))
+        (self.map.len(), Some(self.map.len()))
     }
 }
 
-impl<K, V> IntoIterator for OrdMap<K, V>
+impl<'a, K, V, A> ExactSizeIterator for Drain<'a, K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
-    type Item = (K, V);
-    type IntoIter = IntoIter<K, V>;
-
     #[inline]
-    fn into_iter(mut self) -> IntoIter<K, V> {
-        let res = IntoIter {
-            root: self.root,
-            next: ptr::null_mut(),
-            len: self.len(),
-            entry_fastbin: self.entry_fastbin.move_to(),
-            _marker: marker::PhantomData,
-        };
-        self.root.node = ptr::null_mut();
-        self.count = 0;
-        res
+    fn len(&self) -> usize {
+        self.map.len()
     }
 }
 
 /// An iterator over the (key, value) of a `OrdMap`.
-pub struct Iter<'a, K: Ord + 'a, V: 'a> {
+pub struct Iter<'a, K: 'a, V: 'a> {
     head: AVLNodePtr,
     tail: AVLNodePtr,
     len: usize,
     _marker: marker::PhantomData<&'a (K, V)>,
 }
 
-impl<'a, K: Ord + 'a, V: 'a> Clone for Iter<'a, K, V> {
+impl<'a, K: 'a, V: 'a> Clone for Iter<'a, K, V> {
     fn clone(&self) -> Iter<'a, K, V> {
         Iter {
             head: self.head,
@@ -2064,9 +4819,10 @@ impl<'a, K: Ord + 'a, V: 'a> Clone for Iter<'a, K, V> {
     }
 }
 
-impl<'a, K, V> IntoIterator for &'a OrdMap<K, V>
+impl<'a, K, V, A> IntoIterator for &'a OrdMap<K, V, A>
 where
     K: Ord,
+    A: Alloc,
 {
     type Item = (&'a K, &'a V);
     type IntoIter = Iter<'a, K, V>;
@@ -2076,7 +4832,7 @@ where
     }
 }
 
-impl<'a, K: Ord + 'a, V: 'a> Iterator for Iter<'a, K, V> {
+impl<'a, K: 'a, V: 'a> Iterator for Iter<'a, K, V> {
     type Item = (&'a K, &'a V);
 
     fn next(&mut self) -> Option<(&'a K, &'a V)> {
@@ -2099,7 +4855,7 @@ impl<'a, K: Ord + 'a, V: 'a> Iterator for Iter<'a, K, V> {
     }
 }
 
-impl<'a, K: Ord + 'a, V: 'a> DoubleEndedIterator for Iter<'a, K, V> {
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for Iter<'a, K, V> {
     #[inline]
     fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
         if self.len == 0 {
@@ -2113,15 +4869,24 @@ impl<'a, K: Ord + 'a, V: 'a> DoubleEndedIterator for Iter<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for Iter<'a, K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for Iter<'a, K, V> {}
+
 /// An iterator over the (key, mut value) of a `OrdMap`.
-pub struct IterMut<'a, K: Ord + 'a, V: 'a> {
+pub struct IterMut<'a, K: 'a, V: 'a> {
     head: AVLNodePtr,
     tail: AVLNodePtr,
     len: usize,
     _marker: marker::PhantomData<&'a (K, V)>,
 }
 
-impl<'a, K: Ord + 'a, V: 'a> Clone for IterMut<'a, K, V> {
+impl<'a, K: 'a, V: 'a> Clone for IterMut<'a, K, V> {
     fn clone(&self) -> IterMut<'a, K, V> {
         IterMut {
             head: self.head,
@@ -2132,7 +4897,20 @@ impl<'a, K: Ord + 'a, V: 'a> Clone for IterMut<'a, K, V> {
     }
 }
 
-impl<'a, K: Ord + 'a, V: 'a> Iterator for IterMut<'a, K, V> {
+impl<'a, K, V, A> IntoIterator for &'a mut OrdMap<K, V, A>
+where
+    K: Ord,
+    A: Alloc,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for IterMut<'a, K, V> {
     type Item = (&'a K, &'a mut V);
 
     fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
@@ -2154,7 +4932,7 @@ impl<'a, K: Ord + 'a, V: 'a> Iterator for IterMut<'a, K, V> {
     }
 }
 
-impl<'a, K: Ord + 'a, V: 'a> DoubleEndedIterator for IterMut<'a, K, V> {
+impl<'a, K: 'a, V: 'a> DoubleEndedIterator for IterMut<'a, K, V> {
     #[inline]
     fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
         if self.len == 0 {
@@ -2168,6 +4946,333 @@ impl<'a, K: Ord + 'a, V: 'a> DoubleEndedIterator for IterMut<'a, K, V> {
     }
 }
 
+impl<'a, K: 'a, V: 'a> ExactSizeIterator for IterMut<'a, K, V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, K: 'a, V: 'a> FusedIterator for IterMut<'a, K, V> {}
+
+/// A parallel iterator over the (key, value) of a `OrdMap`.
+///
+/// This `struct` is created by the `par_iter` method on [`OrdMap`] (provided by rayon's
+/// `IntoParallelRefIterator` trait). The tree is split at subtree roots, so every worker visits
+/// a continuous part of the sorted sequence.
+///
+/// [`OrdMap`]: struct.OrdMap.html
+#[cfg(feature = "rayon")]
+pub struct ParIter<'a, K: 'a, V: 'a> {
+    root: AVLNodePtr,
+    _marker: marker::PhantomData<&'a (K, V)>,
+}
+
+/// The tree is not modified while it is borrowed, so the nodes can be read from any thread.
+#[cfg(feature = "rayon")]
+unsafe impl<'a, K: Sync, V: Sync> Send for ParIter<'a, K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let producer = SubtreeProducer {
+            node: self.root,
+            with_left: true,
+            with_right: true,
+            _marker: marker::PhantomData,
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, K, V, A> IntoParallelIterator for &'a OrdMap<K, V, A>
+where
+    K: Ord + Sync,
+    V: Sync,
+    A: Alloc,
+{
+    type Item = (&'a K, &'a V);
+    type Iter = ParIter<'a, K, V>;
+
+    fn into_par_iter(self) -> ParIter<'a, K, V> {
+        ParIter {
+            root: self.root.node,
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+/// Produces `node`, together with its left and right subtree if `with_left` and `with_right`
+/// are set, in incremental order.
+#[cfg(feature = "rayon")]
+struct SubtreeProducer<'a, K: 'a, V: 'a> {
+    node: AVLNodePtr,
+    with_left: bool,
+    with_right: bool,
+    _marker: marker::PhantomData<&'a (K, V)>,
+}
+
+#[cfg(feature = "rayon")]
+unsafe impl<'a, K: Sync, V: Sync> Send for SubtreeProducer<'a, K, V> {}
+
+#[cfg(feature = "rayon")]
+impl<'a, K: Sync, V: Sync> UnindexedProducer for SubtreeProducer<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn split(self) -> (Self, Option<Self>) {
+        if self.node.is_null() {
+            return (self, None);
+        }
+        let subtree = |node| SubtreeProducer {
+            node,
+            with_left: true,
+            with_right: true,
+            _marker: marker::PhantomData,
+        };
+        if self.with_left && self.node.left().not_null() {
+            let left = subtree(self.node.left());
+            let rest = SubtreeProducer {
+                with_left: false,
+                ..self
+            };
+            (left, Some(rest))
+        } else if self.with_right && self.node.right().not_null() {
+            let right = subtree(self.node.right());
+            let rest = SubtreeProducer {
+                with_right: false,
+                ..self
+            };
+            (rest, Some(right))
+        } else {
+            (self, None)
+        }
+    }
+
+    fn fold_with<F>(self, mut folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        if self.node.is_null() {
+            return folder;
+        }
+        let mut node = if self.with_left && self.node.left().not_null() {
+            self.node.left().first_node()
+        } else {
+            self.node
+        };
+        let last = if self.with_right && self.node.right().not_null() {
+            self.node.right().last_node()
+        } else {
+            self.node
+        };
+        loop {
+            folder = folder.consume((node.key_ref::<K, V>(), node.value_ref::<K, V>()));
+            if node == last || folder.full() {
+                return folder;
+            }
+            node = node.next();
+        }
+    }
+}
+
+/// An ordered map like `OrdMap`, but keys are ordered by a user-supplied comparator instead of
+/// `K: Ord`.
+///
+/// The comparator is defined on `Q`, a borrowed form of the key (`K` itself by default), and
+/// lookups take a `&Q` the same way `OrdMap` takes any borrowed form of its keys. Two keys are
+/// treated as the same key if the comparator returns `Ordering::Equal`.
+///
+/// # Examples
+///
+/// ```
+/// use hash_ord::ord_map::OrdMapBy;
+///
+/// let mut map = OrdMapBy::new(|a: &str, b: &str| a.to_lowercase().cmp(&b.to_lowercase()));
+/// map.insert("Foo".to_string(), 1);
+/// map.insert("bar".to_string(), 2);
+/// assert_eq!(map.insert("foo".to_string(), 3), Some(("Foo".to_string(), 1)));
+/// assert_eq!(map.get("FOO"), Some(&3));
+///
+/// let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+/// assert_eq!(keys, vec!["bar".to_string(), "foo".to_string()]);
+/// ```
+pub struct OrdMapBy<K, V, C, Q: ?Sized = K>
+where
+    K: Borrow<Q>,
+    C: Fn(&Q, &Q) -> Ordering,
+{
+    map: OrdMap<K, V>,
+    cmp: C,
+    _marker: marker::PhantomData<fn(&Q)>,
+}
+
+impl<K, V, C, Q: ?Sized> OrdMapBy<K, V, C, Q>
+where
+    K: Borrow<Q>,
+    C: Fn(&Q, &Q) -> Ordering,
+{
+    /// Creates an empty `OrdMapBy` which orders keys by `cmp`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_map::OrdMapBy;
+    ///
+    /// let mut map = OrdMapBy::new(|a: &i32, b: &i32| b.cmp(a));
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// assert_eq!(map.iter().next(), Some((&2, &"b")));
+    /// ```
+    #[inline]
+    pub fn new(cmp: C) -> Self {
+        OrdMapBy {
+            map: OrdMap::new_in(Global),
+            cmp,
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns the number of elements in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.count
+    }
+
+    /// Returns true if the map contains no element.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.count == 0
+    }
+
+    #[inline]
+    fn find_node(&self, q: &Q) -> AVLNodePtr {
+        let cmp = &self.cmp;
+        self.map.find_node_by(|k| cmp(q, k.borrow()))
+    }
+
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map did not have an equal key present, [`None`] is returned.
+    ///
+    /// If the map did have an equal key present, update map with new (key, value) and
+    /// return the old one.
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        let (duplicate, parent, cmp_node_ref) = {
+            let cmp = &self.cmp;
+            let q = key.borrow();
+            unsafe { self.map.find_duplicate_by(|k| cmp(q, k.borrow())) }
+        };
+        self.map.insert_at(key, value, duplicate, parent, cmp_node_ref)
+    }
+
+    /// Returns true if the map contains a value for the specified key.
+    #[inline]
+    pub fn contains_key(&self, q: &Q) -> bool {
+        self.find_node(q).not_null()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    #[inline]
+    pub fn get(&self, q: &Q) -> Option<&V> {
+        let node = self.find_node(q);
+        if node.is_null() {
+            None
+        } else {
+            Some(node.value_ref::<K, V>())
+        }
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    #[inline]
+    pub fn get_mut(&mut self, q: &Q) -> Option<&mut V> {
+        let node = self.find_node(q);
+        if node.is_null() {
+            None
+        } else {
+            Some(node.value_mut::<K, V>())
+        }
+    }
+
+    /// Removes a key from the map, returning the stored key and value if the
+    /// key was previously in the map.
+    pub fn remove(&mut self, q: &Q) -> Option<(K, V)> {
+        let node = self.find_node(q);
+        unsafe { self.map.remove_node(node) }
+    }
+
+    /// An iterator visiting all key-value pairs in the order of the comparator.
+    #[inline]
+    pub fn iter(&self) -> Iter<K, V> {
+        Iter {
+            head: self.map.first,
+            tail: self.map.last,
+            len: self.len(),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in the order of the comparator,
+    /// with mutable references to the values.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<K, V> {
+        IterMut {
+            head: self.map.first,
+            tail: self.map.last,
+            len: self.len(),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Return true if tree is balanced.
+    pub fn check_balanced(&self) -> bool {
+        self.map.root.node.check_valid()
+    }
+
+    /// Return true if tree is a BST under the comparator.
+    pub fn check_ord_valid(&self) -> bool {
+        let mut iter = self.iter();
+        let mut prev = match iter.next() {
+            None => return self.map.root.node.is_null(),
+            Some((k, _)) => k,
+        };
+        let mut cnt = 1usize;
+        for (k, _) in iter {
+            if (self.cmp)(prev.borrow(), k.borrow()) != Ordering::Less {
+                return false;
+            }
+            prev = k;
+            cnt += 1;
+        }
+        cnt == self.len()
+    }
+
+    /// Clears the map, removing all key-value pairs. Keeps the allocated memory
+    /// for reuse.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<'a, K, V, C, Q: ?Sized> IntoIterator for &'a OrdMapBy<K, V, C, Q>
+where
+    K: Borrow<Q>,
+    C: Fn(&Q, &Q) -> Ordering,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
 #[cfg(test)]
 mod test {
     extern crate rand;
@@ -2338,4 +5443,51 @@ mod test {
         let sum: i32 = v.iter().sum();
         assert_eq!(sum, (0..100).sum());
     }
+
+    #[test]
+    fn test_avl_cached_bounds() {
+        fn check(t: &OrdMap<i32, i32>) {
+            assert!(t.first == t.root.node.first_node());
+            assert!(t.last == t.root.node.last_node());
+        }
+        let mut t = OrdMap::new();
+        check(&t);
+        for _ in 0..20 {
+            for _ in 0..200 {
+                let key = (rand::random::<u32>() % 500) as i32;
+                match rand::random::<u32>() % 4 {
+                    0 => {
+                        t.insert(key, key);
+                    }
+                    1 => {
+                        t.entry(key).or_insert(key);
+                    }
+                    _ => {
+                        t.remove(&key);
+                    }
+                }
+                check(&t);
+            }
+            let key = (rand::random::<u32>() % 500) as i32;
+            let mut other = t.split_off(&key);
+            check(&t);
+            check(&other);
+            other.retain(|k, _| k % 3 != 0);
+            check(&other);
+            let mut extra: OrdMap<_, _> = (500..510).map(|i| (i, i)).collect();
+            check(&extra);
+            other.append(&mut extra);
+            check(&other);
+            check(&extra);
+            t.append(&mut other);
+            check(&t);
+            let cloned = t.clone();
+            check(&cloned);
+            check(&t.clone_balanced());
+        }
+        t.clear();
+        check(&t);
+        t.insert(1, 1);
+        check(&t);
+    }
 }
@@ -0,0 +1,588 @@
+use core::borrow::Borrow;
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use core::ops::RangeBounds;
+use ord_map::{self, Entry, OrdMap};
+
+/// An ordered set based on `OrdMap`, whose values are `()`.
+pub struct OrdSet<T>(OrdMap<T, ()>)
+where
+    T: Ord;
+
+impl<T> OrdSet<T>
+where
+    T: Ord,
+{
+    /// Makes a new empty `OrdSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let mut set: OrdSet<i32> = OrdSet::new();
+    /// assert!(set.is_empty());
+    /// ```
+    #[inline]
+    pub fn new() -> OrdSet<T> {
+        OrdSet(OrdMap::new())
+    }
+
+    /// Returns the number of elements in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let mut set = OrdSet::new();
+    /// set.insert(1);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the set contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Clears the set, removing all values.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Adds a value to the set. Returns false if the set already contained an equal value,
+    /// in which case the set is not modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let mut set = OrdSet::new();
+    /// assert_eq!(set.insert(2), true);
+    /// assert_eq!(set.insert(2), false);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.0.entry(value) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(());
+                true
+            }
+        }
+    }
+
+    /// Removes a value from the set. Returns true if the value was present in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let mut set = OrdSet::new();
+    /// set.insert(2);
+    /// assert_eq!(set.remove(&2), true);
+    /// assert_eq!(set.remove(&2), false);
+    /// ```
+    #[inline]
+    pub fn remove<Q: ?Sized>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord,
+    {
+        self.0.remove(value).is_some()
+    }
+
+    /// Removes and returns the value in the set equal to the given one, if any.
+    #[inline]
+    pub fn take<Q: ?Sized>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord,
+    {
+        self.0.remove(value).map(|(k, _)| k)
+    }
+
+    /// Returns true if the set contains the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let set: OrdSet<_> = [1, 2, 3].iter().cloned().collect();
+    /// assert_eq!(set.contains(&1), true);
+    /// assert_eq!(set.contains(&4), false);
+    /// ```
+    #[inline]
+    pub fn contains<Q: ?Sized>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord,
+    {
+        self.0.contains_key(value)
+    }
+
+    /// Returns a reference to the first (minimum) value in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let set: OrdSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// assert_eq!(set.first(), Some(&1));
+    /// assert_eq!(set.last(), Some(&3));
+    /// ```
+    #[inline]
+    pub fn first(&self) -> Option<&T> {
+        self.0.keys().next()
+    }
+
+    /// Returns a reference to the last (maximum) value in the set.
+    #[inline]
+    pub fn last(&self) -> Option<&T> {
+        self.0.keys().next_back()
+    }
+
+    /// Gets an iterator that visits the values in the set in incremental order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let set: OrdSet<_> = [3, 1, 2].iter().cloned().collect();
+    /// let v: Vec<_> = set.iter().cloned().collect();
+    /// assert_eq!(v, [1, 2, 3]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<T> {
+        Iter {
+            iter: self.0.keys(),
+        }
+    }
+
+    /// Constructs a double-ended iterator over a sub-range of values in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let set: OrdSet<_> = (0..10).collect();
+    /// let v: Vec<_> = set.range(3..6).cloned().collect();
+    /// assert_eq!(v, [3, 4, 5]);
+    /// ```
+    #[inline]
+    pub fn range<Q: ?Sized, R>(&self, range: R) -> Range<T>
+    where
+        Q: Ord,
+        T: Borrow<Q>,
+        R: RangeBounds<Q>,
+    {
+        Range {
+            iter: self.0.range(range),
+        }
+    }
+
+    /// Visits the values representing the intersection, in incremental order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let a: OrdSet<_> = (0..4).collect();
+    /// let b: OrdSet<_> = (2..6).collect();
+    /// let v: Vec<_> = a.intersection(&b).cloned().collect();
+    /// assert_eq!(v, [2, 3]);
+    /// ```
+    #[inline]
+    pub fn intersection<'a>(&'a self, other: &'a OrdSet<T>) -> Intersection<'a, T> {
+        Intersection {
+            iter: self.0.intersection(&other.0),
+        }
+    }
+
+    /// Visits the values in `self` but not in `other`, in incremental order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let a: OrdSet<_> = (0..4).collect();
+    /// let b: OrdSet<_> = (2..6).collect();
+    /// let v: Vec<_> = a.difference(&b).cloned().collect();
+    /// assert_eq!(v, [0, 1]);
+    /// ```
+    #[inline]
+    pub fn difference<'a>(&'a self, other: &'a OrdSet<T>) -> Difference<'a, T> {
+        Difference {
+            iter: self.0.difference(&other.0),
+        }
+    }
+
+    /// Visits the values in `self` or in `other`, but not in both, in incremental order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let a: OrdSet<_> = (0..4).collect();
+    /// let b: OrdSet<_> = (2..6).collect();
+    /// let v: Vec<_> = a.symmetric_difference(&b).cloned().collect();
+    /// assert_eq!(v, [0, 1, 4, 5]);
+    /// ```
+    #[inline]
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a OrdSet<T>,
+    ) -> SymmetricDifference<'a, T> {
+        SymmetricDifference {
+            iter: self.0.symmetric_difference(&other.0),
+        }
+    }
+
+    /// Visits the values in `self` or in `other`, without duplicates, in incremental order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hash_ord::ord_set::OrdSet;
+    ///
+    /// let a: OrdSet<_> = (0..4).collect();
+    /// let b: OrdSet<_> = (2..6).collect();
+    /// let v: Vec<_> = a.union(&b).cloned().collect();
+    /// assert_eq!(v, [0, 1, 2, 3, 4, 5]);
+    /// ```
+    #[inline]
+    pub fn union<'a>(&'a self, other: &'a OrdSet<T>) -> Union<'a, T> {
+        Union {
+            iter: self.0.merge_join(&other.0),
+        }
+    }
+
+    /// Returns true if `self` has no values in common with `other`.
+    #[inline]
+    pub fn is_disjoint(&self, other: &OrdSet<T>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    /// Returns true if every value of `self` is contained in `other`.
+    #[inline]
+    pub fn is_subset(&self, other: &OrdSet<T>) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    /// Returns true if every value of `other` is contained in `self`.
+    #[inline]
+    pub fn is_superset(&self, other: &OrdSet<T>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Return true if the underlying tree is balanced.
+    pub fn check_balanced(&self) -> bool {
+        self.0.check_balanced()
+    }
+}
+
+impl<T> Default for OrdSet<T>
+where
+    T: Ord,
+{
+    /// Creates an empty `OrdSet<T>`.
+    #[inline]
+    fn default() -> OrdSet<T> {
+        OrdSet::new()
+    }
+}
+
+impl<T> Clone for OrdSet<T>
+where
+    T: Ord + Clone,
+{
+    fn clone(&self) -> Self {
+        OrdSet(self.0.clone())
+    }
+}
+
+impl<T> PartialEq for OrdSet<T>
+where
+    T: Ord,
+{
+    fn eq(&self, other: &OrdSet<T>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T> Eq for OrdSet<T>
+where
+    T: Ord,
+{
+}
+
+impl<T> Hash for OrdSet<T>
+where
+    T: Ord + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<T> FromIterator<T> for OrdSet<T>
+where
+    T: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> OrdSet<T> {
+        let mut set = OrdSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T> Extend<T> for OrdSet<T>
+where
+    T: Ord,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a OrdSet<T>
+where
+    T: Ord,
+{
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for OrdSet<T>
+where
+    T: Ord,
+{
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            iter: self.0.into_iter().into_sorted_list(),
+        }
+    }
+}
+
+/// An iterator over the values of a `OrdSet`.
+///
+/// This `struct` is created by the [`iter`] method on [`OrdSet`]. See its
+/// documentation for more.
+///
+/// [`iter`]: struct.OrdSet.html#method.iter
+/// [`OrdSet`]: struct.OrdSet.html
+pub struct Iter<'a, T: 'a + Ord> {
+    iter: ord_map::Keys<'a, T, ()>,
+}
+
+impl<'a, T: Ord> Clone for Iter<'a, T> {
+    fn clone(&self) -> Iter<'a, T> {
+        Iter {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: Ord> DoubleEndedIterator for Iter<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back()
+    }
+}
+
+/// An owning iterator over the values of a `OrdSet`, in incremental order.
+///
+/// This `struct` is created by the `into_iter` method on [`OrdSet`]
+/// (provided by the `IntoIterator` trait).
+///
+/// [`OrdSet`]: struct.OrdSet.html
+pub struct IntoIter<T: Ord> {
+    iter: ord_map::SortedList<T, ()>,
+}
+
+impl<T: Ord> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// An iterator over a sub-range of values in a `OrdSet`.
+///
+/// This `struct` is created by the [`range`] method on [`OrdSet`]. See its
+/// documentation for more.
+///
+/// [`range`]: struct.OrdSet.html#method.range
+/// [`OrdSet`]: struct.OrdSet.html
+pub struct Range<'a, T: 'a> {
+    iter: ord_map::Range<'a, T, ()>,
+}
+
+impl<'a, T> Clone for Range<'a, T> {
+    fn clone(&self) -> Range<'a, T> {
+        Range {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Range<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Range<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.next_back().map(|(k, _)| k)
+    }
+}
+
+/// A lazy iterator producing the values of the intersection of two `OrdSet`s.
+///
+/// This `struct` is created by the [`intersection`] method on [`OrdSet`]. See its
+/// documentation for more.
+///
+/// [`intersection`]: struct.OrdSet.html#method.intersection
+/// [`OrdSet`]: struct.OrdSet.html
+pub struct Intersection<'a, T: 'a> {
+    iter: ord_map::Intersection<'a, T, ()>,
+}
+
+impl<'a, T: Ord> Iterator for Intersection<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// A lazy iterator producing the values of the difference of two `OrdSet`s.
+///
+/// This `struct` is created by the [`difference`] method on [`OrdSet`]. See its
+/// documentation for more.
+///
+/// [`difference`]: struct.OrdSet.html#method.difference
+/// [`OrdSet`]: struct.OrdSet.html
+pub struct Difference<'a, T: 'a> {
+    iter: ord_map::Difference<'a, T, ()>,
+}
+
+impl<'a, T: Ord> Iterator for Difference<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// A lazy iterator producing the values of the symmetric difference of two `OrdSet`s.
+///
+/// This `struct` is created by the [`symmetric_difference`] method on [`OrdSet`]. See its
+/// documentation for more.
+///
+/// [`symmetric_difference`]: struct.OrdSet.html#method.symmetric_difference
+/// [`OrdSet`]: struct.OrdSet.html
+pub struct SymmetricDifference<'a, T: 'a> {
+    iter: ord_map::SymmetricDifference<'a, T, ()>,
+}
+
+impl<'a, T: Ord> Iterator for SymmetricDifference<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// A lazy iterator producing the values of the union of two `OrdSet`s.
+///
+/// This `struct` is created by the [`union`] method on [`OrdSet`]. See its
+/// documentation for more.
+///
+/// [`union`]: struct.OrdSet.html#method.union
+/// [`OrdSet`]: struct.OrdSet.html
+pub struct Union<'a, T: 'a> {
+    iter: ord_map::MergeJoin<'a, T, ()>,
+}
+
+impl<'a, T: Ord> Iterator for Union<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
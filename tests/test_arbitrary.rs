@@ -0,0 +1,49 @@
+#![cfg(feature = "fuzz")]
+
+extern crate arbitrary;
+extern crate hash_ord;
+
+use arbitrary::{Arbitrary, Unstructured};
+use hash_ord::hash_map::HashMap;
+use hash_ord::ord_map::OrdMap;
+
+/// Pseudo random bytes from a fixed seed, standing in for fuzzer input.
+fn seeded_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_arbitrary_ord_map() {
+    let mut total = 0;
+    for seed in 0..20 {
+        let bytes = seeded_bytes(seed, 4096);
+        let mut u = Unstructured::new(&bytes);
+        let map = OrdMap::<u16, u8>::arbitrary(&mut u).unwrap();
+        assert!(map.check_balanced());
+        assert!(map.check_ord_valid());
+        assert_eq!(map.node_count(), map.len());
+        total += map.len();
+    }
+    assert!(total > 0);
+}
+
+#[test]
+fn test_arbitrary_hash_map() {
+    for seed in 0..20 {
+        let bytes = seeded_bytes(seed, 4096);
+        let mut u = Unstructured::new(&bytes);
+        let map = HashMap::<u16, u8>::arbitrary(&mut u).unwrap();
+        assert_eq!(map.bucket_depths().sum::<i32>() as usize, map.len());
+        for (k, v) in map.iter() {
+            assert_eq!(map.get(k), Some(v));
+        }
+    }
+}
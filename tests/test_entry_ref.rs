@@ -0,0 +1,71 @@
+#![cfg(feature = "std")]
+
+extern crate hash_ord;
+
+use hash_ord::hash_map::HashMap;
+use hash_ord::ord_map::OrdMap;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the allocations made by the current thread.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOC_COUNT: Cell<usize> = Cell::new(0);
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOC_COUNT.try_with(|cnt| cnt.set(cnt.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn alloc_count() -> usize {
+    ALLOC_COUNT.with(|cnt| cnt.get())
+}
+
+#[test]
+fn test_hash_map_entry_ref() {
+    let mut map: HashMap<String, u32> = HashMap::new();
+    map.insert("key".to_string(), 0);
+
+    let before = alloc_count();
+    for _ in 0..100 {
+        *map.entry_ref("key").or_insert(0) += 1;
+    }
+    map.entry_ref("key").and_modify(|v| *v += 1).or_insert(0);
+    assert_eq!(alloc_count(), before);
+    assert_eq!(map["key"], 101);
+
+    *map.entry_ref("other").or_insert_with(|| 7) += 1;
+    assert!(alloc_count() > before);
+    assert_eq!(map["other"], 8);
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn test_ord_map_entry_ref() {
+    let mut map: OrdMap<String, u32> = OrdMap::new();
+    map.insert("key".to_string(), 0);
+
+    let before = alloc_count();
+    for _ in 0..100 {
+        *map.entry_ref("key").or_insert(0) += 1;
+    }
+    map.entry_ref("key").and_modify(|v| *v += 1).or_insert(0);
+    assert_eq!(alloc_count(), before);
+    assert_eq!(map.get("key"), Some(&101));
+
+    *map.entry_ref("other").or_insert_with(|| 7) += 1;
+    assert!(alloc_count() > before);
+    assert_eq!(map.get("other"), Some(&8));
+    assert_eq!(map.len(), 2);
+}
@@ -0,0 +1,54 @@
+// Only checks the crate without `std` when run with `--no-default-features`, as CI does.
+#![no_std]
+
+extern crate alloc;
+extern crate hash_ord;
+
+use alloc::vec::Vec;
+use core::hash::{BuildHasherDefault, Hasher};
+use hash_ord::hash_map::HashMap;
+use hash_ord::ord_map::OrdMap;
+
+#[derive(Default)]
+struct SimpleHasher(u64);
+
+impl Hasher for SimpleHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.wrapping_mul(31).wrapping_add(b as u64);
+        }
+    }
+}
+
+#[test]
+fn test_no_std_ord_map() {
+    let mut map = OrdMap::new();
+    for i in 0..100 {
+        map.insert(i, i * 2);
+    }
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.get(&10), Some(&20));
+    let keys: Vec<_> = map.keys().cloned().take(3).collect();
+    assert_eq!(keys, [0, 1, 2]);
+    map.remove(&10);
+    assert!(map.get(&10).is_none());
+    assert!(map.check_balanced());
+}
+
+#[test]
+fn test_no_std_hash_map() {
+    let mut map = HashMap::with_hasher(BuildHasherDefault::<SimpleHasher>::default());
+    for i in 0..100 {
+        map.insert(i, i * 2);
+    }
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.get(&10), Some(&20));
+    assert_eq!(map.remove(&10), Some((10, 20)));
+    assert!(map.get(&10).is_none());
+    map.clear();
+    assert!(map.is_empty());
+}
@@ -1,12 +1,18 @@
 extern crate hash_ord;
 extern crate rand;
 
-use hash_ord::ord_map::OrdMap;
+use hash_ord::{Alloc, Global};
+use hash_ord::hash_map::HashMap;
+use hash_ord::ord_map::{OrdMap, OrdMapBy};
 use std::ops::Bound::{Excluded, Included, Unbounded};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use hash_ord::ord_map::Entry::Vacant;
 use hash_ord::ord_map::Entry::Occupied;
+use std::alloc::Layout;
 use std::rc::Rc;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 type DefaultType = OrdMap<i32, Option<i32>>;
 
@@ -63,6 +69,52 @@ fn test_avl_split_off() {
     assert_eq!(*cnt.borrow(), test_num);
 }
 
+#[test]
+fn test_avl_split_off_pivots() {
+    for &pivot in &[-5, 0, 1, 333, 500, 998, 999, 1000, 5000] {
+        let mut ma: OrdMap<_, _> = (0..1000).map(|i| (i * 2, i)).collect();
+        let mb = ma.split_off(&(pivot * 2));
+        let split = if pivot < 0 { 0 } else { pivot.min(1000) };
+        assert!(ma.check_balanced());
+        assert!(mb.check_balanced());
+        assert_eq!(ma.len(), split as usize);
+        assert_eq!(mb.len(), 1000 - split as usize);
+        assert!(ma.iter().map(|(k, v)| (*k, *v)).eq((0..split).map(|i| (i * 2, i))));
+        assert!(mb.iter().map(|(k, v)| (*k, *v)).eq((split..1000).map(|i| (i * 2, i))));
+
+        // the key is not present
+        let mut ma: OrdMap<_, _> = (0..1000).map(|i| (i * 2, i)).collect();
+        let mb = ma.split_off(&(pivot * 2 - 1));
+        assert!(ma.check_balanced());
+        assert!(mb.check_balanced());
+        assert_eq!(ma.len(), split as usize);
+        assert_eq!(mb.len(), 1000 - split as usize);
+        ma.insert(-100, -100);
+        assert_eq!(ma.len(), split as usize + 1);
+    }
+}
+
+#[test]
+fn test_avl_split_off_keeps_larger_half() {
+    let mut ma: OrdMap<_, _> = (0..1000).map(|i| (i, i)).collect();
+    let (small, large) = (&ma[&10] as *const i32, &ma[&900] as *const i32);
+    let mb = ma.split_off(&100);
+    assert!(std::ptr::eq(&mb[&900], large));
+    assert!(!std::ptr::eq(&ma[&10], small));
+    assert!(ma.check_balanced());
+    assert!(mb.check_balanced());
+    assert!(ma.keys().cloned().eq(0..100));
+    assert!(mb.keys().cloned().eq(100..1000));
+
+    let mut ma: OrdMap<_, _> = (0..1000).map(|i| (i, i)).collect();
+    let (small, large) = (&ma[&10] as *const i32, &ma[&900] as *const i32);
+    let mb = ma.split_off(&800);
+    assert!(std::ptr::eq(&ma[&10], small));
+    assert!(!std::ptr::eq(&mb[&900], large));
+    assert!(ma.keys().cloned().eq(0..800));
+    assert!(mb.keys().cloned().eq(800..1000));
+}
+
 #[test]
 fn test_avl_range() {
     let mut map = OrdMap::new();
@@ -150,6 +202,130 @@ fn test_avl_append() {
     assert_eq!(*cnt.borrow(), 2 * test_num + (test_num - test_num / 2));
 }
 
+#[test]
+fn test_avl_append_report_conflicts() {
+    let cnt = RefCell::new(0);
+    let test_num = 100usize;
+    let mut ma = OrdMap::new();
+    for i in 0..test_num {
+        ma.insert(i, (i, Node { b: &cnt }));
+    }
+    let mut mb = OrdMap::new();
+    for i in test_num / 2..test_num * 3 / 2 {
+        mb.insert(i, (i + test_num, Node { b: &cnt }));
+    }
+    let conflicts = ma.append_report_conflicts(&mut mb);
+    assert!(ma.check_balanced());
+    assert!(ma.check_ord_valid());
+    assert_eq!(ma.len(), test_num * 3 / 2);
+    assert_eq!(mb.len(), 0);
+    assert_eq!(*cnt.borrow(), 0);
+    assert_eq!(conflicts.len(), test_num / 2);
+    for (idx, &(k, (v, _))) in conflicts.iter().enumerate() {
+        assert_eq!(k, test_num / 2 + idx);
+        assert_eq!(v, k);
+    }
+    for (k, &(v, _)) in ma.iter() {
+        if *k < test_num / 2 {
+            assert_eq!(v, *k);
+        } else {
+            assert_eq!(v, *k + test_num);
+        }
+    }
+    drop(conflicts);
+    assert_eq!(*cnt.borrow(), test_num / 2);
+    drop(ma);
+    assert_eq!(*cnt.borrow(), test_num * 2);
+
+    let mut ma = default_build_avl(10);
+    let mut mb = OrdMap::new();
+    mb.insert(20, None);
+    assert!(ma.append_report_conflicts(&mut mb).is_empty());
+    assert_eq!(ma.len(), 11);
+}
+
+#[test]
+fn test_avl_append_disjoint() {
+    use std::cell::Cell;
+
+    struct Key<'a>(i32, &'a Cell<usize>);
+    impl<'a> PartialEq for Key<'a> {
+        fn eq(&self, other: &Key<'a>) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl<'a> Eq for Key<'a> {}
+    impl<'a> PartialOrd for Key<'a> {
+        fn partial_cmp(&self, other: &Key<'a>) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl<'a> Ord for Key<'a> {
+        fn cmp(&self, other: &Key<'a>) -> Ordering {
+            self.1.set(self.1.get() + 1);
+            self.0.cmp(&other.0)
+        }
+    }
+
+    let cmp_cnt = Cell::new(0);
+    let build = |r: std::ops::Range<i32>| {
+        let map: OrdMap<_, _> = r.map(|i| (Key(i, &cmp_cnt), i)).collect();
+        map
+    };
+
+    let mut ma = build(0..50);
+    let mut mb = build(50..100);
+    cmp_cnt.set(0);
+    ma.append(&mut mb);
+    let disjoint_cnt = cmp_cnt.get();
+    assert!(ma.check_balanced());
+    assert_eq!(ma.len(), 100);
+    assert!(mb.is_empty());
+    assert!(ma.iter().map(|(k, v)| (k.0, *v)).eq((0..100).map(|i| (i, i))));
+
+    // other before self
+    let mut ma = build(50..100);
+    let mut mb = build(0..50);
+    cmp_cnt.set(0);
+    ma.append(&mut mb);
+    assert!(cmp_cnt.get() <= 2);
+    assert!(ma.check_balanced());
+    assert!(ma.iter().map(|(k, v)| (k.0, *v)).eq((0..100).map(|i| (i, i))));
+
+    let mut ma = build(0..50);
+    let mut mb = build(25..75);
+    cmp_cnt.set(0);
+    ma.append(&mut mb);
+    let overlap_cnt = cmp_cnt.get();
+    assert!(ma.check_balanced());
+    assert_eq!(ma.len(), 75);
+
+    assert!(disjoint_cnt <= 2);
+    assert!(disjoint_cnt < overlap_cnt);
+}
+
+#[test]
+fn test_avl_append_disjoint_unequal_height() {
+    for &(a, b) in &[(1000, 1), (1000, 3), (1, 1000), (3, 1000), (500, 37)] {
+        // other after self
+        let mut ma: OrdMap<_, _> = (0..a).map(|i| (i, i)).collect();
+        let mut mb: OrdMap<_, _> = (a..a + b).map(|i| (i, i)).collect();
+        ma.append(&mut mb);
+        assert!(ma.check_balanced());
+        assert_eq!(ma.len(), a + b);
+        assert!(ma.iter().map(|(k, v)| (*k, *v)).eq((0..a + b).map(|i| (i, i))));
+
+        // other before self
+        let mut ma: OrdMap<_, _> = (b..a + b).map(|i| (i, i)).collect();
+        let mut mb: OrdMap<_, _> = (0..b).map(|i| (i, i)).collect();
+        ma.append(&mut mb);
+        assert!(ma.check_balanced());
+        assert_eq!(ma.len(), a + b);
+        assert!(ma.iter().map(|(k, v)| (*k, *v)).eq((0..a + b).map(|i| (i, i))));
+        assert_eq!(ma.iter().next(), Some((&0, &0)));
+    }
+}
+
 #[test]
 fn test_avl_entry() {
     let xs = [(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)];
@@ -374,6 +550,35 @@ fn test_avl_values_index() {
     }
 }
 
+#[test]
+fn test_avl_keys_values_rev() {
+    let mut t = OrdMap::new();
+    for x in default_make_avl_element(100) {
+        t.insert(x, -x);
+    }
+    let mut keys: Vec<_> = t.keys().collect();
+    let rev_keys: Vec<_> = t.keys().rev().collect();
+    keys.reverse();
+    assert_eq!(keys, rev_keys);
+
+    let mut values: Vec<_> = t.values().cloned().collect();
+    let rev_values: Vec<_> = t.values().rev().cloned().collect();
+    values.reverse();
+    assert_eq!(values, rev_values);
+
+    for (i, v) in t.values_mut().rev().enumerate() {
+        *v = i as i32;
+    }
+    let values: Vec<_> = t.values().cloned().collect();
+    let expected: Vec<_> = (0..100).rev().collect();
+    assert_eq!(values, expected);
+
+    let mut keys = t.keys();
+    assert_eq!(keys.next(), Some(&0));
+    assert_eq!(keys.next_back(), Some(&99));
+    assert_eq!(keys.size_hint(), (98, Some(98)));
+}
+
 #[test]
 fn test_avl_find() {
     let t = default_build_avl(1000);
@@ -402,3 +607,1718 @@ fn test_avl_erase() {
     assert!(t.check_ord_valid());
     assert!(t.check_balanced());
 }
+
+#[test]
+fn test_avl_drain() {
+    let cnt = RefCell::new(0);
+    let test_num = 100;
+    let mut map = OrdMap::new();
+    for i in 0..test_num {
+        map.insert(i, Node { b: &cnt });
+    }
+    {
+        let mut drain = map.drain();
+        assert_eq!(drain.len(), test_num);
+        for i in 0..test_num / 2 {
+            let (k, _) = drain.next().unwrap();
+            assert_eq!(k, i);
+        }
+        assert_eq!(*cnt.borrow(), test_num / 2);
+    }
+    assert!(map.is_empty());
+    assert!(map.check_ord_valid());
+    assert_eq!(*cnt.borrow(), test_num);
+    map.insert(1, Node { b: &cnt });
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_avl_from_sorted_iter() {
+    let test_num = 1_000_000;
+    let fast = OrdMap::from_sorted_iter((0..test_num).map(|i| (i, -i)));
+    assert_eq!(fast.len(), test_num as usize);
+    assert!(fast.check_balanced());
+    assert!(fast.check_ord_valid());
+    let slow: OrdMap<_, _> = (0..test_num).map(|i| (i, -i)).collect();
+    assert!(fast == slow);
+
+    let empty = OrdMap::<i32, i32>::from_sorted_iter(Vec::new());
+    assert!(empty.is_empty());
+    assert!(empty.check_ord_valid());
+}
+
+#[test]
+fn test_avl_cursors_insert() {
+    let mut t = OrdMap::new();
+    for i in 0..50 {
+        t.insert(i * 2, i);
+    }
+    {
+        let mut cursors = t.find_cursors(&50);
+        cursors.insert_before(49, -1);
+        cursors.insert_after(51, -1);
+        assert_eq!(*cursors.get().unwrap().0, 50);
+        cursors.prev();
+        assert_eq!(*cursors.get().unwrap().0, 49);
+        cursors.next();
+        cursors.next();
+        assert_eq!(*cursors.get().unwrap().0, 51);
+    }
+    assert_eq!(t.len(), 52);
+    assert!(t.check_ord_valid());
+    assert!(t.check_balanced());
+
+    let mut t = OrdMap::new();
+    {
+        let mut cursors = t.find_cursors(&0);
+        for i in 0..100 {
+            cursors.insert_before(i, i);
+        }
+    }
+    assert_eq!(t.len(), 100);
+    assert!(t.check_ord_valid());
+    assert!(t.check_balanced());
+}
+
+#[test]
+fn test_avl_cursors_peek() {
+    let mut t = default_build_avl(100);
+    {
+        let cursors = t.find_cursors(&50);
+        assert_eq!(*cursors.peek_next().unwrap().0, 51);
+        assert_eq!(*cursors.peek_prev().unwrap().0, 49);
+        assert_eq!(*cursors.get().unwrap().0, 50);
+    }
+    {
+        let cursors = t.find_cursors(&99);
+        assert!(cursors.peek_next().is_none());
+        assert_eq!(*cursors.peek_prev().unwrap().0, 98);
+    }
+    {
+        let cursors = t.find_cursors(&0);
+        assert!(cursors.peek_prev().is_none());
+        assert_eq!(*cursors.peek_next().unwrap().0, 1);
+    }
+}
+
+#[test]
+fn test_avl_ord() {
+    let a: OrdMap<_, _> = (0..10).map(|i| (i, i)).collect();
+    let b: OrdMap<_, _> = (0..5).map(|i| (i, i)).collect();
+    let c: OrdMap<_, _> = (0..10).map(|i| (i, i)).collect();
+    let d: OrdMap<_, _> = (0..10).map(|i| (i, if i == 7 { 100 } else { i })).collect();
+    let empty = OrdMap::<i32, i32>::new();
+    assert!(b < a);
+    assert!(a > b);
+    assert!(empty < b);
+    assert_eq!(a.cmp(&c), Ordering::Equal);
+    assert_eq!(a.partial_cmp(&c), Some(Ordering::Equal));
+    assert!(a < d);
+    let mut outer = OrdMap::new();
+    outer.insert(d, "d");
+    outer.insert(a, "a");
+    outer.insert(b, "b");
+    let values: Vec<_> = outer.values().cloned().collect();
+    assert_eq!(values, vec!["b", "a", "d"]);
+}
+
+#[test]
+fn test_avl_hash() {
+    fn hash<T: Hash>(t: &T) -> u64 {
+        let mut s = DefaultHasher::new();
+        t.hash(&mut s);
+        s.finish()
+    }
+    let a: OrdMap<_, _> = (0..100).map(|i| (i, -i)).collect();
+    let b: OrdMap<_, _> = (0..100).rev().map(|i| (i, -i)).collect();
+    assert!(a == b);
+    assert_eq!(hash(&a), hash(&b));
+    let c: OrdMap<_, _> = (0..99).map(|i| (i, -i)).collect();
+    assert_ne!(hash(&a), hash(&c));
+}
+
+#[test]
+fn test_avl_ord_map_by() {
+    let cnt = RefCell::new(0);
+    {
+        let mut map =
+            OrdMapBy::new(|a: &String, b: &String| a.to_lowercase().cmp(&b.to_lowercase()));
+        map.insert("Foo".to_string(), Node { b: &cnt });
+        map.insert("bar".to_string(), Node { b: &cnt });
+        map.insert("Baz".to_string(), Node { b: &cnt });
+        assert_eq!(map.len(), 3);
+        let old = map.insert("foo".to_string(), Node { b: &cnt });
+        assert_eq!(old.unwrap().0, "Foo");
+        assert_eq!(*cnt.borrow(), 1);
+        assert_eq!(map.len(), 3);
+        assert!(map.contains_key(&"FOO".to_string()));
+        assert!(!map.contains_key(&"qux".to_string()));
+        let keys: Vec<_> = map.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, vec!["bar", "Baz", "foo"]);
+        assert!(map.check_ord_valid());
+        assert!(map.remove(&"BAR".to_string()).is_some());
+        assert_eq!(*cnt.borrow(), 2);
+        assert_eq!(map.len(), 2);
+    }
+    assert_eq!(*cnt.borrow(), 4);
+
+    let mut map = OrdMapBy::new(|a: &i32, b: &i32| b.cmp(a));
+    for i in 0..100 {
+        map.insert(i, -i);
+    }
+    assert!(map.check_balanced());
+    assert!(map.check_ord_valid());
+    *map.get_mut(&10).unwrap() = 10;
+    assert_eq!(map.get(&10), Some(&10));
+    let keys: Vec<_> = map.iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, (0..100).rev().collect::<Vec<_>>());
+
+    // the comparator may be defined on a borrowed form of the key
+    let mut map = OrdMapBy::new(|a: &str, b: &str| a.len().cmp(&b.len()));
+    map.insert("aaa".to_string(), 3);
+    map.insert("b".to_string(), 1);
+    map.insert("cc".to_string(), 2);
+    assert_eq!(map.insert("dd".to_string(), 4), Some(("cc".to_string(), 2)));
+    assert_eq!(map.get("xx"), Some(&4));
+    assert!(map.contains_key("zzz"));
+    assert_eq!(map.remove("y"), Some(("b".to_string(), 1)));
+    assert_eq!(map.remove("y"), None);
+    let keys: Vec<_> = map.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, vec!["dd", "aaa"]);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.iter().next(), None);
+}
+
+#[test]
+fn test_avl_set_algebra() {
+    let a: OrdMap<_, _> = (0..60).map(|i| (i, i)).collect();
+    let b: OrdMap<_, _> = (40..100).map(|i| (i, -i)).collect();
+
+    let v: Vec<_> = a.intersection(&b).collect();
+    assert_eq!(v.len(), 20);
+    for (i, &(k, v)) in v.iter().enumerate() {
+        assert_eq!(*k, 40 + i as i32);
+        assert_eq!(*v, *k);
+    }
+
+    let keys: Vec<_> = a.difference(&b).map(|(k, _)| *k).collect();
+    assert_eq!(keys, (0..40).collect::<Vec<_>>());
+    let keys: Vec<_> = b.difference(&a).map(|(k, _)| *k).collect();
+    assert_eq!(keys, (60..100).collect::<Vec<_>>());
+
+    let v: Vec<_> = a.symmetric_difference(&b).map(|(k, v)| (*k, *v)).collect();
+    let expect: Vec<_> = (0..40).map(|i| (i, i)).chain((60..100).map(|i| (i, -i))).collect();
+    assert_eq!(v, expect);
+
+    let empty = OrdMap::new();
+    assert_eq!(a.intersection(&empty).count(), 0);
+    assert_eq!(a.difference(&empty).count(), 60);
+    assert_eq!(empty.symmetric_difference(&b).count(), 60);
+}
+
+#[test]
+fn test_avl_split_off_at_rank() {
+    let cnt = RefCell::new(0);
+    let test_num = 100;
+    {
+        let mut ma = OrdMap::new();
+        for i in 0..test_num {
+            ma.insert(i, Node { b: &cnt });
+        }
+        let mb = ma.split_off_at_rank(40);
+        assert_eq!(ma.len(), 40);
+        assert_eq!(mb.len(), 60);
+        assert!(ma.check_balanced() && ma.check_ord_valid());
+        assert!(mb.check_balanced() && mb.check_ord_valid());
+        assert_eq!(*ma.keys().last().unwrap(), 39);
+        assert_eq!(*mb.keys().next().unwrap(), 40);
+        assert_eq!(*cnt.borrow(), 0);
+        drop(mb);
+        assert_eq!(*cnt.borrow(), 60);
+
+        let mc = ma.split_off_at_rank(40);
+        assert!(mc.is_empty());
+        assert_eq!(ma.len(), 40);
+        let md = ma.split_off_at_rank(0);
+        assert!(ma.is_empty());
+        assert_eq!(md.len(), 40);
+    }
+    assert_eq!(*cnt.borrow(), test_num);
+}
+
+#[test]
+fn test_avl_range_borrowed() {
+    let mut map = OrdMap::new();
+    for s in &["a", "b", "bb", "c", "d", "e"] {
+        map.insert(s.to_string(), s.len());
+    }
+    let keys: Vec<_> = map.range::<str, _>((Included("b"), Excluded("d")))
+        .map(|(k, _)| k.as_str())
+        .collect();
+    assert_eq!(keys, vec!["b", "bb", "c"]);
+    let keys: Vec<_> = map.range::<str, _>((Excluded("b"), Unbounded))
+        .map(|(k, _)| k.as_str())
+        .collect();
+    assert_eq!(keys, vec!["bb", "c", "d", "e"]);
+    for (_, v) in map.range_mut::<str, _>((Included("b"), Included("c"))) {
+        *v = 0;
+    }
+    assert_eq!(map.get("bb"), Some(&0));
+    assert_eq!(map.get("d"), Some(&1));
+}
+
+#[test]
+fn test_avl_remove_range() {
+    let cnt = RefCell::new(0);
+    let mut map = OrdMap::new();
+    for i in 0..100 {
+        map.insert(i, Node { b: &cnt });
+    }
+    assert_eq!(map.remove_range(20..60), 40);
+    assert_eq!(*cnt.borrow(), 40);
+    assert_eq!(map.len(), 60);
+    for i in 0..100 {
+        assert_eq!(map.contains_key(&i), i < 20 || i >= 60);
+    }
+    assert!(map.check_balanced());
+    assert!(map.check_ord_valid());
+    assert_eq!(map.remove_range(20..60), 0);
+    assert_eq!(map.remove_range((Excluded(&90), Unbounded)), 9);
+    assert_eq!(map.remove_range(..), 51);
+    assert!(map.is_empty());
+    assert_eq!(*cnt.borrow(), 100);
+}
+
+#[test]
+fn test_avl_entry_replace() {
+    let mut map: OrdMap<Rc<String>, u32> = OrdMap::new();
+    let stored_key = Rc::new("Stringthing".to_string());
+    map.insert(stored_key.clone(), 15);
+
+    let my_key = Rc::new("Stringthing".to_string());
+    if let Occupied(entry) = map.entry(my_key.clone()) {
+        assert_eq!(entry.replace(16), 15);
+    }
+    assert_eq!(map[&stored_key], 16);
+    assert!(Rc::ptr_eq(map.keys().next().unwrap(), &stored_key));
+    assert_eq!(Rc::strong_count(&my_key), 1);
+
+    if let Occupied(entry) = map.entry(my_key.clone()) {
+        let (old_key, old_value) = entry.replace_entry(17);
+        assert!(Rc::ptr_eq(&old_key, &stored_key));
+        assert_eq!(old_value, 16);
+    }
+    assert!(Rc::ptr_eq(map.keys().next().unwrap(), &my_key));
+
+    let mut map: OrdMap<i32, i32> = OrdMap::new();
+    assert_eq!(*map.entry(3).or_insert_with_key(|k| *k * 10), 30);
+    assert_eq!(*map.entry(3).or_insert_with_key(|_| unreachable!()), 30);
+}
+
+#[test]
+fn test_avl_const_new() {
+    const EMPTY: OrdMap<i32, i32> = OrdMap::new();
+    assert!(EMPTY.is_empty());
+    let mut a = EMPTY.clone();
+    let mut b = EMPTY;
+    for i in 0..100 {
+        a.insert(i, -i);
+        b.insert(i, i);
+    }
+    assert_eq!(a.len(), 100);
+    assert_eq!(b.len(), 100);
+    assert!(EMPTY.is_empty());
+    assert!(a.check_balanced() && b.check_balanced());
+}
+
+#[test]
+fn test_avl_default() {
+    #[derive(Default)]
+    struct Wrapper {
+        map: OrdMap<i32, i32>,
+        cnt: usize,
+    }
+    let mut w = Wrapper::default();
+    assert!(w.map.is_empty());
+    assert_eq!(w.cnt, 0);
+    w.map.insert(1, 1);
+    w.cnt += 1;
+    assert_eq!(w.map.len(), w.cnt);
+}
+
+#[test]
+fn test_avl_nearest_key() {
+    let map: OrdMap<_, _> = (0..100).map(|i| (i * 10, i)).collect();
+    let empty: OrdMap<i32, i32> = OrdMap::new();
+    for q in -5..1005 {
+        let le = map.iter().filter(|&(k, _)| *k <= q).last();
+        let lt = map.iter().filter(|&(k, _)| *k < q).last();
+        let ge = map.iter().find(|&(k, _)| *k >= q);
+        let gt = map.iter().find(|&(k, _)| *k > q);
+        assert_eq!(map.get_le(&q), le);
+        assert_eq!(map.get_lt(&q), lt);
+        assert_eq!(map.get_ge(&q), ge);
+        assert_eq!(map.get_gt(&q), gt);
+        assert_eq!(empty.get_le(&q), None);
+        assert_eq!(empty.get_gt(&q), None);
+    }
+    assert_eq!(map.get_le(&0), Some((&0, &0)));
+    assert_eq!(map.get_lt(&0), None);
+    assert_eq!(map.get_ge(&990), Some((&990, &99)));
+    assert_eq!(map.get_gt(&990), None);
+    assert_eq!(map.get_lt(&55), Some((&50, &5)));
+    assert_eq!(map.get_gt(&55), Some((&60, &6)));
+}
+
+#[test]
+fn test_avl_height_and_node_count() {
+    let mut map = OrdMap::new();
+    assert_eq!(map.height(), 0);
+    assert_eq!(map.node_count(), 0);
+    assert!(map.is_balanced());
+    let test_num = 10000;
+    for round in 0..test_num {
+        let x = rand::random::<usize>() % 1000;
+        if round % 3 == 0 {
+            map.remove(&x);
+        } else {
+            map.insert(x, round);
+        }
+        if round % 100 == 0 {
+            assert!(map.is_balanced());
+            assert_eq!(map.node_count(), map.len());
+            // the height of an AVL tree is less than 1.45 * log2(n + 2)
+            let bound = 1.45 * ((map.len() + 2) as f64).log2();
+            assert!((map.height() as f64) < bound);
+            assert_eq!(map.height(), map.max_height());
+        }
+    }
+    for x in 0..1000 {
+        map.remove(&x);
+    }
+    assert_eq!(map.height(), 0);
+    assert_eq!(map.node_count(), 0);
+}
+
+#[test]
+fn test_avl_clear_large() {
+    let cnt = RefCell::new(0);
+    let test_num = 1000000;
+    let mut map: OrdMap<_, _> = (0..test_num).map(|i| (i, Node { b: &cnt })).collect();
+    assert_eq!(map.len(), test_num);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(*cnt.borrow(), test_num);
+    map.insert(1, Node { b: &cnt });
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_avl_for_each() {
+    let mut map = OrdMap::new();
+    for x in default_make_avl_element(1000) {
+        map.insert(x, x as i64 * 3);
+    }
+    let mut sum = 0;
+    let mut prev = -1;
+    map.for_each(|k, v| {
+        assert!(prev < *k);
+        prev = *k;
+        sum += *v;
+    });
+    assert_eq!(sum, map.iter().fold(0, |acc, (_, v)| acc + *v));
+
+    map.for_each_mut(|k, v| *v = -*k as i64);
+    assert_eq!(map.iter().fold(0, |acc, (_, v)| acc + *v), -sum / 3);
+
+    let empty: OrdMap<i32, i32> = OrdMap::new();
+    empty.for_each(|_, _| panic!());
+}
+
+/// An allocator which counts the pages it hands out and takes back. Clones of one made by
+/// `interchangeable` say they may free each other's pages, any other allocator does not.
+#[derive(Clone)]
+struct CountingAlloc<'a> {
+    alloc_cnt: &'a Cell<usize>,
+    dealloc_cnt: &'a Cell<usize>,
+    interchangeable: bool,
+}
+
+impl<'a> CountingAlloc<'a> {
+    fn new(alloc_cnt: &'a Cell<usize>, dealloc_cnt: &'a Cell<usize>) -> Self {
+        CountingAlloc {
+            alloc_cnt,
+            dealloc_cnt,
+            interchangeable: false,
+        }
+    }
+
+    fn interchangeable(alloc_cnt: &'a Cell<usize>, dealloc_cnt: &'a Cell<usize>) -> Self {
+        CountingAlloc {
+            interchangeable: true,
+            ..CountingAlloc::new(alloc_cnt, dealloc_cnt)
+        }
+    }
+}
+
+unsafe impl<'a> Alloc for CountingAlloc<'a> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.alloc_cnt.set(self.alloc_cnt.get() + 1);
+        Global.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.dealloc_cnt.set(self.dealloc_cnt.get() + 1);
+        Global.dealloc(ptr, layout)
+    }
+
+    fn is_interchangeable(&self, other: &Self) -> bool {
+        self.interchangeable && std::ptr::eq(self.alloc_cnt, other.alloc_cnt)
+    }
+}
+
+#[test]
+fn test_avl_allocator() {
+    let (alloc_cnt, dealloc_cnt) = (Cell::new(0), Cell::new(0));
+    let allocator = CountingAlloc::new(&alloc_cnt, &dealloc_cnt);
+    let mut map = OrdMap::new_in(allocator);
+    assert_eq!(alloc_cnt.get(), 0);
+    for x in default_make_avl_element(1000) {
+        map.insert(x, -x);
+    }
+    assert!(alloc_cnt.get() > 0);
+    assert_eq!(dealloc_cnt.get(), 0);
+
+    let other = map.split_off(&500);
+    let cloned = map.clone();
+    assert_eq!(cloned.len(), 500);
+    assert_eq!(other.len(), 500);
+    let mut cnt = 0;
+    for (k, v) in other.into_iter() {
+        assert_eq!(k, -v);
+        cnt += 1;
+    }
+    assert_eq!(cnt, 500);
+    drop(cloned);
+    map.clear();
+    drop(map);
+    assert_eq!(alloc_cnt.get(), dealloc_cnt.get());
+}
+
+#[test]
+fn test_avl_memory_usage() {
+    let mut map = OrdMap::new();
+    assert_eq!(map.memory_usage(), 0);
+    let mut last = 0;
+    for i in 0..1000 {
+        map.insert(i, i);
+        assert!(map.memory_usage() >= last);
+        last = map.memory_usage();
+    }
+    assert!(last > 1000 * 2 * std::mem::size_of::<i32>());
+    for i in 0..1000 {
+        map.remove(&i);
+    }
+    // removed entries are kept for reuse
+    assert_eq!(map.memory_usage(), last);
+    for i in 0..1000 {
+        map.insert(i, i);
+    }
+    assert_eq!(map.memory_usage(), last);
+}
+
+#[test]
+fn test_avl_align() {
+    #[repr(align(64))]
+    #[derive(Clone)]
+    struct Aligned(i32);
+
+    let mut map = OrdMap::new();
+    for x in default_make_avl_element(1000) {
+        map.insert(x, Aligned(x));
+    }
+    let cloned = map.clone();
+    for m in [&map, &cloned].iter() {
+        for (k, v) in m.iter() {
+            assert_eq!(v as *const Aligned as usize % 64, 0);
+            assert_eq!(v.0, *k);
+        }
+    }
+    let mut map: OrdMap<u128, u8> = OrdMap::new();
+    for x in 0..1000u128 {
+        map.insert(x << 64, x as u8);
+    }
+    for (k, _) in map.iter() {
+        assert_eq!(k as *const u128 as usize % std::mem::align_of::<u128>(), 0);
+    }
+}
+
+#[test]
+fn test_avl_hash_map_conversion() {
+    let v = default_make_avl_element(1000);
+    let ord_map: OrdMap<i32, i32> = v.iter().map(|&x| (x, -x)).collect();
+
+    let hash_map: HashMap<i32, i32> = HashMap::from(ord_map.clone());
+    assert_eq!(hash_map.len(), ord_map.len());
+    for (k, v) in ord_map.iter() {
+        assert_eq!(hash_map.get(k), Some(v));
+    }
+
+    let back = OrdMap::from(hash_map.clone());
+    assert!(back.check_balanced());
+    assert!(back.check_ord_valid());
+    assert!(back == ord_map);
+    assert!(HashMap::from(back) == hash_map);
+}
+
+#[test]
+fn test_avl_from_btree_map() {
+    use std::collections::BTreeMap;
+
+    let btree_map: BTreeMap<i32, i32> = default_make_avl_element(1000)
+        .into_iter()
+        .map(|x| (x, -x))
+        .collect();
+    let map = OrdMap::from(btree_map.clone());
+    assert!(map.check_balanced());
+    assert_eq!(map.len(), btree_map.len());
+    for (k, v) in btree_map.iter() {
+        assert_eq!(map.get(k), Some(v));
+    }
+    assert!(map.iter().eq(btree_map.iter()));
+}
+
+#[test]
+fn test_avl_from_iter_ref() {
+    let map: OrdMap<i32, i32> = (0..100).map(|i| (i, -i)).collect();
+    let copied: OrdMap<i32, i32> = map.iter().collect();
+    assert!(copied == map);
+    assert!(copied.check_balanced());
+}
+
+#[test]
+fn test_avl_debug() {
+    let mut map = OrdMap::new();
+    map.insert(3, "c");
+    map.insert(1, "a");
+    map.insert(2, "b");
+    assert_eq!(format!("{:?}", map), r#"{1: "a", 2: "b", 3: "c"}"#);
+    assert_eq!(format!("{:?}", OrdMap::<i32, i32>::new()), "{}");
+
+    assert_eq!(
+        format!("{:?}", map.entry(1)),
+        r#"Entry(OccupiedEntry { key: 1, value: "a" })"#
+    );
+    assert_eq!(format!("{:?}", map.entry(4)), "Entry(VacantEntry(4))");
+    assert_eq!(
+        format!("{:?}", map.find_cursors(&2)),
+        r#"Cursors(Some((2, "b")))"#
+    );
+}
+
+#[test]
+fn test_avl_range_clone_back() {
+    let map: OrdMap<i32, i32> = (0..10).map(|i| (i, -i)).collect();
+    let mut range = map.range::<i32, _>((Included(&2), Excluded(&6)));
+    assert_eq!(format!("{:?}", range), "[(2, -2), (3, -3), (4, -4), (5, -5)]");
+
+    let peeked: Vec<_> = range.clone().map(|(k, _)| *k).collect();
+    assert_eq!(peeked, vec![2, 3, 4, 5]);
+
+    assert_eq!(range.next_back(), Some((&5, &-5)));
+    assert_eq!(range.next(), Some((&2, &-2)));
+    let backtrack = range.clone();
+    assert_eq!(range.next_back(), Some((&4, &-4)));
+    assert_eq!(range.next_back(), Some((&3, &-3)));
+    assert_eq!(range.next(), None);
+    assert_eq!(format!("{:?}", backtrack), "[(3, -3), (4, -4)]");
+
+    let mut tail = map.range::<i32, _>((Excluded(&7), Unbounded));
+    assert_eq!(tail.next_back(), Some((&9, &-9)));
+    assert_eq!(tail.clone().count(), 1);
+}
+
+#[test]
+fn test_avl_get_or_insert_with() {
+    let mut map = OrdMap::new();
+    let mut called = false;
+    *map.get_or_insert_with(1, || {
+        called = true;
+        10
+    }) += 1;
+    assert!(called);
+    assert_eq!(map[&1], 11);
+
+    called = false;
+    assert_eq!(
+        *map.get_or_insert_with(1, || {
+            called = true;
+            0
+        }),
+        11
+    );
+    assert!(!called);
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_avl_clear_refill() {
+    let test_num: i32 = 100000;
+    let mut map: OrdMap<_, _> = (0..test_num).map(|i| (i, i)).collect();
+    let usage = map.memory_usage();
+    for _ in 0..3 {
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.memory_usage(), usage);
+        for i in (0..test_num).rev() {
+            map.insert(i, -i);
+        }
+        assert_eq!(map.len(), test_num as usize);
+        assert_eq!(map.memory_usage(), usage);
+    }
+    assert!(map.check_balanced());
+}
+
+thread_local! {
+    static LIVE_TRACKED: std::cell::Cell<isize> = std::cell::Cell::new(0);
+    static PANIC_COUNTDOWN: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// Counts live instances, and panics in `clone` or `cmp` when `PANIC_COUNTDOWN` reaches zero.
+struct Tracked(i32);
+
+impl Tracked {
+    fn new(x: i32) -> Tracked {
+        LIVE_TRACKED.with(|live| live.set(live.get() + 1));
+        Tracked(x)
+    }
+
+    fn live() -> isize {
+        LIVE_TRACKED.with(|live| live.get())
+    }
+
+    fn panic_after(n: usize) {
+        PANIC_COUNTDOWN.with(|countdown| countdown.set(n));
+    }
+
+    fn tick() {
+        PANIC_COUNTDOWN.with(|countdown| match countdown.get() {
+            0 => {}
+            1 => {
+                countdown.set(0);
+                panic!("tracked panic");
+            }
+            n => countdown.set(n - 1),
+        });
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        LIVE_TRACKED.with(|live| live.set(live.get() - 1));
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Tracked {
+        Tracked::tick();
+        Tracked::new(self.0)
+    }
+}
+
+impl PartialEq for Tracked {
+    fn eq(&self, other: &Tracked) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Tracked {}
+
+impl PartialOrd for Tracked {
+    fn partial_cmp(&self, other: &Tracked) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Tracked {
+    fn cmp(&self, other: &Tracked) -> Ordering {
+        Tracked::tick();
+        self.0.cmp(&other.0)
+    }
+}
+
+#[test]
+fn test_avl_clone_panic_safe() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let map: OrdMap<_, _> = (0..100).map(|i| (Tracked::new(i), Tracked::new(-i))).collect();
+    assert_eq!(Tracked::live(), 200);
+    for n in &[1, 2, 51, 150, 200] {
+        Tracked::panic_after(*n);
+        assert!(catch_unwind(AssertUnwindSafe(|| map.clone())).is_err());
+        assert_eq!(Tracked::live(), 200);
+    }
+    Tracked::panic_after(0);
+    let cloned = map.clone();
+    assert_eq!(Tracked::live(), 400);
+    assert!(cloned == map);
+    drop(cloned);
+    drop(map);
+    assert_eq!(Tracked::live(), 0);
+}
+
+#[test]
+fn test_avl_clone_from_panic_safe() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let source: OrdMap<_, _> = (0..100).map(|i| (Tracked::new(i), Tracked::new(-i))).collect();
+    let mut map: OrdMap<_, _> = (0..10).map(|i| (Tracked::new(i), Tracked::new(i))).collect();
+    for n in &[1, 2, 51, 150, 200] {
+        Tracked::panic_after(*n);
+        assert!(catch_unwind(AssertUnwindSafe(|| map.clone_from(&source))).is_err());
+        Tracked::panic_after(0);
+        assert_eq!(Tracked::live(), 200);
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+        assert!(map.check_balanced());
+        map.insert(Tracked::new(1), Tracked::new(1));
+        assert_eq!(map.iter().next().map(|(k, _)| k.0), Some(1));
+    }
+    map.clone_from(&source);
+    assert!(map == source);
+    drop(map);
+    drop(source);
+    assert_eq!(Tracked::live(), 0);
+}
+
+#[test]
+fn test_avl_append_panic_safe() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    for n in &[3, 10, 30, 60] {
+        let mut a: OrdMap<_, _> = (0..100)
+            .filter(|i| i % 2 == 0)
+            .map(|i| (Tracked::new(i), Tracked::new(i)))
+            .collect();
+        let mut b: OrdMap<_, _> = (50..150).map(|i| (Tracked::new(i), Tracked::new(-i))).collect();
+        assert_eq!(Tracked::live(), 300);
+        Tracked::panic_after(*n);
+        assert!(catch_unwind(AssertUnwindSafe(|| a.append(&mut b))).is_err());
+        Tracked::panic_after(0);
+        assert_eq!(a.len(), a.iter().count());
+        assert_eq!(b.len(), b.iter().count());
+        drop(a);
+        drop(b);
+        assert_eq!(Tracked::live(), 0);
+    }
+}
+
+#[test]
+fn test_avl_split_off_panic_safe() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let mut map: OrdMap<_, _> = (0..100).map(|i| (Tracked::new(i), Tracked::new(-i))).collect();
+    let key = Tracked::new(37);
+    for n in &[1, 3, 5] {
+        Tracked::panic_after(*n);
+        assert!(catch_unwind(AssertUnwindSafe(|| map.split_off(&key))).is_err());
+        Tracked::panic_after(0);
+        assert_eq!(map.len(), 100);
+        assert!(map.check_balanced());
+        assert!(map.keys().map(|k| k.0).eq(0..100));
+        assert_eq!(map.iter().next_back().map(|(k, _)| k.0), Some(99));
+    }
+    let other = map.split_off(&key);
+    assert_eq!(map.len(), 37);
+    assert_eq!(other.len(), 63);
+    drop(key);
+    drop(other);
+    drop(map);
+    assert_eq!(Tracked::live(), 0);
+}
+
+thread_local! {
+    static CMP_COUNT: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+#[derive(PartialEq, Eq)]
+struct CountingKey(i32);
+
+impl PartialOrd for CountingKey {
+    fn partial_cmp(&self, other: &CountingKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CountingKey {
+    fn cmp(&self, other: &CountingKey) -> Ordering {
+        CMP_COUNT.with(|cnt| cnt.set(cnt.get() + 1));
+        self.0.cmp(&other.0)
+    }
+}
+
+#[test]
+fn test_avl_entry_single_descent() {
+    let mut map = OrdMap::new();
+    for i in 0..1000 {
+        map.insert(CountingKey(i * 2), i);
+    }
+    let height = map.height() as usize;
+    let cmp_count = || CMP_COUNT.with(|cnt| cnt.replace(0));
+
+    for i in 0..1000 {
+        cmp_count();
+        *map.entry(CountingKey(i * 2 + 1)).or_insert(-i) += 1;
+        assert!(cmp_count() <= height);
+    }
+    assert_eq!(map.len(), 2000);
+    assert!(map.check_balanced());
+
+    let height = map.height() as usize;
+    for i in 0..2000 {
+        cmp_count();
+        match map.entry(CountingKey(i)) {
+            Occupied(o) => assert_eq!((o.get_key_value().0).0, i),
+            Vacant(_) => unreachable!(),
+        }
+        assert!(cmp_count() <= height);
+    }
+}
+
+#[test]
+fn test_avl_append_keep_existing() {
+    let mut a: OrdMap<_, _> = (0..100).filter(|i| i % 2 == 0).map(|i| (i, "a")).collect();
+    let mut b: OrdMap<_, _> = (50..150).map(|i| (i, "b")).collect();
+    a.append_keep_existing(&mut b);
+    assert!(b.is_empty());
+    assert_eq!(a.len(), 25 + 100);
+    assert!(a.check_balanced());
+    assert!(a.check_ord_valid());
+    for (k, v) in a.iter() {
+        let expected = if *k < 100 && k % 2 == 0 { "a" } else { "b" };
+        assert_eq!(*v, expected);
+    }
+
+    let cnt = RefCell::new(0);
+    let mut a: OrdMap<_, _> = (0..10).map(|i| (i, Node { b: &cnt })).collect();
+    let mut b: OrdMap<_, _> = (5..15).map(|i| (i, Node { b: &cnt })).collect();
+    a.append_keep_existing(&mut b);
+    assert_eq!(*cnt.borrow(), 5);
+    assert_eq!(a.len(), 15);
+}
+
+#[test]
+fn test_avl_exact_size_iterators() {
+    let mut map: OrdMap<i32, i32> = (0..10).map(|i| (i, -i)).collect();
+
+    let mut iter = map.iter();
+    iter.next();
+    iter.next_back();
+    assert_eq!(iter.len(), 8);
+    let rest: Vec<_> = iter.rev().map(|(k, _)| *k).collect();
+    assert_eq!(rest, (1..9).rev().collect::<Vec<_>>());
+
+    let mut keys = map.keys();
+    keys.next();
+    assert_eq!(keys.len(), 9);
+    assert_eq!(keys.rev().next(), Some(&9));
+
+    let mut values = map.values();
+    values.next_back();
+    assert_eq!(values.len(), 9);
+    assert_eq!(values.rev().next(), Some(&-8));
+
+    {
+        let mut values_mut = map.values_mut();
+        values_mut.next();
+        assert_eq!(values_mut.len(), 9);
+        for v in values_mut.rev() {
+            *v *= 10;
+        }
+    }
+    assert_eq!(map[&0], 0);
+    assert_eq!(map[&9], -90);
+
+    let mut iter_mut = map.iter_mut();
+    for _ in 0..10 {
+        iter_mut.next();
+    }
+    assert_eq!(iter_mut.len(), 0);
+    assert!(iter_mut.next().is_none());
+    assert!(iter_mut.next().is_none());
+    assert!(iter_mut.rev().next().is_none());
+}
+
+#[test]
+fn test_avl_first_last_key_value_mut() {
+    let mut map = OrdMap::new();
+    assert!(map.first_key_value_mut().is_none());
+    assert!(map.last_key_value_mut().is_none());
+    for x in default_make_avl_element(100) {
+        map.insert(x, x);
+    }
+    {
+        let (k, v) = map.first_key_value_mut().unwrap();
+        assert_eq!(*k, 0);
+        *v = -100;
+    }
+    *map.last_key_value_mut().unwrap().1 += 1000;
+    assert_eq!(map.first_key_value(), Some((&0, &-100)));
+    assert_eq!(map.last_key_value(), Some((&99, &1099)));
+    assert_eq!(map[&0], -100);
+}
+
+#[test]
+fn test_avl_into_iter_ref_mut() {
+    let mut map: OrdMap<i32, i32> = (0..100).map(|i| (i, i * 2)).collect();
+    for (_, v) in &mut map {
+        *v += 1;
+    }
+    for (k, v) in &map {
+        assert_eq!(*v, k * 2 + 1);
+    }
+    let last: Vec<_> = (&map).into_iter().rev().take(2).map(|(k, _)| *k).collect();
+    assert_eq!(last, vec![99, 98]);
+    for (_, v) in (&mut map).into_iter().rev().take(1) {
+        *v = 0;
+    }
+    assert_eq!(map[&99], 0);
+}
+
+#[test]
+fn test_avl_clone_balanced() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let mut map = OrdMap::new();
+    for i in 0..1000 {
+        map.insert(i, -i);
+    }
+    for i in 0..500 {
+        map.remove(&i);
+    }
+    let clone = map.clone_balanced();
+    assert!(clone == map);
+    assert!(clone.check_balanced());
+    assert_eq!(clone.len(), map.len());
+    assert_eq!(clone.height(), 9);
+    assert!(clone.height() <= map.height());
+    assert!(OrdMap::<i32, i32>::new().clone_balanced().is_empty());
+
+    let map: OrdMap<_, _> = (0..100).map(|i| (Tracked::new(i), Tracked::new(-i))).collect();
+    for n in &[1, 2, 51, 200] {
+        Tracked::panic_after(*n);
+        assert!(catch_unwind(AssertUnwindSafe(|| map.clone_balanced())).is_err());
+        assert_eq!(Tracked::live(), 200);
+    }
+    Tracked::panic_after(0);
+    let clone = map.clone_balanced();
+    assert!(clone == map);
+    drop(clone);
+    drop(map);
+    assert_eq!(Tracked::live(), 0);
+}
+
+#[test]
+fn test_avl_retain() {
+    let cnt = RefCell::new(0);
+    {
+        let mut map = OrdMap::new();
+        for i in 0..100 {
+            map.insert(i, (i, Node { b: &cnt }));
+        }
+        map.retain(|&k, v| {
+            v.0 *= 2;
+            k % 3 == 0
+        });
+        assert_eq!(*cnt.borrow(), 66);
+        assert_eq!(map.len(), 34);
+        assert!(map.check_balanced());
+        let mut expected = (0..100).filter(|i| i % 3 == 0);
+        for (k, v) in map.iter() {
+            assert_eq!(Some(*k), expected.next());
+            assert_eq!(v.0, k * 2);
+        }
+        assert_eq!(expected.next(), None);
+        map.retain(|_, _| false);
+        assert!(map.is_empty());
+        assert_eq!(*cnt.borrow(), 100);
+    }
+    assert_eq!(*cnt.borrow(), 100);
+}
+
+#[test]
+fn test_avl_range_bound_combinations() {
+    use std::collections::BTreeMap;
+
+    let map: OrdMap<_, _> = (0..20).map(|i| (i * 2, i)).collect();
+    let btree_map: BTreeMap<_, _> = (0..20).map(|i| (i * 2, i)).collect();
+    let bound = |kind: usize, k: i32| match kind {
+        0 => Included(k),
+        1 => Excluded(k),
+        _ => Unbounded,
+    };
+    for lo in -1..42 {
+        for hi in lo..42 {
+            for lo_kind in 0..3 {
+                for hi_kind in 0..3 {
+                    if lo == hi && (lo_kind == 1 || hi_kind == 1) {
+                        continue;
+                    }
+                    let r = (bound(lo_kind, lo), bound(hi_kind, hi));
+                    let expected: Vec<_> = btree_map.range(r).collect();
+                    assert_eq!(map.range::<i32, _>(r).collect::<Vec<_>>(), expected);
+                    let mut rev: Vec<_> = map.range::<i32, _>(r).rev().collect();
+                    rev.reverse();
+                    assert_eq!(rev, expected);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_avl_entry_key_or_default() {
+    let mut map: OrdMap<i32, Vec<i32>> = OrdMap::new();
+    assert_eq!(map.entry(1).key(), &1);
+    assert!(map.is_empty());
+    map.entry(1).or_default().push(10);
+    map.entry(1).or_default().push(11);
+    map.entry(2).and_modify(|v| v.push(0)).or_default();
+    assert_eq!(map.entry(1).key(), &1);
+    assert_eq!(map[&1], vec![10, 11]);
+    assert_eq!(map[&2], Vec::<i32>::new());
+    assert_eq!(map.len(), 2);
+    assert!(map.check_balanced());
+}
+
+#[test]
+fn test_avl_remove_borrowed() {
+    let mut map: OrdMap<String, usize> = OrdMap::new();
+    for word in &["delta", "alpha", "charlie", "bravo"] {
+        map.insert(word.to_string(), word.len());
+    }
+    assert_eq!(map.remove("charlie"), Some(("charlie".to_string(), 7)));
+    assert_eq!(map.remove("charlie"), None);
+    assert_eq!(map.remove("echo"), None);
+    assert_eq!(map.len(), 3);
+    assert!(!map.contains_key("charlie"));
+    assert_eq!(
+        map.keys().map(|k| k.as_str()).collect::<Vec<_>>(),
+        ["alpha", "bravo", "delta"]
+    );
+    assert!(map.check_balanced());
+}
+
+#[test]
+fn test_avl_first_last_entry() {
+    let mut empty: OrdMap<i32, i32> = OrdMap::new();
+    assert!(empty.first_entry().is_none());
+    assert!(empty.last_entry().is_none());
+
+    // event queue keyed by time, only due events are popped
+    let mut queue: OrdMap<u32, &str> = OrdMap::new();
+    queue.insert(30, "c");
+    queue.insert(10, "a");
+    queue.insert(20, "b");
+    let now = 25;
+    let mut fired = Vec::new();
+    while let Some(entry) = queue.first_entry() {
+        if *entry.key() > now {
+            break;
+        }
+        fired.push(entry.remove_entry());
+    }
+    assert_eq!(fired, [(10, "a"), (20, "b")]);
+    assert_eq!(queue.first_key_value(), Some((&30, &"c")));
+    assert_eq!(queue.len(), 1);
+
+    queue.insert(40, "d");
+    if let Some(mut entry) = queue.last_entry() {
+        assert_eq!(entry.get_key_value(), (&40, &"d"));
+        *entry.get_mut() = "e";
+    }
+    assert_eq!(queue.last_key_value(), Some((&40, &"e")));
+    assert_eq!(queue.last_entry().map(|entry| entry.remove()), Some("e"));
+    assert_eq!(queue.last_key_value(), Some((&30, &"c")));
+    assert!(queue.check_balanced());
+}
+
+#[test]
+fn test_avl_cursors_remove_current() {
+    let mut t = default_build_avl(100);
+    {
+        let mut cursors = t.find_cursors(&40);
+        for i in 40..43 {
+            assert_eq!(cursors.remove_current(), Some((i, Some(-i))));
+            assert_eq!(*cursors.get().unwrap().0, i + 1);
+            assert_eq!(*cursors.peek_prev().unwrap().0, 39);
+        }
+    }
+    assert_eq!(t.len(), 97);
+    assert!(!t.contains_key(&41));
+    {
+        let mut cursors = t.find_cursors(&99);
+        assert_eq!(cursors.remove_current(), Some((99, Some(-99))));
+        assert!(cursors.get().is_none());
+        assert_eq!(cursors.remove_current(), None);
+    }
+    assert_eq!(t.len(), 96);
+    assert!(t.check_ord_valid());
+    assert!(t.check_balanced());
+}
+
+#[test]
+fn test_avl_range_mut_borrowed_increment() {
+    let mut map: OrdMap<String, i32> = OrdMap::new();
+    for (i, s) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+        map.insert(s.to_string(), i as i32);
+    }
+    for (_, v) in map.range_mut::<str, _>((Included("b"), Excluded("e"))) {
+        *v += 10;
+    }
+    let values: Vec<_> = map.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    assert_eq!(
+        values,
+        [("a", 0), ("b", 11), ("c", 12), ("d", 13), ("e", 4), ("f", 5)]
+    );
+    for (k, v) in map.range_mut::<str, _>((Excluded("d"), Unbounded)).rev() {
+        *v = k.len() as i32;
+    }
+    assert_eq!(map.get("e"), Some(&1));
+    assert_eq!(map.get("f"), Some(&1));
+    assert_eq!(map.get("d"), Some(&13));
+}
+
+#[test]
+fn test_avl_replace() {
+    #[derive(Debug)]
+    struct Tagged(i32, &'static str);
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Tagged) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Tagged {}
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Tagged) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Tagged) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    let mut map = OrdMap::new();
+    for i in 0..10 {
+        assert!(map.replace(Tagged(i, "old"), i).is_none());
+    }
+    let (old_key, old_value) = map.replace(Tagged(5, "new"), 50).unwrap();
+    assert_eq!(old_key.1, "old");
+    assert_eq!(old_value, 5);
+    assert_eq!(map.len(), 10);
+    let tags: Vec<_> = map.iter().map(|(k, v)| (k.0, k.1, *v)).collect();
+    assert_eq!(tags[4], (4, "old", 4));
+    assert_eq!(tags[5], (5, "new", 50));
+    assert_eq!(tags[6], (6, "old", 6));
+    assert!(map.check_balanced());
+}
+
+#[test]
+fn test_avl_get_mut_or_insert_default() {
+    let mut map: OrdMap<i32, Vec<i32>> = OrdMap::new();
+    for i in 0..30 {
+        map.get_mut_or_insert_default(i % 3).push(i);
+    }
+    assert_eq!(map.len(), 3);
+    for (k, v) in map.iter() {
+        assert_eq!(*v, (0..30).filter(|i| i % 3 == *k).collect::<Vec<_>>());
+    }
+    assert!(map.get_mut_or_insert_default(7).is_empty());
+    assert_eq!(map.len(), 4);
+}
+
+#[test]
+fn test_avl_range_keys_values() {
+    let map: OrdMap<_, _> = (0..100).map(|i| (i, -i)).collect();
+    let keys: Vec<_> = map.range_keys(20..40).collect();
+    let expected: Vec<_> = map.range(20..40).map(|(k, _)| k).collect();
+    assert_eq!(keys, expected);
+    let values: Vec<_> = map.range_values((Excluded(&20), Included(&40))).collect();
+    let expected: Vec<_> = map.range((Excluded(&20), Included(&40)))
+        .map(|(_, v)| v)
+        .collect();
+    assert_eq!(values, expected);
+    assert_eq!(values.len(), 20);
+    assert_eq!(map.range_keys(90..).rev().next(), Some(&99));
+    assert_eq!(map.range_values(..10).next_back(), Some(&-9));
+    assert_eq!(map.range_keys(200..).next(), None);
+}
+
+#[test]
+fn test_avl_keys_subset_superset() {
+    let all: OrdMap<_, _> = (0..100).map(|i| (i, i)).collect();
+    let even: OrdMap<_, _> = (0..100).filter(|i| i % 2 == 0).map(|i| (i, -i)).collect();
+    let odd: OrdMap<_, _> = (0..100).filter(|i| i % 2 == 1).map(|i| (i, -i)).collect();
+    let high: OrdMap<_, _> = (100..110).map(|i| (i, i)).collect();
+    let empty = OrdMap::new();
+
+    assert!(even.keys_subset_of(&all));
+    assert!(all.keys_superset_of(&even));
+    assert!(!all.keys_subset_of(&even));
+    assert!(!even.keys_superset_of(&all));
+    assert!(all.keys_subset_of(&all));
+    assert!(all.keys_superset_of(&all));
+
+    assert!(!even.keys_subset_of(&odd));
+    assert!(!odd.keys_subset_of(&even));
+    assert!(!high.keys_subset_of(&all));
+    assert!(!all.keys_superset_of(&high));
+
+    assert!(empty.keys_subset_of(&all));
+    assert!(empty.keys_subset_of(&empty));
+    assert!(all.keys_superset_of(&empty));
+    assert!(!all.keys_subset_of(&empty));
+
+    let mut almost = even.clone();
+    almost.insert(99, 0);
+    assert!(!almost.keys_subset_of(&even));
+    assert!(almost.keys_superset_of(&even));
+}
+
+#[test]
+fn test_avl_merge_join() {
+    use hash_ord::ord_map::MergeSide::{Both, Left, Right};
+
+    let evens: OrdMap<_, _> = (0..10).filter(|i| i % 2 == 0).map(|i| (i, i)).collect();
+    let odds: OrdMap<_, _> = (0..10).filter(|i| i % 2 == 1).map(|i| (i, -i)).collect();
+    let low: OrdMap<_, _> = (0..5).map(|i| (i, i)).collect();
+    let high: OrdMap<_, _> = (3..8).map(|i| (i, -i)).collect();
+    let far: OrdMap<_, _> = (100..102).map(|i| (i, i)).collect();
+    let empty = OrdMap::new();
+
+    // interleaved keys never meet
+    let v: Vec<_> = evens.merge_join(&odds).collect();
+    assert_eq!(v.len(), 10);
+    for (i, &(k, side)) in v.iter().enumerate() {
+        assert_eq!(*k, i as i32);
+        if i % 2 == 0 {
+            assert_eq!(side, Left(k));
+        } else {
+            assert_eq!(side, Right(&-k));
+        }
+    }
+
+    // overlapping ranges
+    let v: Vec<_> = low.merge_join(&high).map(|(k, side)| (*k, side)).collect();
+    assert_eq!(
+        v,
+        vec![
+            (0, Left(&0)),
+            (1, Left(&1)),
+            (2, Left(&2)),
+            (3, Both(&3, &-3)),
+            (4, Both(&4, &-4)),
+            (5, Right(&-5)),
+            (6, Right(&-6)),
+            (7, Right(&-7)),
+        ]
+    );
+    assert_eq!(low.merge_join(&high).size_hint(), (5, Some(10)));
+
+    // disjoint ranges, in both orders
+    let keys: Vec<_> = far.merge_join(&low).map(|(k, _)| *k).collect();
+    assert_eq!(keys, [0, 1, 2, 3, 4, 100, 101]);
+    assert!(far.merge_join(&low).take(5).all(|(k, side)| side == Right(k)));
+    assert_eq!(low.merge_join(&low).count(), 5);
+    assert!(low.merge_join(&low).all(|(k, side)| side == Both(k, k)));
+    assert_eq!(low.merge_join(&empty).count(), 5);
+    assert_eq!(empty.merge_join(&empty).next(), None);
+}
+
+#[test]
+fn test_avl_into_vec() {
+    let map = default_build_avl(1000);
+    // `into_iter` tears the tree down in post-order
+    let mut expected: Vec<_> = map.clone().into_iter().collect();
+    expected.sort_by_key(|&(k, _)| k);
+    let v = map.into_vec();
+    assert_eq!(v, expected);
+    assert_eq!(v.len(), 1000);
+    assert!(v.windows(2).all(|w| w[0].0 < w[1].0));
+    assert!(DefaultType::new().into_vec().is_empty());
+
+    let cnt = RefCell::new(0);
+    {
+        let map: OrdMap<_, _> = (0..100).map(|i| (i, Node { b: &cnt })).collect();
+        let v = map.into_vec();
+        assert_eq!(*cnt.borrow(), 0);
+        assert_eq!(v.len(), 100);
+    }
+    assert_eq!(*cnt.borrow(), 100);
+}
+
+#[test]
+fn test_avl_extend_reserve() {
+    let n = 100_000;
+    let mut reserved: OrdMap<i32, i32> = OrdMap::new();
+    reserved.reserve(n);
+    let usage = reserved.memory_usage();
+
+    let mut map = OrdMap::new();
+    map.extend((0..n as i32).map(|i| (i, i)));
+    assert_eq!(map.len(), n);
+    // all pages were allocated by the up-front reservation
+    assert_eq!(map.memory_usage(), usage);
+
+    let collected: OrdMap<_, _> = (0..n as i32).map(|i| (i, i)).collect();
+    assert_eq!(collected.memory_usage(), usage);
+    assert!(collected == map);
+}
+
+#[test]
+fn test_avl_into_reversed() {
+    use std::cmp::Reverse;
+
+    let n = 1000;
+    let map: OrdMap<_, _> = (0..n).map(|i| (i, -i)).collect();
+    let reversed = map.into_reversed();
+    assert_eq!(reversed.len(), n as usize);
+    assert!(reversed.check_balanced());
+    let pairs: Vec<_> = reversed.iter().map(|(k, v)| (k.0, *v)).collect();
+    assert_eq!(pairs, (0..n).rev().map(|i| (i, -i)).collect::<Vec<_>>());
+    assert_eq!(reversed.get(&Reverse(42)), Some(&-42));
+    assert_eq!(reversed.range(Reverse(5)..Reverse(2)).count(), 3);
+    assert!(OrdMap::<i32, i32>::new().into_reversed().is_empty());
+}
+
+#[test]
+fn test_avl_count_range() {
+    let map: OrdMap<_, _> = (0..100).map(|i| (i * 2, i)).collect();
+    let bounds = [-10, -1, 0, 1, 50, 51, 198, 199, 250];
+    for &lo in bounds.iter() {
+        for &hi in bounds.iter().filter(|&&hi| hi >= lo) {
+            assert_eq!(map.count_range(lo..=hi), map.range(lo..=hi).count());
+            assert_eq!(map.count_range(lo..hi), map.range(lo..hi).count());
+            assert_eq!(
+                map.count_range((Excluded(&lo), Included(&hi))),
+                map.range((Excluded(&lo), Included(&hi))).count()
+            );
+        }
+        assert_eq!(map.count_range(lo..), map.range(lo..).count());
+        assert_eq!(map.count_range(..lo), map.range(..lo).count());
+    }
+    assert_eq!(map.count_range(..), 100);
+    assert_eq!(map.count_range(10..20), 5);
+    assert_eq!(OrdMap::<i32, i32>::new().count_range(..), 0);
+}
+
+#[test]
+fn test_avl_append_reuses_pages() {
+    let (alloc_cnt, dealloc_cnt) = (Cell::new(0), Cell::new(0));
+    let allocator = CountingAlloc::interchangeable(&alloc_cnt, &dealloc_cnt);
+    let mut map = OrdMap::new_in(allocator.clone());
+    map.insert(-1, -1);
+    let rounds = 50;
+    let batch = 200;
+    for round in 0..rounds {
+        let mut other = OrdMap::new_in(allocator.clone());
+        // alternate between disjoint and overlapping key ranges
+        let start = if round % 2 == 0 { round * batch } else { 0 };
+        for i in start..start + batch {
+            other.insert(i, round);
+        }
+        let allocated = alloc_cnt.get();
+        let usage = map.memory_usage() + other.memory_usage();
+        map.append(&mut other);
+        assert_eq!(alloc_cnt.get(), allocated);
+        assert_eq!(dealloc_cnt.get(), 0);
+        assert_eq!(other.memory_usage(), 0);
+        assert_eq!(map.memory_usage(), usage);
+        assert!(other.is_empty());
+        assert!(map.check_balanced());
+        assert_eq!(map[&start], round);
+    }
+    assert_eq!(map.len(), 1 + (rounds as usize / 2) * batch as usize);
+    drop(map);
+    assert_eq!(alloc_cnt.get(), dealloc_cnt.get());
+}
+
+#[test]
+fn test_avl_append_distinct_allocators() {
+    // two allocators which do not know of each other, so every page must go back to the one it
+    // came from
+    let counters = [
+        (Cell::new(0), Cell::new(0)),
+        (Cell::new(0), Cell::new(0)),
+    ];
+    let allocators: Vec<_> = counters
+        .iter()
+        .map(|(alloc_cnt, dealloc_cnt)| CountingAlloc::new(alloc_cnt, dealloc_cnt))
+        .collect();
+    for &(start, end) in &[(100, 200), (-100, 0), (50, 150)] {
+        let mut map = OrdMap::new_in(allocators[0].clone());
+        let mut other = OrdMap::new_in(allocators[1].clone());
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        for i in start..end {
+            other.insert(i, -i);
+        }
+        map.append(&mut other);
+        assert!(other.is_empty());
+        assert!(map.check_balanced());
+        assert_eq!(map[&(end - 1)], 1 - end);
+        drop(map);
+        assert_eq!(counters[0].0.get(), counters[0].1.get());
+        drop(other);
+        assert_eq!(counters[1].0.get(), counters[1].1.get());
+    }
+
+    let mut map = OrdMap::new_in(allocators[0].clone());
+    map.insert(0, 0);
+    let mut other = OrdMap::new_in(allocators[1].clone());
+    other.insert(0, 1);
+    other.insert(1, 1);
+    map.union_with(other, |_, a, b| a + b);
+    assert_eq!(map[&0], 1);
+    drop(map);
+    for (alloc_cnt, dealloc_cnt) in &counters {
+        assert!(alloc_cnt.get() > 0);
+        assert_eq!(alloc_cnt.get(), dealloc_cnt.get());
+    }
+}
+
+#[test]
+fn test_avl_assert_invariants() {
+    use std::collections::BTreeMap;
+
+    let mut map = OrdMap::new();
+    let mut model = BTreeMap::new();
+    map.assert_invariants();
+    for op in 0..10000 {
+        let key = (rand::random::<usize>() % 1000) as i32;
+        match rand::random::<usize>() % 6 {
+            0 | 1 => assert_eq!(
+                map.insert(key, op).map(|(_, v)| v),
+                model.insert(key, op)
+            ),
+            2 => assert_eq!(map.remove(&key).map(|(_, v)| v), model.remove(&key)),
+            3 => {
+                *map.entry(key).or_insert(0) += 1;
+                *model.entry(key).or_insert(0) += 1;
+            }
+            4 => {
+                let mut other = map.split_off(&key);
+                map.assert_invariants();
+                other.assert_invariants();
+                map.append(&mut other);
+            }
+            _ => {
+                map.retain(|k, _| k % 7 != key % 7);
+                model.retain(|k, _| k % 7 != key % 7);
+            }
+        }
+        if op % 100 == 0 {
+            map.assert_invariants();
+        }
+    }
+    map.assert_invariants();
+    assert!(map.iter().eq(model.iter()));
+}
+
+#[test]
+fn test_avl_partition_point() {
+    let map: OrdMap<_, _> = (0..100).map(|i| (i, -i)).collect();
+    assert_eq!(map.partition_point(|k: &i32| *k < 37), 37);
+    for split in -1..102 {
+        assert_eq!(
+            map.partition_point(|k: &i32| *k < split),
+            map.range(..split).count()
+        );
+    }
+    let strings: OrdMap<_, _> = ["apple", "banana", "cherry", "date"]
+        .iter()
+        .map(|s| (s.to_string(), ()))
+        .collect();
+    assert_eq!(strings.partition_point(|k: &str| k < "c"), 2);
+    assert_eq!(OrdMap::<i32, i32>::new().partition_point(|_: &i32| true), 0);
+}
+
+#[test]
+fn test_avl_scan_mut() {
+    let mut map: OrdMap<_, _> = (0..100).rev().map(|i| (i, i as u64)).collect();
+    let (sum, cnt) = map.scan_mut((0, 0), |state, k, v| {
+        assert_eq!(*k, state.1);
+        state.0 += *v;
+        state.1 += 1;
+        *v = state.0;
+    });
+    assert_eq!(sum, 4950);
+    assert_eq!(cnt, 100);
+    for (k, v) in map.iter() {
+        assert_eq!(*v, (0..=*k as u64).sum::<u64>());
+    }
+
+    let mut empty: OrdMap<i32, i32> = OrdMap::new();
+    assert_eq!(empty.scan_mut(7, |_, _, _| panic!()), 7);
+}
+
+#[test]
+fn test_avl_union_with() {
+    let mut a: OrdMap<_, _> = (0..100).map(|i| (i * 2, 1)).collect();
+    let b: OrdMap<_, _> = (0..100).map(|i| (i * 3, 10)).collect();
+    a.union_with(b, |_, x, y| x + y);
+    assert!(a.check_balanced());
+    for k in 0..300 {
+        let expected = match (k % 2 == 0 && k < 200, k % 3 == 0) {
+            (true, true) => Some(11),
+            (true, false) => Some(1),
+            (false, true) => Some(10),
+            (false, false) => None,
+        };
+        assert_eq!(a.get(&k).cloned(), expected);
+    }
+
+    // the combiner sees the key, and disjoint or empty maps skip it
+    let mut a: OrdMap<_, _> = (0..10).map(|i| (i, vec![i])).collect();
+    a.union_with((5..15).map(|i| (i, vec![-i])).collect(), |k, mut x, y| {
+        assert!(*k >= 5 && *k < 10);
+        x.extend(y);
+        x
+    });
+    assert_eq!(a[&7], [7, -7]);
+    assert_eq!(a[&12], [-12]);
+    a.union_with((20..30).map(|i| (i, vec![])).collect(), |_, _, _| panic!());
+    a.union_with(OrdMap::new(), |_, _, _| panic!());
+    assert_eq!(a.len(), 25);
+
+    // a panicking combiner leaks nothing
+    let cnt = RefCell::new(0);
+    {
+        let mut a: OrdMap<_, _> = (0..10).map(|i| (i, Node { b: &cnt })).collect();
+        let b: OrdMap<_, _> = (0..10).map(|i| (i, Node { b: &cnt })).collect();
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            a.union_with(b, |k, x, y| {
+                if *k == 5 {
+                    panic!();
+                }
+                drop(y);
+                x
+            })
+        }));
+        assert!(res.is_err());
+    }
+    assert_eq!(*cnt.borrow(), 20);
+}
+
+#[test]
+fn test_avl_bulk_insert() {
+    use std::collections::BTreeMap;
+
+    let n = 50000;
+    let mut map = OrdMap::new();
+    let mut model = BTreeMap::new();
+    for i in 0..n {
+        let k = rand::random::<i32>() % (n * 2);
+        map.insert(k, i);
+        model.insert(k, i);
+    }
+    let pairs: Vec<_> = (0..n).map(|i| (rand::random::<i32>() % (n * 2), -i)).collect();
+    for &(k, v) in pairs.iter() {
+        model.insert(k, v);
+    }
+    map.bulk_insert(pairs);
+    assert!(map.check_balanced());
+    assert!(map.check_ord_valid());
+    assert_eq!(map.len(), model.len());
+    assert!(map.iter().eq(model.iter()));
+
+    let mut empty = OrdMap::new();
+    empty.bulk_insert(vec![(2, 'b'), (1, 'a'), (2, 'c')]);
+    assert_eq!(empty.into_vec(), [(1, 'a'), (2, 'c')]);
+
+    let cnt = RefCell::new(0);
+    {
+        let mut map: OrdMap<_, _> = (0..10).map(|i| (i, Node { b: &cnt })).collect();
+        map.bulk_insert((5..15).chain(5..15).map(|i| (i, Node { b: &cnt })));
+        assert_eq!(map.len(), 15);
+        assert_eq!(*cnt.borrow(), 15);
+    }
+    assert_eq!(*cnt.borrow(), 30);
+}
+
+#[test]
+fn test_avl_clone_from_reuses_pages() {
+    let (alloc_cnt, dealloc_cnt) = (Cell::new(0), Cell::new(0));
+    let allocator = CountingAlloc::new(&alloc_cnt, &dealloc_cnt);
+    let mut source = OrdMap::new_in(allocator.clone());
+    for x in default_make_avl_element(1000) {
+        source.insert(x, -x);
+    }
+    let mut target = OrdMap::new_in(allocator);
+
+    target.clone_from(&source);
+    assert!(target == source);
+    assert!(target.check_balanced());
+    let after_first = alloc_cnt.get();
+    for _ in 0..10 {
+        target.clone_from(&source);
+        assert!(target == source);
+    }
+    assert_eq!(alloc_cnt.get(), after_first);
+
+    // a smaller source leaves free cells behind
+    let mut small = OrdMap::new_in(CountingAlloc::new(&alloc_cnt, &dealloc_cnt));
+    for (k, v) in source.range(..0) {
+        small.insert(*k, *v);
+    }
+    let before = alloc_cnt.get();
+    target.clone_from(&small);
+    assert!(target == small);
+    assert_eq!(target.first_key_value(), small.first_key_value());
+    assert_eq!(target.last_key_value(), small.last_key_value());
+    assert_eq!(alloc_cnt.get(), before);
+
+    // a larger source grows the pages
+    for x in 1000..3000 {
+        source.insert(x, -x);
+    }
+    target.clone_from(&source);
+    assert!(target == source);
+    assert!(alloc_cnt.get() > before);
+}
+
+#[test]
+fn test_avl_successor_predecessor() {
+    let mut map = OrdMap::new();
+    for x in default_make_avl_element(100) {
+        map.insert(x, x * 2);
+    }
+    let handle = map.find_ref(&50).unwrap();
+    assert_eq!(map.get_at(handle), (&50, &100));
+    let next = map.successor(handle).unwrap();
+    assert_eq!(map.get_at(next), (&51, &102));
+    let prev = map.predecessor(handle).unwrap();
+    assert_eq!(map.get_at(prev), (&49, &98));
+    assert!(map.predecessor(next) == Some(handle));
+    assert!(map.successor(prev) == Some(handle));
+    assert!(map.find_ref(&100).is_none());
+
+    // stepping from the first handle visits every key in order
+    let mut keys = vec![];
+    let mut cur = map.find_ref(&0);
+    while let Some(h) = cur {
+        keys.push(*map.get_at(h).0);
+        cur = map.successor(h);
+    }
+    assert_eq!(keys, (0..100).collect::<Vec<_>>());
+    let mut cur = map.find_ref(&99);
+    while let Some(h) = cur {
+        keys.push(*map.get_at(h).0);
+        cur = map.predecessor(h);
+    }
+    assert_eq!(keys[100..], (0..100).rev().collect::<Vec<_>>()[..]);
+}
@@ -0,0 +1,100 @@
+extern crate hash_ord;
+extern crate rand;
+
+use hash_ord::ord_set::OrdSet;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+
+fn make_shuffled(n: usize) -> Vec<i32> {
+    let mut v = vec![0i32; n];
+    for idx in 0..v.len() {
+        v[idx] = idx as i32;
+        let pos = rand::random::<usize>() % (idx + 1);
+        v.swap(idx, pos);
+    }
+    v
+}
+
+#[test]
+fn test_ord_set_insert_remove() {
+    let mut set = OrdSet::new();
+    for x in make_shuffled(1000) {
+        assert!(set.insert(x));
+        assert!(!set.insert(x));
+    }
+    assert_eq!(set.len(), 1000);
+    assert!(set.check_balanced());
+    for x in 0..500 {
+        assert!(set.remove(&(x * 2)));
+        assert!(!set.remove(&(x * 2)));
+    }
+    assert_eq!(set.len(), 500);
+    assert!(set.check_balanced());
+    for x in 0..1000 {
+        assert_eq!(set.contains(&x), x % 2 == 1);
+    }
+    assert_eq!(set.take(&1), Some(1));
+    assert_eq!(set.take(&1), None);
+    set.clear();
+    assert!(set.is_empty());
+    assert_eq!(set.first(), None);
+    assert_eq!(set.last(), None);
+}
+
+#[test]
+fn test_ord_set_iter() {
+    let set: OrdSet<_> = make_shuffled(1000).into_iter().collect();
+    assert!(set.iter().cloned().eq(0..1000));
+    assert!(set.iter().rev().cloned().eq((0..1000).rev()));
+    assert!((&set).into_iter().cloned().eq(0..1000));
+    assert_eq!(set.first(), Some(&0));
+    assert_eq!(set.last(), Some(&999));
+    assert!(set.clone() == set);
+    assert!(set.into_iter().eq(0..1000));
+}
+
+#[test]
+fn test_ord_set_range() {
+    let set: OrdSet<_> = make_shuffled(100).into_iter().map(|x| x * 2).collect();
+    assert!(set.range(10..20).cloned().eq((5..10).map(|x| x * 2)));
+    assert!(set.range(11..=21).cloned().eq((6..11).map(|x| x * 2)));
+    assert!(set.range(..).cloned().eq((0..100).map(|x| x * 2)));
+    assert!(set
+        .range((Excluded(10), Unbounded))
+        .rev()
+        .cloned()
+        .eq((6..100).rev().map(|x| x * 2)));
+    assert_eq!(set.range((Included(300), Unbounded)).next(), None);
+
+    let set: OrdSet<String> = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+    let v: Vec<_> = set
+        .range::<str, _>((Included("b"), Excluded("d")))
+        .collect();
+    assert_eq!(v, ["b", "c"]);
+}
+
+#[test]
+fn test_ord_set_intersection() {
+    let a: OrdSet<_> = make_shuffled(100).into_iter().filter(|x| x % 2 == 0).collect();
+    let b: OrdSet<_> = make_shuffled(100).into_iter().filter(|x| x % 3 == 0).collect();
+    assert!(a.intersection(&b).cloned().eq((0..100).filter(|x| x % 6 == 0)));
+    assert!(a
+        .difference(&b)
+        .cloned()
+        .eq((0..100).filter(|x| x % 2 == 0 && x % 3 != 0)));
+    assert!(a
+        .symmetric_difference(&b)
+        .cloned()
+        .eq((0..100).filter(|x| (x % 2 == 0) != (x % 3 == 0))));
+    assert!(a
+        .union(&b)
+        .cloned()
+        .eq((0..100).filter(|x| x % 2 == 0 || x % 3 == 0)));
+
+    let c: OrdSet<_> = (0..100).filter(|x| x % 6 == 0).collect();
+    assert!(c.is_subset(&a));
+    assert!(a.is_superset(&c));
+    assert!(!a.is_subset(&b));
+    let d: OrdSet<_> = (0..100).filter(|x| x % 2 == 1).collect();
+    assert!(a.is_disjoint(&d));
+    assert!(!a.is_disjoint(&b));
+}
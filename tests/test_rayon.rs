@@ -0,0 +1,67 @@
+#![cfg(feature = "rayon")]
+
+extern crate hash_ord;
+extern crate rayon;
+
+use hash_ord::hash_map::HashMap;
+use hash_ord::ord_map::OrdMap;
+use rayon::prelude::*;
+
+#[test]
+fn test_ord_map_par_iter_sum() {
+    let map = OrdMap::from_sorted_iter((0..1_000_000u64).map(|i| (i, i * 3)));
+    let par_sum: u64 = map.par_iter().map(|(_, v)| *v).sum();
+    let seq_sum: u64 = map.values().sum();
+    assert_eq!(par_sum, seq_sum);
+    assert_eq!(map.par_iter().count(), map.len());
+}
+
+#[test]
+fn test_ord_map_par_iter_order() {
+    let mut map = OrdMap::new();
+    for i in (0..10_000).rev() {
+        map.insert(i, -i);
+    }
+    let keys: Vec<i32> = map.par_iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, (0..10_000).collect::<Vec<_>>());
+    let pairs = map.par_iter().collect::<Vec<_>>();
+    assert_eq!(pairs, map.iter().collect::<Vec<_>>());
+
+    let empty: OrdMap<i32, i32> = OrdMap::new();
+    assert_eq!(empty.par_iter().count(), 0);
+}
+
+#[test]
+fn test_hash_map_par_iter_count() {
+    let map: HashMap<u64, u64> = (0..1_000_000u64).map(|i| (i, i * 3)).collect();
+    assert_eq!(map.par_iter().count(), map.len());
+    let par_sum: u64 = map.par_iter().map(|(_, v)| *v).sum();
+    let seq_sum: u64 = map.values().sum();
+    assert_eq!(par_sum, seq_sum);
+    let mut pairs = map.par_iter().collect::<Vec<_>>();
+    pairs.sort();
+    let mut seq_pairs = map.iter().collect::<Vec<_>>();
+    seq_pairs.sort();
+    assert_eq!(pairs, seq_pairs);
+
+    let empty: HashMap<u64, u64> = HashMap::new();
+    assert_eq!(empty.par_iter().count(), 0);
+}
+
+#[test]
+fn test_hash_map_par_values_mut() {
+    let mut par_map: HashMap<u64, u64> = (0..1_000_000u64).map(|i| (i, i * 3)).collect();
+    let mut seq_map = par_map.clone();
+    par_map.par_values_mut().for_each(|v| *v *= 2);
+    for v in seq_map.values_mut() {
+        *v *= 2;
+    }
+    assert_eq!(par_map.len(), seq_map.len());
+    for (k, v) in seq_map.iter() {
+        assert_eq!(par_map.get(k), Some(v));
+    }
+    assert_eq!(par_map.par_values_mut().count(), par_map.len());
+
+    let mut empty: HashMap<u64, u64> = HashMap::new();
+    assert_eq!(empty.par_values_mut().count(), 0);
+}